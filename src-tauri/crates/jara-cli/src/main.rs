@@ -0,0 +1,1043 @@
+use clap::{Args, Parser, Subcommand};
+use jara_core::animation::{AnimationFormat, AnimationRequest};
+use jara_core::concat::ConcatRequest;
+use jara_core::encoding::{BurnSubtitle, ConversionOptions, StreamPlan, SubtitleFile};
+use jara_core::ffmpeg::{ConvertProgress, FFmpeg};
+use jara_core::frames::{ContactSheetOptions, FrameOptions};
+use jara_core::image_convert::ImageOptions;
+use jara_core::loudness::{LoudnessMeasurement, LoudnessOptions, LoudnessRequest};
+use jara_core::media_info::MediaInfo;
+use jara_core::settings::Settings;
+use jara_core::trim::{parse_time, TimeRange, TrimMode, TrimRequest};
+use jara_core::ytdlp::{DownloadProgress, DownloadRequest, VideoInfo};
+use jara_core::{BinaryPaths, EventSink, Services};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+
+// Exit codes. clap itself exits with 2 on invalid arguments
+const EXIT_OK: u8 = 0;
+const EXIT_FAILED: u8 = 1;
+// Some playlist entries failed, the others were downloaded
+const EXIT_PARTIAL: u8 = 3;
+const EXIT_INTERRUPTED: u8 = 130;
+
+#[derive(Parser)]
+#[command(name = "jara-cli", version, about = "Downloads e conversões do Jara, sem interface gráfica")]
+struct Cli {
+    #[arg(long, global = true, help = "Progresso e resultados como JSON, um objeto por linha")]
+    json: bool,
+
+    #[arg(long, global = true, value_name = "PATH", help = "Executável do yt-dlp a usar")]
+    yt_dlp: Option<PathBuf>,
+
+    #[arg(long, global = true, value_name = "PATH", help = "Executável do ffmpeg a usar")]
+    ffmpeg: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Executável do ffprobe a usar (padrão: ao lado do ffmpeg)"
+    )]
+    ffprobe: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Mostra informações e formatos de um vídeo")]
+    Info { url: String },
+
+    #[command(about = "Baixa um vídeo")]
+    Download {
+        url: String,
+        #[command(flatten)]
+        options: DownloadArgs,
+    },
+
+    #[command(about = "Baixa ou lista os vídeos de uma playlist")]
+    Playlist {
+        url: String,
+        #[arg(long, help = "Só lista os vídeos, sem baixar")]
+        list: bool,
+        #[command(flatten)]
+        options: DownloadArgs,
+    },
+
+    #[command(about = "Mostra as faixas, capítulos e metadados de um arquivo de mídia")]
+    Probe { input: String },
+
+    #[command(about = "Converte áudio ou vídeo com o ffmpeg")]
+    ConvertMedia {
+        input: String,
+        format: String,
+        #[command(flatten)]
+        options: EncodingArgs,
+        #[arg(long, help = "Só mostra os argumentos do ffmpeg, sem converter")]
+        dry_run: bool,
+    },
+
+    #[command(about = "Corta trechos de um áudio ou vídeo")]
+    Trim {
+        input: String,
+        #[arg(
+            long,
+            required = true,
+            value_name = "RANGE",
+            help = "Trecho a manter, ex.: 0:30-1:45 ou 1:00:00- (pode repetir)"
+        )]
+        range: Vec<String>,
+        #[arg(long, help = "Recodifica para cortar no quadro exato, em vez de no keyframe anterior")]
+        precise: bool,
+        #[arg(long, help = "Junta os trechos em um só arquivo")]
+        join: bool,
+    },
+
+    #[command(about = "Junta vários áudios ou vídeos em um arquivo, na ordem dada")]
+    Concat {
+        #[arg(required = true, num_args = 2..)]
+        inputs: Vec<String>,
+        #[arg(long, short, help = "Arquivo de saída (padrão: <primeiro>-joined.<ext>)")]
+        output: Option<String>,
+        #[arg(long, help = "Cria um capítulo para cada arquivo")]
+        chapters: bool,
+    },
+
+    #[command(about = "Cria um GIF ou WebP animado a partir de um vídeo")]
+    Animate {
+        input: String,
+        #[arg(long, help = "WebP animado em vez de GIF")]
+        webp: bool,
+        #[arg(long, value_name = "TEMPO", value_parser = time_arg, help = "Início, ex.: 1:30 (padrão: começo do vídeo)")]
+        start: Option<f64>,
+        #[arg(long, value_name = "TEMPO", value_parser = time_arg, help = "Fim (padrão: fim do vídeo)")]
+        end: Option<f64>,
+        #[arg(long, help = "Quadros por segundo (padrão: 15)")]
+        fps: Option<f64>,
+        #[arg(long, help = "Largura em pixels (padrão: 480)")]
+        width: Option<u32>,
+        #[arg(long, help = "Quantas vezes a animação toca (padrão: 0, sem parar)")]
+        loops: Option<u32>,
+        #[arg(long, value_name = "KIB", help = "Reduz fps e largura até o arquivo caber nesse tamanho")]
+        max_size_kb: Option<u64>,
+    },
+
+    #[command(about = "Salva um quadro de um vídeo como imagem")]
+    Frame {
+        input: String,
+        #[arg(long, value_name = "TEMPO", value_parser = time_arg, help = "Momento do quadro, ex.: 1:30")]
+        at: f64,
+        #[command(flatten)]
+        options: FrameArgs,
+    },
+
+    #[command(about = "Salva quadros espalhados igualmente pelo vídeo")]
+    Frames {
+        input: String,
+        #[arg(long, default_value_t = 8, help = "Quantos quadros")]
+        count: u32,
+        #[command(flatten)]
+        options: FrameArgs,
+    },
+
+    #[command(about = "Monta uma folha de contato com quadros do vídeo")]
+    ContactSheet {
+        input: String,
+        #[arg(long, help = "Colunas (padrão: 4)")]
+        columns: Option<u32>,
+        #[arg(long, help = "Linhas (padrão: 4)")]
+        rows: Option<u32>,
+        #[arg(long, help = "Largura de cada quadro em pixels (padrão: 320)")]
+        tile_width: Option<u32>,
+        #[arg(long, help = "Não escreve o tempo de cada quadro")]
+        no_timestamps: bool,
+        #[arg(long, help = "jpg, png ou webp (padrão: jpg)")]
+        format: Option<String>,
+    },
+
+    #[command(about = "Normaliza o volume (EBU R128) de um ou mais arquivos")]
+    Normalize {
+        #[arg(required = true)]
+        inputs: Vec<String>,
+        #[arg(long, help = "podcast (-16 LUFS), streaming (-14 LUFS, padrão) ou broadcast (-23 LUFS)")]
+        preset: Option<String>,
+        #[arg(long, allow_negative_numbers = true, value_name = "LUFS", help = "Volume integrado alvo")]
+        integrated: Option<f64>,
+        #[arg(long, allow_negative_numbers = true, value_name = "DBTP", help = "Pico máximo")]
+        true_peak: Option<f64>,
+        #[arg(long, value_name = "LU", help = "Faixa de volume (LRA) alvo")]
+        lra: Option<f64>,
+    },
+
+    #[command(about = "Converte uma imagem para outro formato")]
+    ConvertImage {
+        input: String,
+        format: String,
+        #[arg(long, help = "Redimensiona para caber em NxN pixels")]
+        max_size: Option<u32>,
+        #[arg(long, help = "Recorta um quadrado central")]
+        square: bool,
+    },
+
+    #[command(about = "Cria um PDF a partir de uma imagem")]
+    ImageToPdf { input: String },
+}
+
+#[derive(Args)]
+struct DownloadArgs {
+    #[arg(long, help = "Preset salvo no Jara (padrão: o preset padrão das configurações)")]
+    preset: Option<String>,
+    #[arg(long, help = "ID de formato do yt-dlp")]
+    format: Option<String>,
+    #[arg(long, help = "Só o áudio, em mp3")]
+    audio: bool,
+    #[arg(long, short, help = "Pasta de destino (padrão: a das configurações)")]
+    output: Option<String>,
+    #[arg(long, value_name = "LANG", help = "Baixa e embute legendas, ex.: pt,en")]
+    subs: Option<String>,
+    #[arg(long, value_name = "RANGE", help = "Trecho a baixar, ex.: \"*0:00-1:30\" (pode repetir)")]
+    section: Vec<String>,
+    #[arg(long, value_name = "FILE", help = "Arquivo de histórico; vídeos já listados nele são pulados")]
+    archive: Option<String>,
+}
+
+impl DownloadArgs {
+    fn to_request(&self, url: &str, settings: &Settings) -> Result<DownloadRequest, String> {
+        let preset = settings.find_preset(self.preset.as_deref())?;
+        let output_path = self.output.clone().unwrap_or_else(|| settings.output_path());
+
+        let mut request = preset.to_request(url, &output_path);
+        if self.format.is_some() {
+            request.format_id = self.format.clone();
+        }
+        if self.audio {
+            request.audio_only = true;
+        }
+        if let Some(lang) = &self.subs {
+            request.download_subs = true;
+            request.sub_lang = Some(lang.clone());
+        }
+        request.sections = self.section.clone();
+        request.download_archive = self.archive.clone();
+        Ok(request)
+    }
+}
+
+#[derive(Args)]
+struct EncodingArgs {
+    #[arg(long, help = "Preset de conversão salvo no Jara; as outras opções o sobrescrevem")]
+    preset: Option<String>,
+    #[arg(long, value_name = "CODEC", help = "h264, hevc, vp9, av1 ou copy")]
+    video_codec: Option<String>,
+    #[arg(long, help = "Qualidade constante; menor é melhor")]
+    crf: Option<u32>,
+    #[arg(long, value_name = "KBPS", help = "Bitrate de vídeo em kbit/s")]
+    video_bitrate: Option<u32>,
+    #[arg(long, value_name = "PRESET", help = "Velocidade do x264/x265, ex.: fast, slow")]
+    encoder_preset: Option<String>,
+    #[arg(long, help = "Largura em pixels; sozinha mantém a proporção")]
+    width: Option<u32>,
+    #[arg(long, help = "Altura em pixels; sozinha mantém a proporção")]
+    height: Option<u32>,
+    #[arg(long, help = "Quadros por segundo")]
+    fps: Option<f64>,
+    #[arg(long, value_name = "CODEC", help = "aac, mp3, opus, vorbis, flac, pcm ou copy")]
+    audio_codec: Option<String>,
+    #[arg(long, value_name = "KBPS", help = "Bitrate de áudio em kbit/s")]
+    audio_bitrate: Option<u32>,
+    #[arg(long, value_name = "HZ", help = "Taxa de amostragem, ex.: 48000")]
+    sample_rate: Option<u32>,
+    #[arg(long, help = "Número de canais de áudio")]
+    channels: Option<u32>,
+    #[arg(
+        long = "subtitle",
+        value_name = "ARQUIVO",
+        help = "Legenda .srt, .vtt ou .ass a incluir como faixa (pode repetir)"
+    )]
+    subtitles: Vec<String>,
+    #[arg(long = "subtitle-lang", value_name = "IDIOMA", help = "Idioma de cada --subtitle, na mesma ordem, ex.: por")]
+    subtitle_langs: Vec<String>,
+    #[arg(
+        long,
+        value_name = "FAIXA|ARQUIVO",
+        help = "Queima na imagem uma legenda: o # de uma faixa mostrado por probe ou um arquivo"
+    )]
+    burn_subtitle: Option<String>,
+    #[arg(long, value_name = "FONTE", help = "Fonte da legenda queimada")]
+    subtitle_font: Option<String>,
+    #[arg(long, value_name = "TAMANHO", help = "Tamanho da legenda queimada")]
+    subtitle_size: Option<u32>,
+    #[arg(long, value_name = "#RRGGBB", help = "Cor da legenda queimada")]
+    subtitle_color: Option<String>,
+    #[arg(long, value_name = "PIXELS", help = "Contorno da legenda queimada")]
+    subtitle_outline: Option<u32>,
+    #[arg(long, help = "Legenda queimada em negrito")]
+    subtitle_bold: bool,
+}
+
+impl EncodingArgs {
+    fn to_options(&self, settings: &Settings) -> Result<ConversionOptions, String> {
+        let mut options = match &self.preset {
+            Some(name) => settings.find_conversion_preset(name)?.options,
+            None => ConversionOptions::default(),
+        };
+
+        let text = |value: &Option<String>, current: Option<String>| value.clone().or(current);
+        options.video_codec = text(&self.video_codec, options.video_codec);
+        options.encoder_preset = text(&self.encoder_preset, options.encoder_preset);
+        options.audio_codec = text(&self.audio_codec, options.audio_codec);
+        // A CRF on the command line replaces a preset's bitrate and vice versa
+        if self.crf.is_some() || self.video_bitrate.is_some() {
+            options.crf = self.crf;
+            options.video_bitrate = self.video_bitrate;
+        }
+        options.width = self.width.or(options.width);
+        options.height = self.height.or(options.height);
+        options.fps = self.fps.or(options.fps);
+        options.audio_bitrate = self.audio_bitrate.or(options.audio_bitrate);
+        options.sample_rate = self.sample_rate.or(options.sample_rate);
+        options.channels = self.channels.or(options.channels);
+
+        if self.subtitle_langs.len() > self.subtitles.len() {
+            return Err("Há mais --subtitle-lang do que --subtitle".to_string());
+        }
+        if !self.subtitles.is_empty() {
+            options.subtitle_files = self
+                .subtitles
+                .iter()
+                .enumerate()
+                .map(|(i, path)| SubtitleFile {
+                    path: path.clone(),
+                    language: self.subtitle_langs.get(i).cloned(),
+                    title: None,
+                })
+                .collect();
+        }
+        if let Some(track) = &self.burn_subtitle {
+            // A number is a track of the input, anything else a file
+            let (stream, file) = match track.parse() {
+                Ok(index) => (Some(index), None),
+                Err(_) => (None, Some(track.clone())),
+            };
+            options.burn_subtitle = Some(BurnSubtitle {
+                stream,
+                file,
+                ..BurnSubtitle::default()
+            });
+        }
+        let styled = self.subtitle_font.is_some()
+            || self.subtitle_size.is_some()
+            || self.subtitle_color.is_some()
+            || self.subtitle_outline.is_some()
+            || self.subtitle_bold;
+        match options.burn_subtitle.as_mut() {
+            Some(burn) => {
+                burn.font = text(&self.subtitle_font, burn.font.take());
+                burn.font_size = self.subtitle_size.or(burn.font_size);
+                burn.color = text(&self.subtitle_color, burn.color.take());
+                burn.outline = self.subtitle_outline.or(burn.outline);
+                burn.bold |= self.subtitle_bold;
+            }
+            None if styled => return Err("O estilo da legenda só vale com --burn-subtitle".to_string()),
+            None => {}
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Args)]
+struct FrameArgs {
+    #[arg(long, help = "jpg, png ou webp (padrão: jpg)")]
+    format: Option<String>,
+    #[arg(long, help = "Largura em pixels (padrão: a do vídeo)")]
+    width: Option<u32>,
+}
+
+impl FrameArgs {
+    fn to_options(&self) -> FrameOptions {
+        FrameOptions {
+            format: self.format.clone(),
+            width: self.width,
+        }
+    }
+}
+
+// Writes everything that goes to stdout, either as text or as JSON lines
+#[derive(Clone)]
+struct Reporter {
+    json: bool,
+    labels: Arc<Mutex<HashMap<String, String>>>,
+    // Last (status, step) printed per download, so text output stays readable
+    last: Arc<Mutex<HashMap<String, (String, i64)>>>,
+}
+
+impl Reporter {
+    fn new(json: bool) -> Self {
+        Self {
+            json,
+            labels: Arc::new(Mutex::new(HashMap::new())),
+            last: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn emit<T: Serialize>(&self, kind: &str, value: &T) {
+        let mut value = serde_json::to_value(value).unwrap_or(Value::Null);
+        match value.as_object_mut() {
+            Some(object) => {
+                object.insert("type".to_string(), json!(kind));
+            }
+            None => value = json!({ "type": kind, "value": value }),
+        }
+        println!("{}", value);
+    }
+
+    fn label(&self, download_id: &str) -> String {
+        self.labels
+            .lock()
+            .unwrap()
+            .get(download_id)
+            .cloned()
+            .unwrap_or_else(|| download_id.to_string())
+    }
+
+    fn progress(&self, progress: &DownloadProgress) {
+        if self.json {
+            self.emit("progress", progress);
+            return;
+        }
+
+        // Text mode prints status changes and every 10% (or 30s of a live recording)
+        let step = match progress.status.as_str() {
+            "recording" => progress.elapsed.unwrap_or(0.0) as i64 / 30,
+            _ => progress.progress as i64 / 10,
+        };
+        let key = (progress.status.clone(), step);
+        {
+            let mut last = self.last.lock().unwrap();
+            if last.get(&progress.download_id) == Some(&key) {
+                return;
+            }
+            last.insert(progress.download_id.clone(), key);
+        }
+
+        let label = self.label(&progress.download_id);
+        match progress.status.as_str() {
+            // Failures are reported once the job finishes
+            "queued" | "error" | "cancelled" => {}
+            "completed" => {
+                println!("{}: concluído", label);
+                for file in &progress.files {
+                    println!("  {} ({})", file.path, format_size(file.size));
+                }
+            }
+            "waiting" => println!("{}: aguardando o início da transmissão", label),
+            "recording" => println!(
+                "{}: gravando, {} em {:.0}s",
+                label,
+                format_size(progress.downloaded_bytes.unwrap_or(0)),
+                progress.elapsed.unwrap_or(0.0)
+            ),
+            status => println!(
+                "{}: {} {:.1}% {} ETA {}",
+                label,
+                status,
+                progress.progress,
+                progress.speed.as_deref().unwrap_or("-"),
+                progress.eta.as_deref().unwrap_or("-")
+            ),
+        }
+    }
+
+    fn output(&self, path: &str) {
+        if self.json {
+            self.emit("output", &json!({ "path": path }));
+        } else {
+            println!("{}", path);
+        }
+    }
+
+    fn error(&self, message: &str) {
+        if self.json {
+            self.emit("error", &json!({ "message": message }));
+        } else {
+            eprintln!("Erro: {}", message);
+        }
+    }
+}
+
+impl EventSink for Reporter {
+    fn download_progress(&self, progress: DownloadProgress) {
+        self.progress(&progress);
+    }
+
+    fn convert_progress(&self, progress: ConvertProgress) {
+        if self.json {
+            self.emit("convert_progress", &progress);
+            return;
+        }
+        // The output path is printed once the conversion returns
+        if progress.status != "converting" {
+            if !progress.streams.is_empty() {
+                println!("{}", describe_streams(&progress.streams));
+            }
+            return;
+        }
+
+        let key = (progress.status.clone(), progress.progress as i64 / 10);
+        {
+            let mut last = self.last.lock().unwrap();
+            if last.get(&progress.conversion_id) == Some(&key) {
+                return;
+            }
+            last.insert(progress.conversion_id.clone(), key);
+        }
+
+        let eta = progress.eta.map(|eta| format!("{:.0}s", eta));
+        println!(
+            "convertendo {:.1}% {} ETA {}",
+            progress.progress,
+            progress.speed.map(|speed| format!("{:.2}x", speed)).as_deref().unwrap_or("-"),
+            eta.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let reporter = Reporter::new(cli.json);
+
+    let detected = BinaryPaths::detect();
+    let ffprobe = cli
+        .ffprobe
+        .or_else(|| cli.ffmpeg.as_deref().map(FFmpeg::ffprobe_beside))
+        .unwrap_or(detected.ffprobe);
+    let paths = BinaryPaths {
+        ytdlp: cli.yt_dlp.unwrap_or(detected.ytdlp),
+        ffmpeg: cli.ffmpeg.unwrap_or(detected.ffmpeg),
+        ffprobe,
+    };
+    let services = Services::new(paths, Settings::load(), reporter.clone());
+
+    let result = tokio::select! {
+        result = run(cli.command, &services, &reporter) => result,
+        // yt-dlp and ffmpeg are in the same process group and get the signal too
+        _ = tokio::signal::ctrl_c() => {
+            reporter.error("Interrompido");
+            return ExitCode::from(EXIT_INTERRUPTED);
+        }
+    };
+
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            reporter.error(&e);
+            ExitCode::from(EXIT_FAILED)
+        }
+    }
+}
+
+async fn run(command: Command, services: &Services, reporter: &Reporter) -> Result<u8, String> {
+    match command {
+        Command::Info { url } => {
+            let info = services.ytdlp().await?.get_video_info(&url).await?;
+            if reporter.json {
+                reporter.emit("info", &info);
+            } else {
+                print_info(&info);
+            }
+            Ok(EXIT_OK)
+        }
+        Command::Download { url, options } => {
+            let request = options.to_request(&url, &services.settings())?;
+            // Fails once here instead of once per job
+            services.ytdlp().await?;
+            let (_, failed) = download_all(services, vec![(url, request)], reporter).await;
+            Ok(if failed == 0 { EXIT_OK } else { EXIT_FAILED })
+        }
+        Command::Playlist { url, list, options } => {
+            let playlist = services.ytdlp().await?.get_playlist_info(&url).await?;
+
+            if list {
+                if reporter.json {
+                    reporter.emit("playlist", &playlist);
+                } else {
+                    println!("{} ({} vídeos)", playlist.title, playlist.entry_count);
+                    for (i, entry) in playlist.entries.iter().enumerate() {
+                        println!(
+                            "{:>4}  {:>8}  {}  {}",
+                            i + 1,
+                            entry.duration_string.as_deref().unwrap_or("-"),
+                            entry.title,
+                            entry.url
+                        );
+                    }
+                }
+                return Ok(EXIT_OK);
+            }
+
+            let settings = services.settings();
+            let requests = playlist
+                .entries
+                .iter()
+                .map(|entry| Ok((entry.title.clone(), options.to_request(&entry.url, &settings)?)))
+                .collect::<Result<Vec<_>, String>>()?;
+            if requests.is_empty() {
+                return Err("Playlist vazia".to_string());
+            }
+
+            let (completed, failed) = download_all(services, requests, reporter).await;
+            Ok(match (completed, failed) {
+                (_, 0) => EXIT_OK,
+                (0, _) => EXIT_FAILED,
+                _ => EXIT_PARTIAL,
+            })
+        }
+        Command::Probe { input } => {
+            let info = services.ffmpeg.probe_media(&input).await?;
+            if reporter.json {
+                reporter.emit("media_info", &info);
+            } else {
+                print_media_info(&info);
+            }
+            Ok(EXIT_OK)
+        }
+        Command::ConvertMedia {
+            input,
+            format,
+            options,
+            dry_run,
+        } => {
+            let options = options.to_options(&services.settings())?;
+            if dry_run {
+                let plan = services.ffmpeg.plan_conversion(&input, &format, &options).await?;
+                if reporter.json {
+                    reporter.emit("conversion_plan", &plan);
+                } else {
+                    if !plan.streams.is_empty() {
+                        println!("{}", describe_streams(&plan.streams));
+                    }
+                    println!("ffmpeg {}", shell_words(&plan.args));
+                }
+                return Ok(EXIT_OK);
+            }
+
+            let output = services
+                .ffmpeg
+                .start_conversion("convert".to_string(), &input, &format, &options, reporter.clone())
+                .await?;
+            reporter.output(&output);
+            Ok(EXIT_OK)
+        }
+        Command::Trim {
+            input,
+            range,
+            precise,
+            join,
+        } => {
+            let request = TrimRequest {
+                input_path: input,
+                ranges: range
+                    .iter()
+                    .map(|r| TimeRange::parse(r))
+                    .collect::<Result<Vec<_>, String>>()?,
+                mode: if precise { TrimMode::Precise } else { TrimMode::Fast },
+                join,
+            };
+            let outputs = services
+                .ffmpeg
+                .start_trim("trim".to_string(), &request, reporter.clone())
+                .await?;
+            for output in &outputs {
+                reporter.output(output);
+            }
+            Ok(EXIT_OK)
+        }
+        Command::Concat {
+            inputs,
+            output,
+            chapters,
+        } => {
+            let request = ConcatRequest {
+                input_paths: inputs,
+                output_path: output,
+                chapters,
+            };
+            let result = services
+                .ffmpeg
+                .start_concat("concat".to_string(), &request, reporter.clone())
+                .await?;
+            if reporter.json {
+                reporter.emit("concat", &result);
+            } else {
+                match &result.reason {
+                    Some(reason) => println!("Recodificado: {}", reason),
+                    None => println!("Juntado sem recodificar"),
+                }
+                println!("{}", result.output);
+            }
+            Ok(EXIT_OK)
+        }
+        Command::Animate {
+            input,
+            webp,
+            start,
+            end,
+            fps,
+            width,
+            loops,
+            max_size_kb,
+        } => {
+            let request = AnimationRequest {
+                input_path: input,
+                format: if webp { AnimationFormat::Webp } else { AnimationFormat::Gif },
+                start,
+                end,
+                fps,
+                width,
+                loops,
+                max_size_kb,
+            };
+            let result = services
+                .ffmpeg
+                .start_animation("animate".to_string(), &request, reporter.clone())
+                .await?;
+            if reporter.json {
+                reporter.emit("animation", &result);
+            } else {
+                println!(
+                    "{} fps, {} px, {} KiB",
+                    result.fps,
+                    result.width,
+                    result.size.div_ceil(1024)
+                );
+                println!("{}", result.output);
+            }
+            Ok(EXIT_OK)
+        }
+        Command::Frame { input, at, options } => {
+            let output = services.ffmpeg.extract_frame(&input, at, &options.to_options()).await?;
+            reporter.output(&output);
+            Ok(EXIT_OK)
+        }
+        Command::Frames { input, count, options } => {
+            let outputs = services
+                .ffmpeg
+                .extract_frames(&input, count, &options.to_options())
+                .await?;
+            for output in &outputs {
+                reporter.output(output);
+            }
+            Ok(EXIT_OK)
+        }
+        Command::ContactSheet {
+            input,
+            columns,
+            rows,
+            tile_width,
+            no_timestamps,
+            format,
+        } => {
+            let options = ContactSheetOptions {
+                columns,
+                rows,
+                tile_width,
+                hide_timestamps: no_timestamps,
+                format,
+            };
+            let output = services.ffmpeg.contact_sheet(&input, &options).await?;
+            reporter.output(&output);
+            Ok(EXIT_OK)
+        }
+        Command::Normalize {
+            inputs,
+            preset,
+            integrated,
+            true_peak,
+            lra,
+        } => {
+            let request = LoudnessRequest {
+                input_paths: inputs,
+                options: LoudnessOptions {
+                    preset,
+                    integrated,
+                    true_peak,
+                    lra,
+                },
+            };
+            let outcomes = services
+                .ffmpeg
+                .start_normalize("normalize".to_string(), &request, reporter.clone())
+                .await?;
+
+            let mut failed = 0;
+            for outcome in &outcomes {
+                if reporter.json {
+                    reporter.emit("loudness", outcome);
+                    failed += usize::from(outcome.error.is_some());
+                    continue;
+                }
+                match (&outcome.result, &outcome.error) {
+                    (Some(result), _) => {
+                        println!(
+                            "{}: {} -> {}{}",
+                            outcome.input,
+                            describe_loudness(&result.measured),
+                            describe_loudness(&result.normalized),
+                            if result.linear { "" } else { " (com compressão dinâmica)" }
+                        );
+                        println!("{}", result.output);
+                    }
+                    (None, error) => {
+                        failed += 1;
+                        eprintln!("{}: {}", outcome.input, error.as_deref().unwrap_or_default());
+                    }
+                }
+            }
+            Ok(match failed {
+                0 => EXIT_OK,
+                n if n == outcomes.len() => EXIT_FAILED,
+                _ => EXIT_PARTIAL,
+            })
+        }
+        Command::ConvertImage {
+            input,
+            format,
+            max_size,
+            square,
+        } => {
+            let options = ImageOptions { max_size, square };
+            let output = services.images.convert_with_options(&input, &format, &options)?;
+            reporter.output(&output);
+            Ok(EXIT_OK)
+        }
+        Command::ImageToPdf { input } => {
+            let output = services.documents.convert(&input, "pdf")?;
+            reporter.output(&output);
+            Ok(EXIT_OK)
+        }
+    }
+}
+
+// Runs the requests through the same queue as the desktop app and waits for
+// all of them. Returns (completed, failed)
+async fn download_all(
+    services: &Services,
+    requests: Vec<(String, DownloadRequest)>,
+    reporter: &Reporter,
+) -> (usize, usize) {
+    let manager = &services.downloads;
+    let mut events = manager.subscribe();
+
+    let ids: Vec<String> = requests
+        .into_iter()
+        .map(|(label, request)| {
+            let id = manager.enqueue(request);
+            reporter.labels.lock().unwrap().insert(id.clone(), label);
+            id
+        })
+        .collect();
+
+    // Every job ends with a "completed", "error" or "cancelled" update
+    let mut pending: HashSet<String> = ids.iter().cloned().collect();
+    while !pending.is_empty() {
+        match events.recv().await {
+            Ok(progress) if is_finished(&progress.status) => {
+                pending.remove(&progress.download_id);
+            }
+            Ok(_) => {}
+            Err(RecvError::Lagged(_)) => pending.retain(|id| {
+                manager
+                    .get_job(id)
+                    .map(|job| !is_finished(&job.status))
+                    .unwrap_or(false)
+            }),
+            Err(RecvError::Closed) => break,
+        }
+    }
+
+    let jobs: Vec<_> = ids.iter().filter_map(|id| manager.get_job(id)).collect();
+    let completed = jobs.iter().filter(|job| job.status == "completed").count();
+
+    for job in jobs.iter().filter(|job| job.status != "completed") {
+        let message = job.error.clone().unwrap_or_else(|| job.status.clone());
+        reporter.error(&format!("{}: {}", reporter.label(&job.id), message.trim()));
+    }
+    // The download itself worked, so these don't count as failures
+    for job in &jobs {
+        for hook in job.hooks.iter().filter(|hook| !hook.success) {
+            let message = hook.error.as_deref().unwrap_or("falhou");
+            reporter.error(&format!("{}: ação {}: {}", reporter.label(&job.id), hook.action, message.trim()));
+        }
+    }
+
+    if reporter.json {
+        reporter.emit(
+            "summary",
+            &json!({ "completed": completed, "failed": jobs.len() - completed }),
+        );
+    } else if jobs.len() > 1 {
+        println!("{} de {} downloads concluídos", completed, jobs.len());
+    }
+
+    (completed, jobs.len() - completed)
+}
+
+fn is_finished(status: &str) -> bool {
+    matches!(status, "completed" | "error" | "cancelled")
+}
+
+fn print_media_info(info: &MediaInfo) {
+    println!("Formato: {}", info.container_name.as_deref().unwrap_or(&info.container));
+    if let Some(duration) = info.duration {
+        println!("Duração: {:.1}s", duration);
+    }
+    if let Some(bitrate) = info.bitrate {
+        println!("Bitrate: {} kb/s", bitrate / 1000);
+    }
+
+    println!();
+    for stream in &info.streams {
+        let details = match stream.kind.as_str() {
+            "video" => {
+                let mut details = vec![
+                    format!("{}x{}", stream.width.unwrap_or(0), stream.height.unwrap_or(0)),
+                    stream.fps.map(|fps| format!("{:.3} fps", fps)).unwrap_or_default(),
+                    stream.pixel_format.clone().unwrap_or_default(),
+                ];
+                details.extend(stream.hdr.clone());
+                if stream.attached_pic {
+                    details.push("capa".to_string());
+                }
+                details
+            }
+            "audio" => vec![
+                stream.sample_rate.map(|rate| format!("{} Hz", rate)).unwrap_or_default(),
+                stream
+                    .channel_layout
+                    .clone()
+                    .or(stream.channels.map(|c| format!("{} canais", c)))
+                    .unwrap_or_default(),
+            ],
+            _ => Vec::new(),
+        };
+        let details: Vec<String> = details.into_iter().filter(|d| !d.is_empty()).collect();
+        println!(
+            "#{:<3} {:<9} {:<10} {:<4} {}",
+            stream.index,
+            stream.kind,
+            stream.codec.as_deref().unwrap_or("-"),
+            stream.language.as_deref().unwrap_or("-"),
+            details.join(", ")
+        );
+    }
+
+    if !info.chapters.is_empty() {
+        println!();
+        for chapter in &info.chapters {
+            println!(
+                "{:>8.1}s  {}",
+                chapter.start,
+                chapter.title.as_deref().unwrap_or("")
+            );
+        }
+    }
+}
+
+fn print_info(info: &VideoInfo) {
+    println!("{}", info.title);
+    if let Some(uploader) = &info.uploader {
+        println!("Canal: {}", uploader);
+    }
+    if let Some(duration) = &info.duration_string {
+        println!("Duração: {}", duration);
+    }
+    if info.is_live {
+        println!("Ao vivo");
+    }
+
+    println!();
+    println!("{:<12} {:<6} {:<12} {:<10} NOTA", "ID", "EXT", "RESOLUÇÃO", "TAMANHO");
+    for format in &info.formats {
+        let size = format
+            .filesize
+            .or(format.filesize_approx)
+            .map(format_size)
+            .unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<12} {:<6} {:<12} {:<10} {}",
+            format.format_id,
+            format.ext,
+            format.resolution.as_deref().unwrap_or("-"),
+            size,
+            format.format_note.as_deref().unwrap_or("")
+        );
+    }
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+// e.g. "vídeo h264: copiado, áudio opus: recodificado com aac"
+fn describe_streams(streams: &[StreamPlan]) -> String {
+    streams
+        .iter()
+        .map(|stream| {
+            let kind = match stream.kind.as_str() {
+                "video" => "vídeo",
+                "audio" => "áudio",
+                "subtitle" => "legenda",
+                other => other,
+            };
+            let action = match (stream.action.as_str(), &stream.encoder) {
+                ("copy", _) => "copiado".to_string(),
+                ("drop", _) => "removido".to_string(),
+                (_, Some(encoder)) => format!("recodificado com {}", encoder),
+                _ => "recodificado".to_string(),
+            };
+            format!("{} {}: {}", kind, stream.source_codec.as_deref().unwrap_or("?"), action)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn time_arg(value: &str) -> Result<f64, String> {
+    parse_time(value).ok_or_else(|| format!("Tempo inválido: {} (use segundos ou 1:30)", value))
+}
+
+// e.g. "-23.1 LUFS, pico -4.2 dBTP, LRA 7.5 LU"
+fn describe_loudness(measurement: &LoudnessMeasurement) -> String {
+    format!(
+        "{:.1} LUFS, pico {:.1} dBTP, LRA {:.1} LU",
+        measurement.integrated, measurement.true_peak, measurement.lra
+    )
+}
+
+// Quotes the arguments that need it, so the line can be pasted in a shell
+fn shell_words(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_.,:/=+@%".contains(c)) {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', r"'\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
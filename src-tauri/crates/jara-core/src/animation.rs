@@ -0,0 +1,305 @@
+use crate::events::{EventSink, NoEvents};
+use crate::ffmpeg::{decimal_arg, new_conversion_id, remove_partial_output, ConvertProgress, FFmpeg};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+const DEFAULT_FPS: f64 = 15.0;
+const DEFAULT_WIDTH: u32 = 480;
+// How far the size limit may push the frame rate and width down
+const MIN_FPS: f64 = 5.0;
+const MIN_WIDTH: u32 = 120;
+// Encodes tried before giving up on the size limit
+const MAX_ATTEMPTS: u32 = 6;
+// libwebp quality, 0 to 100
+const WEBP_QUALITY: u32 = 75;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnimationFormat {
+    #[default]
+    Gif,
+    Webp,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimationRequest {
+    pub input_path: String,
+    pub format: AnimationFormat,
+    // Seconds; the whole video when unset
+    pub start: Option<f64>,
+    pub end: Option<f64>,
+    // 15 when unset
+    pub fps: Option<f64>,
+    // 480 px when unset, never wider than the video; the height follows
+    pub width: Option<u32>,
+    // How many times it plays; unset or 0 loops forever
+    pub loops: Option<u32>,
+    // KiB. Frame rate and width are lowered until the file fits
+    pub max_size_kb: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnimationResult {
+    pub output: String,
+    // What was used, after any tuning for the size limit
+    pub fps: f64,
+    pub width: u32,
+    // Bytes
+    pub size: u64,
+    pub attempts: u32,
+}
+
+impl AnimationFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            AnimationFormat::Gif => "gif",
+            AnimationFormat::Webp => "webp",
+        }
+    }
+}
+
+impl FFmpeg {
+    pub async fn video_to_animation(&self, request: &AnimationRequest) -> Result<AnimationResult, String> {
+        self.start_animation(new_conversion_id(), request, NoEvents).await
+    }
+
+    // Writes "<name>.gif" or "<name>.webp" next to the video. GIFs get a
+    // palette made for the clip first. Reports "convert-progress" events for
+    // each encode; cancel_conversion stops it with the same id
+    pub async fn start_animation<S>(
+        &self,
+        conversion_id: String,
+        request: &AnimationRequest,
+        events: S,
+    ) -> Result<AnimationResult, String>
+    where
+        S: EventSink,
+    {
+        let input = PathBuf::from(&request.input_path);
+        if !input.exists() {
+            return Err("Arquivo de entrada não encontrado".to_string());
+        }
+        let output = input.with_extension(request.format.extension());
+        if output == input {
+            return Err("O arquivo de saída não pode ser a entrada".to_string());
+        }
+
+        let mut fps = request.fps.unwrap_or(DEFAULT_FPS);
+        if !(fps > 0.0 && fps <= 50.0) {
+            return Err(format!("Taxa de quadros inválida: {} (use até 50)", fps));
+        }
+        if let Some(width) = request.width {
+            if !(16..=1920).contains(&width) {
+                return Err(format!("Largura inválida: {} (use de 16 a 1920)", width));
+            }
+        }
+        if request.max_size_kb == Some(0) {
+            return Err("O tamanho máximo precisa ser maior que zero".to_string());
+        }
+        // shrink only lowers the frame rate, down to MIN_FPS
+        if request.max_size_kb.is_some() && fps < MIN_FPS {
+            return Err(format!(
+                "Com tamanho máximo, a taxa de quadros precisa ser de pelo menos {} fps",
+                decimal_arg(MIN_FPS)
+            ));
+        }
+
+        let media = self.probe_media(&request.input_path).await.ok();
+        if media.as_ref().is_some_and(|m| m.video().is_none()) {
+            return Err("O arquivo não tem vídeo".to_string());
+        }
+        let source_width = media.as_ref().and_then(|m| m.video()).and_then(|v| v.width);
+        let mut width = request.width.unwrap_or(DEFAULT_WIDTH).min(source_width.unwrap_or(u32::MAX));
+
+        let start = request.start.unwrap_or(0.0);
+        let duration = media.as_ref().and_then(|m| m.duration);
+        if let Some(total) = duration {
+            if start >= total {
+                return Err("O início fica depois do fim do vídeo".to_string());
+            }
+        }
+        let end = match (request.end, duration) {
+            (Some(end), _) if end <= start => {
+                return Err("O fim precisa vir depois do início".to_string());
+            }
+            (Some(end), Some(total)) => Some(end.min(total)),
+            (end, total) => end.or(total),
+        };
+        let length = end.map(|end| end - start);
+
+        let clip = Clip {
+            input: &request.input_path,
+            start,
+            length,
+        };
+        let limit = request.max_size_kb.map(|kb| kb * 1024);
+
+        let mut attempts = 0;
+        let size = loop {
+            attempts += 1;
+            let result = match request.format {
+                AnimationFormat::Gif => {
+                    self.encode_gif(&conversion_id, &clip, &frame_filter(fps, width), request.loops, &output, &events)
+                        .await
+                }
+                AnimationFormat::Webp => {
+                    let args = webp_args(&clip, fps, width, request.loops, &output);
+                    self.run_ffmpeg(&conversion_id, &args, 0.0, length, &[], &events)
+                        .await
+                        .map(|_| ())
+                }
+            }
+            .and_then(|()| {
+                std::fs::metadata(&output)
+                    .map(|m| m.len())
+                    .map_err(|e| format!("Falha ao ler o arquivo gerado: {}", e))
+            });
+            let size = match result {
+                Ok(size) => size,
+                Err(e) => {
+                    remove_partial_output(&input, &output);
+                    return Err(e);
+                }
+            };
+
+            let Some(limit) = limit.filter(|limit| size > *limit) else {
+                break size;
+            };
+            let smaller = (attempts < MAX_ATTEMPTS)
+                .then(|| shrink(fps, width, limit as f64 / size as f64))
+                .flatten();
+            match smaller {
+                Some((smaller_fps, smaller_width)) => (fps, width) = (smaller_fps, smaller_width),
+                None => {
+                    remove_partial_output(&input, &output);
+                    return Err(format!(
+                        "Não foi possível ficar abaixo de {} KiB; o menor resultado teve {} KiB ({} fps, {} px)",
+                        limit / 1024,
+                        size.div_ceil(1024),
+                        decimal_arg(fps),
+                        width
+                    ));
+                }
+            }
+        };
+
+        let output_str = output.to_string_lossy().to_string();
+        events.convert_progress(ConvertProgress {
+            conversion_id,
+            status: "completed".to_string(),
+            progress: 100.0,
+            fps: None,
+            speed: None,
+            eta: None,
+            output: Some(output_str.clone()),
+            streams: Vec::new(),
+        });
+        Ok(AnimationResult {
+            output: output_str,
+            fps,
+            width,
+            size,
+            attempts,
+        })
+    }
+
+    // palettegen picks the 256 colors that suit this clip, then paletteuse
+    // maps every frame onto them
+    async fn encode_gif<S>(
+        &self,
+        conversion_id: &str,
+        clip: &Clip<'_>,
+        frames: &str,
+        loops: Option<u32>,
+        output: &Path,
+        events: &S,
+    ) -> Result<(), String>
+    where
+        S: EventSink,
+    {
+        let palette = output.with_extension("palette.png");
+        let total = clip.length.map(|length| length * 2.0);
+
+        let mut args = clip.input_args();
+        args.extend(["-vf".to_string(), format!("{},palettegen=stats_mode=diff", frames)]);
+        args.extend(["-y", "-progress", "pipe:1", "-nostats"].map(String::from));
+        args.push(palette.to_string_lossy().to_string());
+        if let Err(e) = self.run_ffmpeg(conversion_id, &args, 0.0, total, &[], events).await {
+            let _ = std::fs::remove_file(&palette);
+            return Err(e);
+        }
+
+        let mut args = clip.input_args();
+        args.extend(["-i".to_string(), palette.to_string_lossy().to_string()]);
+        args.extend([
+            "-lavfi".to_string(),
+            format!("{}[x];[x][1:v]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle", frames),
+        ]);
+        // The GIF muxer counts repeats after the first play, with -1 for none
+        let repeats = match loops.unwrap_or(0) {
+            0 => 0,
+            1 => -1,
+            plays => plays as i64 - 1,
+        };
+        args.extend(["-loop".to_string(), repeats.to_string()]);
+        args.extend(["-y", "-progress", "pipe:1", "-nostats"].map(String::from));
+        args.push(output.to_string_lossy().to_string());
+        let done = clip.length.unwrap_or(0.0);
+        let result = self.run_ffmpeg(conversion_id, &args, done, total, &[], events).await;
+        let _ = std::fs::remove_file(&palette);
+        result.map(|_| ())
+    }
+}
+
+// The part of the video an animation is made from
+struct Clip<'a> {
+    input: &'a str,
+    start: f64,
+    // Until the end of the video when unknown
+    length: Option<f64>,
+}
+
+impl Clip<'_> {
+    fn input_args(&self) -> Vec<String> {
+        let mut args = vec!["-ss".to_string(), decimal_arg(self.start)];
+        if let Some(length) = self.length {
+            args.extend(["-t".to_string(), decimal_arg(length)]);
+        }
+        args.extend(["-i".to_string(), self.input.to_string()]);
+        args
+    }
+}
+
+fn frame_filter(fps: f64, width: u32) -> String {
+    format!("fps={},scale={}:-1:flags=lanczos", decimal_arg(fps), width)
+}
+
+fn webp_args(clip: &Clip, fps: f64, width: u32, loops: Option<u32>, output: &Path) -> Vec<String> {
+    let mut args = clip.input_args();
+    args.extend(["-vf".to_string(), frame_filter(fps, width)]);
+    args.extend(["-c:v", "libwebp", "-quality"].map(String::from));
+    args.push(WEBP_QUALITY.to_string());
+    // Here the count is of plays, with 0 for forever
+    args.extend(["-loop".to_string(), loops.unwrap_or(0).to_string(), "-an".to_string()]);
+    args.extend(["-y", "-progress", "pipe:1", "-nostats"].map(String::from));
+    args.push(output.to_string_lossy().to_string());
+    args
+}
+
+// The next frame rate and width to try when the file came out `ratio` times
+// the size it should have. The size grows with the frame rate and the square
+// of the width, so a third of the cut goes to the frame rate and the rest to
+// the width. None when both are already at their minimum
+fn shrink(fps: f64, width: u32, ratio: f64) -> Option<(f64, u32)> {
+    if fps <= MIN_FPS && width <= MIN_WIDTH {
+        return None;
+    }
+    // Aim a bit under the limit, so a near miss doesn't need a third try
+    let ratio = ratio * 0.9;
+    let smaller_fps = (fps * ratio.cbrt()).max(MIN_FPS).min(fps).round();
+    let left = ratio * fps / smaller_fps;
+    // Even widths keep the chroma subsampling of WebP happy
+    let smaller_width = ((width as f64 * left.sqrt()) as u32 & !1).clamp(MIN_WIDTH, width);
+    Some((smaller_fps, smaller_width))
+}
@@ -0,0 +1,291 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use url::Url;
+
+// Options applied to every imported URL unless a CSV row overrides them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportDefaults {
+    pub output_path: String,
+    pub format_id: Option<String>,
+    #[serde(default)]
+    pub audio_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportEntry {
+    pub line: usize,
+    pub url: String,
+    // "video", "playlist" or "channel"
+    pub kind: String,
+    pub format_id: Option<String>,
+    pub audio_only: bool,
+    pub output_path: String,
+    pub sections: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportIssue {
+    pub line: usize,
+    pub value: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub entries: Vec<ImportEntry>,
+    pub duplicates: Vec<ImportIssue>,
+    pub invalid: Vec<ImportIssue>,
+    // Job ids, in the same order as entries
+    pub job_ids: Vec<String>,
+}
+
+pub struct BulkImporter;
+
+impl BulkImporter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    // Reads a plain-text list, a CSV or a Netscape bookmarks export
+    pub fn parse(&self, input_path: &str, defaults: &ImportDefaults) -> Result<ImportReport, String> {
+        let input = PathBuf::from(input_path);
+
+        if !input.exists() {
+            return Err("Arquivo de entrada não encontrado".to_string());
+        }
+
+        let content = std::fs::read_to_string(&input)
+            .map_err(|e| format!("Falha ao ler arquivo: {}", e))?;
+        let content = content.trim_start_matches('\u{feff}');
+
+        let ext = input
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        let is_bookmarks = matches!(ext.as_str(), "html" | "htm")
+            || content.trim_start().starts_with("<!DOCTYPE NETSCAPE-Bookmark-file");
+
+        let rows = if is_bookmarks {
+            parse_bookmarks(content)
+        } else if ext == "csv" {
+            parse_csv(content)?
+        } else {
+            parse_text(content)
+        };
+
+        let mut report = ImportReport::default();
+        let mut seen = HashSet::new();
+
+        for (line, mut entry) in rows {
+            entry.line = line;
+            if entry.output_path.is_empty() {
+                entry.output_path = defaults.output_path.clone();
+            }
+            if entry.format_id.is_none() && !entry.audio_only {
+                entry.format_id = defaults.format_id.clone();
+                entry.audio_only = defaults.audio_only;
+            }
+
+            let url = match validate_url(&entry.url) {
+                Ok(url) => url,
+                Err(reason) => {
+                    report.invalid.push(ImportIssue { line, value: entry.url, reason });
+                    continue;
+                }
+            };
+
+            if !seen.insert(url.clone()) {
+                report.duplicates.push(ImportIssue {
+                    line,
+                    value: url,
+                    reason: "URL repetida".to_string(),
+                });
+                continue;
+            }
+
+            entry.kind = classify(&url).to_string();
+            entry.url = url;
+            report.entries.push(entry);
+        }
+
+        Ok(report)
+    }
+}
+
+impl Default for BulkImporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn empty_entry(url: &str) -> ImportEntry {
+    ImportEntry {
+        line: 0,
+        url: url.trim().to_string(),
+        kind: String::new(),
+        format_id: None,
+        audio_only: false,
+        output_path: String::new(),
+        sections: Vec::new(),
+    }
+}
+
+fn parse_text(content: &str) -> Vec<(usize, ImportEntry)> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") || line.starts_with(';') {
+                return None;
+            }
+            // Anything after the URL ("https://... # weekly") is a comment
+            let url = line.split_whitespace().next()?;
+            Some((i + 1, empty_entry(url)))
+        })
+        .collect()
+}
+
+// Header row required; recognized columns are url, format, output and section.
+// format accepts a yt-dlp format id or "audio"; section accepts several ranges
+// separated by "|", e.g. "*0:00-1:30|*10:00-12:00"
+fn parse_csv(content: &str) -> Result<Vec<(usize, ImportEntry)>, String> {
+    let mut records = split_csv_records(content).into_iter();
+
+    let (_, header) = records.next().ok_or("O arquivo CSV está vazio")?;
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+
+    let url_col = column(&["url", "link"]).ok_or("O CSV precisa de uma coluna \"url\"")?;
+    let format_col = column(&["format", "formato"]);
+    let output_col = column(&["output", "output_path", "folder", "pasta"]);
+    let section_col = column(&["section", "sections", "trecho"]);
+
+    let mut rows = Vec::new();
+    for (line, record) in records {
+        let field = |col: Option<usize>| {
+            col.and_then(|c| record.get(c))
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        let Some(url) = field(Some(url_col)) else {
+            continue;
+        };
+        if url.starts_with('#') {
+            continue;
+        }
+
+        let mut entry = empty_entry(&url);
+        match field(format_col) {
+            Some(format) if format.eq_ignore_ascii_case("audio") => entry.audio_only = true,
+            Some(format) => entry.format_id = Some(format),
+            None => {}
+        }
+        entry.output_path = field(output_col).unwrap_or_default();
+        entry.sections = field(section_col)
+            .map(|s| s.split('|').map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect())
+            .unwrap_or_default();
+
+        rows.push((line, entry));
+    }
+
+    Ok(rows)
+}
+
+// Minimal RFC 4180 reader: quoted fields may contain commas, quotes and newlines.
+// Returns each record with the line number it starts on
+fn split_csv_records(content: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' if in_quotes => in_quotes = false,
+            // A quote only opens a quoted field at its start; elsewhere it is
+            // part of the value, e.g. in a URL
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => record.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.trim().is_empty()) {
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                record_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    record.push(field);
+    if record.iter().any(|f| !f.trim().is_empty()) {
+        records.push((record_line, record));
+    }
+
+    records
+}
+
+fn parse_bookmarks(content: &str) -> Vec<(usize, ImportEntry)> {
+    let href_regex = Regex::new(r#"(?i)<a\s[^>]*href\s*=\s*"([^"]+)""#).unwrap();
+
+    content
+        .lines()
+        .enumerate()
+        .flat_map(|(i, line)| {
+            href_regex
+                .captures_iter(line)
+                .map(|caps| {
+                    let url = caps[1].replace("&amp;", "&");
+                    (i + 1, empty_entry(&url))
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn validate_url(value: &str) -> Result<String, String> {
+    let url = Url::parse(value).map_err(|_| "URL inválida".to_string())?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Protocolo não suportado: {}", url.scheme()));
+    }
+    if url.host_str().map(|h| h.is_empty()).unwrap_or(true) {
+        return Err("URL sem domínio".to_string());
+    }
+
+    Ok(url.to_string())
+}
+
+fn classify(url: &str) -> &'static str {
+    if url.contains("playlist") || url.contains("list=") {
+        "playlist"
+    } else if url.contains("/@")
+        || url.contains("/channel/")
+        || url.contains("/c/")
+        || url.contains("/user/")
+    {
+        "channel"
+    } else {
+        "video"
+    }
+}
@@ -0,0 +1,154 @@
+use crate::ytdlp::YtDlp;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex as TokioMutex;
+use url::Url;
+
+// Where the watcher reads from; the app passes the system clipboard and tests
+// a scripted one
+pub trait ClipboardSource: Send {
+    fn read_text(&mut self) -> Option<String>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedUrl {
+    pub url: String,
+    // yt-dlp extractor that matched the URL
+    pub site: String,
+}
+
+// Extractor names from `yt-dlp --list-extractors`, fetched once and cached
+#[derive(Clone)]
+pub struct SupportedSites {
+    ytdlp: Option<Arc<TokioMutex<YtDlp>>>,
+    names: Arc<TokioMutex<Option<HashSet<String>>>>,
+}
+
+impl SupportedSites {
+    pub fn new(ytdlp: Arc<TokioMutex<YtDlp>>) -> Self {
+        Self {
+            ytdlp: Some(ytdlp),
+            names: Arc::new(TokioMutex::new(None)),
+        }
+    }
+
+    // Preloaded list, no yt-dlp needed
+    pub fn with_extractors(extractors: &[String]) -> Self {
+        Self {
+            ytdlp: None,
+            names: Arc::new(TokioMutex::new(Some(extractor_names(extractors)))),
+        }
+    }
+
+    // Matches the URL's domain labels against extractor names, so
+    // "www.youtube.com" and "youtu.be" both resolve to "youtube".
+    // This is a heuristic: it never fetches the URL itself
+    pub async fn site_for(&self, url: &str) -> Option<String> {
+        let host = Url::parse(url).ok()?.host_str()?.to_lowercase();
+
+        let mut names = self.names.lock().await;
+        if names.is_none() {
+            let ytdlp = self.ytdlp.as_ref()?;
+            let ytdlp = {
+                let mut ytdlp = ytdlp.lock().await;
+                ytdlp.ensure_ytdlp_exists().await.ok()?;
+                ytdlp.clone()
+            };
+            // Not cached on failure, so the next copy tries again
+            *names = Some(extractor_names(&ytdlp.list_extractors().await.ok()?));
+        }
+        let names = names.as_ref()?;
+
+        let labels: Vec<&str> = host.split('.').collect();
+        let domain_labels = &labels[..labels.len().saturating_sub(1)];
+        let joined: String = labels
+            .iter()
+            .filter(|l| !matches!(**l, "www" | "m"))
+            .copied()
+            .collect();
+
+        domain_labels
+            .iter()
+            .copied()
+            .filter(|l| !matches!(*l, "www" | "m" | "com" | "co"))
+            .chain(std::iter::once(joined.as_str()))
+            .find(|candidate| names.contains(*candidate))
+            .map(String::from)
+    }
+}
+
+fn extractor_names(extractors: &[String]) -> HashSet<String> {
+    extractors
+        .iter()
+        .map(|e| e.split(':').next().unwrap_or(e).trim().to_lowercase())
+        .filter(|e| !e.is_empty() && e != "generic")
+        .collect()
+}
+
+// Polls a clipboard source and reports newly copied URLs of supported sites.
+// Stops when dropped
+pub struct ClipboardWatcher {
+    stop: Arc<AtomicBool>,
+}
+
+impl ClipboardWatcher {
+    pub fn start<F>(
+        mut source: Box<dyn ClipboardSource>,
+        sites: SupportedSites,
+        interval: Duration,
+        on_detect: F,
+    ) -> Self
+    where
+        F: Fn(DetectedUrl) + Send + Sync + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        let url_regex = Regex::new(r#"https?://[^\s<>"']+"#).unwrap();
+
+        tokio::spawn(async move {
+            // Whatever is on the clipboard before the watcher starts is not "new"
+            let mut last_text = source.read_text();
+            let mut reported: HashSet<String> = HashSet::new();
+
+            while !stop_flag.load(Ordering::SeqCst) {
+                tokio::time::sleep(interval).await;
+
+                let text = source.read_text();
+                if text.is_none() || text == last_text {
+                    continue;
+                }
+                last_text = text.clone();
+
+                for url in url_regex.find_iter(text.as_deref().unwrap_or("")) {
+                    let url = url.as_str().trim_end_matches(['.', ',', ')', ';']);
+                    if reported.contains(url) {
+                        continue;
+                    }
+                    if let Some(site) = sites.site_for(url).await {
+                        reported.insert(url.to_string());
+                        on_detect(DetectedUrl {
+                            url: url.to_string(),
+                            site,
+                        });
+                    }
+                }
+            }
+        });
+
+        Self { stop }
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for ClipboardWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
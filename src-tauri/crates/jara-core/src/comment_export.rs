@@ -0,0 +1,132 @@
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize)]
+struct Comment {
+    id: String,
+    // "root" for top-level comments, otherwise the id of the replied comment
+    parent: String,
+    author: String,
+    author_id: Option<String>,
+    timestamp: Option<i64>,
+    like_count: Option<u64>,
+    is_pinned: bool,
+    author_is_uploader: bool,
+    text: String,
+}
+
+pub struct CommentExporter;
+
+impl CommentExporter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    // Turns the comments stored in a yt-dlp .info.json into CSV or NDJSON
+    pub fn export(&self, input_path: &str, output_format: &str) -> Result<String, String> {
+        let input = PathBuf::from(input_path);
+
+        if !input.exists() {
+            return Err("Arquivo de entrada não encontrado".to_string());
+        }
+
+        let output_format = output_format.to_lowercase();
+        if !matches!(output_format.as_str(), "csv" | "ndjson") {
+            return Err(format!("Formato não suportado: {}", output_format));
+        }
+
+        let content = std::fs::read_to_string(&input)
+            .map_err(|e| format!("Falha ao ler arquivo: {}", e))?;
+        let raw: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| format!("Falha ao parsear JSON: {}", e))?;
+
+        let comments: Vec<Comment> = raw["comments"]
+            .as_array()
+            .ok_or("O arquivo não contém comentários. Baixe com a opção de comentários ativada.")?
+            .iter()
+            .map(|c| Comment {
+                id: c["id"].as_str().unwrap_or("").to_string(),
+                parent: c["parent"].as_str().unwrap_or("root").to_string(),
+                author: c["author"].as_str().unwrap_or("").to_string(),
+                author_id: c["author_id"].as_str().map(String::from),
+                timestamp: c["timestamp"].as_i64(),
+                like_count: c["like_count"].as_u64(),
+                is_pinned: c["is_pinned"].as_bool().unwrap_or(false),
+                author_is_uploader: c["author_is_uploader"].as_bool().unwrap_or(false),
+                text: c["text"].as_str().unwrap_or("").to_string(),
+            })
+            .collect();
+
+        let rendered = if output_format == "csv" {
+            write_csv(&comments)
+        } else {
+            write_ndjson(&comments)?
+        };
+
+        // "video.info.json" -> "video.comments.csv"
+        let file_name = input
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("comments");
+        let stem = file_name
+            .strip_suffix(".info.json")
+            .or_else(|| file_name.strip_suffix(".json"))
+            .unwrap_or(file_name);
+        let output = input.with_file_name(format!("{}.comments.{}", stem, output_format));
+        let output_str = output.to_string_lossy().to_string();
+
+        std::fs::write(&output, rendered)
+            .map_err(|e| format!("Falha ao salvar arquivo: {}", e))?;
+
+        Ok(output_str)
+    }
+}
+
+impl Default for CommentExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn write_csv(comments: &[Comment]) -> String {
+    let mut out = String::from(
+        "id,parent,author,author_id,timestamp,like_count,is_pinned,author_is_uploader,text\n",
+    );
+
+    for c in comments {
+        let row = [
+            csv_field(&c.id),
+            csv_field(&c.parent),
+            csv_field(&c.author),
+            csv_field(c.author_id.as_deref().unwrap_or("")),
+            c.timestamp.map(|t| t.to_string()).unwrap_or_default(),
+            c.like_count.map(|l| l.to_string()).unwrap_or_default(),
+            c.is_pinned.to_string(),
+            c.author_is_uploader.to_string(),
+            csv_field(&c.text),
+        ];
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+
+    out
+}
+
+fn write_ndjson(comments: &[Comment]) -> Result<String, String> {
+    let mut out = String::new();
+    for c in comments {
+        let line =
+            serde_json::to_string(c).map_err(|e| format!("Falha ao gerar JSON: {}", e))?;
+        out.push_str(&line);
+        out.push('\n');
+    }
+    Ok(out)
+}
@@ -0,0 +1,396 @@
+use crate::encoding::{can_copy, is_audio_format, reencode_args, ConversionOptions, StreamPlan};
+use crate::events::{EventSink, NoEvents};
+use crate::ffmpeg::{decimal_arg, new_conversion_id, write_concat_list, ConvertProgress, FFmpeg};
+use crate::media_info::{ChapterInfo, MediaInfo, StreamInfo};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcatRequest {
+    // In output order
+    pub input_paths: Vec<String>,
+    // Defaults to "<first file>-joined.<ext>" next to the first file
+    pub output_path: Option<String>,
+    // A chapter per input, named after the file
+    #[serde(default)]
+    pub chapters: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcatResult {
+    pub output: String,
+    // Joined without re-encoding
+    pub lossless: bool,
+    // Why the files had to be normalized and re-encoded
+    pub reason: Option<String>,
+    pub chapters: Vec<ChapterInfo>,
+}
+
+impl FFmpeg {
+    pub async fn concat_media(&self, request: &ConcatRequest) -> Result<ConcatResult, String> {
+        self.start_concat(new_conversion_id(), request, NoEvents).await
+    }
+
+    // Files whose streams match are joined with the concat demuxer and no
+    // re-encoding. Otherwise every input is scaled, padded and resampled to
+    // the first one's video size, frame rate and sample rate and re-encoded.
+    // Progress and cancelling work like start_conversion
+    pub async fn start_concat<S>(&self, conversion_id: String, request: &ConcatRequest, events: S) -> Result<ConcatResult, String>
+    where
+        S: EventSink,
+    {
+        let paths = &request.input_paths;
+        if paths.len() < 2 {
+            return Err("Informe ao menos dois arquivos para juntar".to_string());
+        }
+
+        let output = match &request.output_path {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let first = Path::new(&paths[0]);
+                let stem = first.file_stem().unwrap_or_default().to_string_lossy();
+                let ext = first.extension().unwrap_or_default().to_string_lossy();
+                first.with_file_name(format!("{}-joined.{}", stem, ext))
+            }
+        };
+        let format = output
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .filter(|e| !e.is_empty())
+            .ok_or("O arquivo de saída precisa ter uma extensão")?;
+        if paths.iter().any(|p| Path::new(p) == output) {
+            return Err("O arquivo de saída não pode ser uma das entradas".to_string());
+        }
+        // A failed run removes the output, so it must be one this run creates
+        if output.exists() {
+            return Err(format!("O arquivo de saída já existe: {}", output.to_string_lossy()));
+        }
+
+        let mut inputs = Vec::new();
+        for path in paths {
+            inputs.push(self.probe_media(path).await?);
+        }
+
+        let durations: Vec<Option<f64>> = inputs.iter().map(|m| m.duration).collect();
+        let total: Option<f64> = durations.iter().copied().sum();
+        let chapters = if request.chapters {
+            chapter_list(paths, &durations)?
+        } else {
+            Vec::new()
+        };
+
+        let audio_only = is_audio_format(&format);
+        let kinds: &[&str] = if audio_only {
+            &["audio"]
+        } else {
+            &["video", "audio", "subtitle"]
+        };
+        let reason = mismatch(paths, &inputs, &format, kinds);
+
+        let mut temporary = Vec::new();
+        let metadata_path = output.with_extension("chapters.txt");
+        if !chapters.is_empty() {
+            write_chapters(&metadata_path, &chapters)?;
+            temporary.push(metadata_path.clone());
+        }
+
+        let built = match &reason {
+            None => {
+                let list_path = output.with_extension("concat.txt");
+                let parts: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+                write_concat_list(&list_path, &parts).map(|()| {
+                    temporary.push(list_path.clone());
+                    lossless_args(&list_path, &metadata_path, &chapters, &inputs[0], kinds)
+                })
+            }
+            Some(_) => normalized_args(paths, &inputs, &format, audio_only, &metadata_path, &chapters),
+        };
+        let (mut args, streams) = match built {
+            Ok(built) => built,
+            Err(e) => {
+                remove_files(&temporary);
+                return Err(e);
+            }
+        };
+        args.extend(["-progress".to_string(), "pipe:1".to_string(), "-nostats".to_string()]);
+        args.push(output.to_string_lossy().to_string());
+
+        let result = self
+            .run_ffmpeg(&conversion_id, &args, 0.0, total, &streams, &events)
+            .await;
+        remove_files(&temporary);
+        if let Err(e) = result {
+            let _ = std::fs::remove_file(&output);
+            return Err(e);
+        }
+
+        let output = output.to_string_lossy().to_string();
+        events.convert_progress(ConvertProgress {
+            conversion_id,
+            status: "completed".to_string(),
+            progress: 100.0,
+            fps: None,
+            speed: None,
+            eta: None,
+            output: Some(output.clone()),
+            streams,
+        });
+        Ok(ConcatResult {
+            output,
+            lossless: reason.is_none(),
+            reason,
+            chapters,
+        })
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+// The streams that are joined; cover art is left out
+fn joined_streams<'a>(media: &'a MediaInfo, kinds: &[&str]) -> Vec<&'a StreamInfo> {
+    media
+        .streams
+        .iter()
+        .filter(|s| kinds.contains(&s.kind.as_str()) && !s.attached_pic)
+        .collect()
+}
+
+// Everything the concat demuxer needs to be identical across files
+fn signature(stream: &StreamInfo) -> String {
+    let codec = stream.codec.as_deref().unwrap_or("?");
+    match stream.kind.as_str() {
+        "video" => format!(
+            "vídeo {} {}x{} {} {}fps",
+            codec,
+            stream.width.unwrap_or(0),
+            stream.height.unwrap_or(0),
+            stream.pixel_format.as_deref().unwrap_or("?"),
+            decimal_arg(stream.fps.unwrap_or(0.0))
+        ),
+        "audio" => format!(
+            "áudio {} {} Hz {} canais",
+            codec,
+            stream.sample_rate.unwrap_or(0),
+            stream.channels.unwrap_or(0)
+        ),
+        _ => format!("legenda {}", codec),
+    }
+}
+
+// Why the files can't be joined as they are, if they can't
+fn mismatch(paths: &[String], inputs: &[MediaInfo], format: &str, kinds: &[&str]) -> Option<String> {
+    let layouts: Vec<Vec<&StreamInfo>> = inputs.iter().map(|m| joined_streams(m, kinds)).collect();
+
+    for (path, layout) in paths.iter().zip(&layouts) {
+        for stream in layout {
+            let codec = stream.codec.as_deref().unwrap_or("?");
+            if !can_copy(format, &stream.kind, codec) {
+                return Some(format!("{}: {} não pode ser copiado para {}", file_name(path), codec, format));
+            }
+        }
+    }
+
+    let first: Vec<String> = layouts[0].iter().map(|s| signature(s)).collect();
+    for (path, layout) in paths.iter().zip(&layouts).skip(1) {
+        let current: Vec<String> = layout.iter().map(|s| signature(s)).collect();
+        if current.len() != first.len() {
+            return Some(format!("{}: faixas diferentes das do primeiro arquivo", file_name(path)));
+        }
+        if let Some((expected, found)) = first.iter().zip(&current).find(|(a, b)| a != b) {
+            return Some(format!("{}: {} em vez de {}", file_name(path), found, expected));
+        }
+    }
+    None
+}
+
+fn lossless_args(
+    list_path: &Path,
+    metadata_path: &Path,
+    chapters: &[ChapterInfo],
+    first: &MediaInfo,
+    kinds: &[&str],
+) -> (Vec<String>, Vec<StreamPlan>) {
+    let mut args: Vec<String> = ["-f", "concat", "-safe", "0", "-i"].map(String::from).to_vec();
+    args.push(list_path.to_string_lossy().to_string());
+    if !chapters.is_empty() {
+        args.extend(["-i".to_string(), metadata_path.to_string_lossy().to_string()]);
+    }
+    args.push("-y".to_string());
+    for kind in kinds {
+        // "V" skips cover art
+        let specifier = match *kind {
+            "video" => "0:V?",
+            "audio" => "0:a?",
+            _ => "0:s?",
+        };
+        args.extend(["-map".to_string(), specifier.to_string()]);
+    }
+    if !chapters.is_empty() {
+        args.extend(["-map_chapters".to_string(), "1".to_string()]);
+    }
+    args.extend(["-c".to_string(), "copy".to_string()]);
+
+    let streams = joined_streams(first, kinds)
+        .into_iter()
+        .map(|s| StreamPlan {
+            kind: s.kind.clone(),
+            source_codec: s.codec.clone(),
+            action: "copy".to_string(),
+            encoder: None,
+        })
+        .collect();
+    (args, streams)
+}
+
+// One video and one audio stream per input, brought to the first input's
+// format and joined with the concat filter. Inputs without video or audio
+// get black frames or silence for their whole duration
+fn normalized_args(
+    paths: &[String],
+    inputs: &[MediaInfo],
+    format: &str,
+    audio_only: bool,
+    metadata_path: &Path,
+    chapters: &[ChapterInfo],
+) -> Result<(Vec<String>, Vec<StreamPlan>), String> {
+    let target_video = if audio_only {
+        None
+    } else {
+        inputs.iter().find_map(|m| m.video())
+    };
+    let target_audio = inputs.iter().find_map(|m| m.audio());
+    if target_video.is_none() && target_audio.is_none() {
+        return Err("Nenhuma faixa de áudio ou vídeo para juntar".to_string());
+    }
+
+    // Encoders want even sizes
+    let even = |side: Option<u32>, default: u32| side.map(|s| s - s % 2).filter(|s| *s > 0).unwrap_or(default);
+    let width = even(target_video.and_then(|v| v.width), 1280);
+    let height = even(target_video.and_then(|v| v.height), 720);
+    let fps = decimal_arg(target_video.and_then(|v| v.fps).unwrap_or(30.0));
+    let rate = target_audio.and_then(|a| a.sample_rate).unwrap_or(48000);
+    let layout = if target_audio.and_then(|a| a.channels) == Some(1) {
+        "mono"
+    } else {
+        "stereo"
+    };
+
+    let mut filters = Vec::new();
+    let mut links = String::new();
+    for (i, (path, media)) in paths.iter().zip(inputs).enumerate() {
+        let duration = || {
+            media
+                .duration
+                .map(decimal_arg)
+                .ok_or_else(|| format!("Duração desconhecida de {}", file_name(path)))
+        };
+        if target_video.is_some() {
+            filters.push(match media.video() {
+                Some(_) => format!(
+                    "[{i}:V:0]scale={width}:{height}:force_original_aspect_ratio=decrease,\
+                     pad={width}:{height}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={fps},format=yuv420p[v{i}]"
+                ),
+                None => format!(
+                    "color=c=black:s={width}x{height}:r={fps}:d={},format=yuv420p,setsar=1[v{i}]",
+                    duration()?
+                ),
+            });
+            links.push_str(&format!("[v{i}]"));
+        }
+        if target_audio.is_some() {
+            filters.push(match media.audio() {
+                Some(_) => format!("[{i}:a:0]aresample={rate},aformat=channel_layouts={layout}[a{i}]"),
+                None => format!("aevalsrc=0:c={layout}:s={rate}:d={}[a{i}]", duration()?),
+            });
+            links.push_str(&format!("[a{i}]"));
+        }
+    }
+    filters.push(format!(
+        "{links}concat=n={}:v={}:a={}{}{}",
+        inputs.len(),
+        target_video.is_some() as u8,
+        target_audio.is_some() as u8,
+        if target_video.is_some() { "[v]" } else { "" },
+        if target_audio.is_some() { "[a]" } else { "" },
+    ));
+
+    let mut args = Vec::new();
+    for path in paths {
+        args.extend(["-i".to_string(), path.clone()]);
+    }
+    if !chapters.is_empty() {
+        args.extend(["-i".to_string(), metadata_path.to_string_lossy().to_string()]);
+    }
+    args.extend(["-y".to_string(), "-filter_complex".to_string(), filters.join(";")]);
+    if target_video.is_some() {
+        args.extend(["-map".to_string(), "[v]".to_string()]);
+    }
+    if target_audio.is_some() {
+        args.extend(["-map".to_string(), "[a]".to_string()]);
+    }
+    if !chapters.is_empty() {
+        args.extend(["-map_chapters".to_string(), paths.len().to_string()]);
+    }
+
+    // Planned like a conversion of the reference streams
+    let mut reference = inputs[0].clone();
+    reference.streams = [target_video, target_audio].into_iter().flatten().cloned().collect();
+    let plan = reencode_args(format, &ConversionOptions::default(), Some(&reference))?;
+    args.extend(plan.args);
+    Ok((args, plan.streams))
+}
+
+fn chapter_list(paths: &[String], durations: &[Option<f64>]) -> Result<Vec<ChapterInfo>, String> {
+    let mut start = 0.0;
+    paths
+        .iter()
+        .zip(durations)
+        .map(|(path, duration)| {
+            let duration = duration
+                .ok_or_else(|| format!("Duração desconhecida de {}; não é possível criar capítulos", file_name(path)))?;
+            let chapter = ChapterInfo {
+                start,
+                end: start + duration,
+                title: Path::new(path).file_stem().map(|s| s.to_string_lossy().to_string()),
+            };
+            start += duration;
+            Ok(chapter)
+        })
+        .collect()
+}
+
+// ffmpeg's FFMETADATA format, read as an extra input
+fn write_chapters(path: &Path, chapters: &[ChapterInfo]) -> Result<(), String> {
+    let mut content = ";FFMETADATA1\n".to_string();
+    for chapter in chapters {
+        content.push_str(&format!(
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            (chapter.start * 1000.0).round() as u64,
+            (chapter.end * 1000.0).round() as u64,
+            escape_metadata(chapter.title.as_deref().unwrap_or(""))
+        ));
+    }
+    std::fs::write(path, content).map_err(|e| format!("Falha ao criar capítulos: {}", e))
+}
+
+fn escape_metadata(value: &str) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn remove_files(files: &[PathBuf]) {
+    for file in files {
+        let _ = std::fs::remove_file(file);
+    }
+}
@@ -0,0 +1,250 @@
+use crate::services::Services;
+use crate::ytdlp::{check_url, DownloadProgress};
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use tokio::sync::{broadcast, oneshot};
+
+// Localhost-only HTTP API for browser extensions and scripts. Every route
+// except /health needs the token, either as "Authorization: Bearer <token>"
+// or, for WebSocket clients that can't set headers, as ?token=<token>.
+//
+//   GET    /api/v1/health
+//   GET    /api/v1/video-info?url=...
+//   GET    /api/v1/presets
+//   GET    /api/v1/jobs
+//   POST   /api/v1/jobs          {"url": "...", "preset": "audio"}
+//   DELETE /api/v1/jobs/{id}
+//   POST   /api/v1/convert       {"input_path": "...", "output_format": "mp3"}
+//   GET    /api/v1/ws            pushes DownloadProgress messages
+pub struct ControlApi {
+    port: u16,
+    token: String,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Clone)]
+struct ApiState {
+    token: String,
+    services: Services,
+}
+
+// Only a URL and a preset: the folder and post-download actions come from the
+// user's own presets, so a token holder can't write elsewhere or run programs.
+// The URL itself is checked in Services::enqueue_url
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EnqueueBody {
+    url: String,
+    preset: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ConvertBody {
+    input_path: String,
+    output_format: String,
+}
+
+impl ControlApi {
+    pub async fn start(port: u16, token: String, services: Services) -> Result<Self, String> {
+        if token.len() < 16 {
+            return Err("Token da API muito curto".to_string());
+        }
+
+        let state = ApiState {
+            token: token.clone(),
+            services,
+        };
+        let protected = Router::new()
+            .route("/video-info", get(video_info))
+            .route("/presets", get(presets))
+            .route("/jobs", get(list_jobs).post(enqueue))
+            .route("/jobs/{id}", delete(cancel))
+            .route("/convert", post(convert))
+            .route("/ws", get(progress_socket))
+            .route_layer(middleware::from_fn_with_state(state.clone(), require_token));
+
+        let app = Router::new()
+            .route("/health", get(health))
+            .merge(protected)
+            .with_state(state);
+        let app = Router::new().nest("/api/v1", app);
+
+        // A server being replaced may take a moment to release the port
+        let mut attempts = 0;
+        let listener = loop {
+            match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+                Ok(listener) => break listener,
+                Err(e) if e.kind() == std::io::ErrorKind::AddrInUse && attempts < 10 => {
+                    attempts += 1;
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                }
+                Err(e) => return Err(format!("Falha ao abrir a porta {}: {}", port, e)),
+            }
+        };
+
+        let (shutdown, signal) = oneshot::channel::<()>();
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app)
+                .with_graceful_shutdown(async {
+                    let _ = signal.await;
+                })
+                .await;
+        });
+
+        Ok(Self {
+            port,
+            token,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+}
+
+impl Drop for ControlApi {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+// 32 random bytes, hex encoded
+pub fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Falha ao gerar token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn error(status: StatusCode, message: impl Into<String>) -> Response {
+    (status, Json(json!({ "error": message.into() }))).into_response()
+}
+
+fn to_response<T: serde::Serialize>(result: Result<T, String>) -> Response {
+    match result {
+        Ok(value) => Json(value).into_response(),
+        Err(e) => error(StatusCode::BAD_REQUEST, e),
+    }
+}
+
+// Compares without returning early, so response timing doesn't leak the token
+fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+async fn require_token(
+    State(state): State<ApiState>,
+    Query(query): Query<HashMap<String, String>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(String::from);
+    let given = bearer.or_else(|| query.get("token").cloned()).unwrap_or_default();
+
+    if token_matches(&state.token, &given) {
+        next.run(request).await
+    } else {
+        error(StatusCode::UNAUTHORIZED, "Token inválido")
+    }
+}
+
+async fn health() -> Response {
+    Json(json!({ "app": "jara", "version": env!("CARGO_PKG_VERSION") })).into_response()
+}
+
+async fn video_info(
+    State(state): State<ApiState>,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    let Some(url) = query.get("url") else {
+        return error(StatusCode::BAD_REQUEST, "Parâmetro obrigatório ausente: url");
+    };
+    if let Err(e) = check_url(url) {
+        return error(StatusCode::BAD_REQUEST, e);
+    }
+
+    match state.services.ytdlp().await {
+        Ok(ytdlp) => to_response(ytdlp.get_video_info(url).await),
+        Err(e) => error(StatusCode::SERVICE_UNAVAILABLE, e),
+    }
+}
+
+async fn presets(State(state): State<ApiState>) -> Response {
+    Json(state.services.list_presets()).into_response()
+}
+
+async fn list_jobs(State(state): State<ApiState>) -> Response {
+    Json(state.services.downloads.list_jobs()).into_response()
+}
+
+async fn enqueue(State(state): State<ApiState>, Json(body): Json<EnqueueBody>) -> Response {
+    let result = state.services.enqueue_url(&body.url, body.preset.as_deref());
+    to_response(result.map(|id| json!({ "id": id })))
+}
+
+async fn cancel(State(state): State<ApiState>, Path(id): Path<String>) -> Response {
+    if state.services.downloads.get_job(&id).is_none() {
+        return error(StatusCode::NOT_FOUND, "Download não encontrado");
+    }
+    to_response(state.services.downloads.cancel(&id).await)
+}
+
+async fn convert(State(state): State<ApiState>, Json(body): Json<ConvertBody>) -> Response {
+    to_response(
+        state
+            .services
+            .convert_by_extension(&body.input_path, &body.output_format)
+            .await,
+    )
+}
+
+async fn progress_socket(State(state): State<ApiState>, upgrade: WebSocketUpgrade) -> Response {
+    let events = state.services.downloads.subscribe();
+    upgrade.on_upgrade(move |socket| forward_progress(socket, events))
+}
+
+async fn forward_progress(mut socket: WebSocket, mut events: broadcast::Receiver<DownloadProgress>) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(progress) => {
+                    let Ok(text) = serde_json::to_string(&progress) else { continue };
+                    if socket.send(Message::Text(text.into())).await.is_err() {
+                        break;
+                    }
+                }
+                // A slow client missed some updates; the next ones still apply
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                _ => {}
+            },
+        }
+    }
+}
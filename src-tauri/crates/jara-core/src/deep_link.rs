@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use crate::ytdlp::check_url;
+use url::Url;
+
+const MAX_LINK_LENGTH: usize = 4096;
+
+// What a jara:// link asks the app to do:
+//   jara://download?url=<video url>[&preset=<name>]
+//   jara://convert?path=<absolute file path>&format=<extension>
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum DeepLinkAction {
+    Download { url: String, preset: Option<String> },
+    Convert { path: String, format: String },
+}
+
+// Links come from other programs, so anything unexpected is rejected
+// instead of ignored: unknown actions, unknown or repeated parameters,
+// non-http URLs, relative paths, odd format names and outputs that would
+// overwrite a file
+pub fn parse_deep_link(link: &str) -> Result<DeepLinkAction, String> {
+    if link.len() > MAX_LINK_LENGTH {
+        return Err("Link muito longo".to_string());
+    }
+
+    let parsed = Url::parse(link).map_err(|_| "Link inválido".to_string())?;
+    if parsed.scheme() != "jara" {
+        return Err(format!("Protocolo não suportado: {}", parsed.scheme()));
+    }
+
+    // "jara://download?..." puts the action in the host, "jara:download?..." in the path
+    let action = parsed
+        .host_str()
+        .filter(|h| !h.is_empty())
+        .map(String::from)
+        .unwrap_or_else(|| parsed.path().trim_matches('/').to_string());
+
+    let mut params: HashMap<String, String> = HashMap::new();
+    for (key, value) in parsed.query_pairs() {
+        if params.insert(key.to_string(), value.to_string()).is_some() {
+            return Err(format!("Parâmetro repetido: {}", key));
+        }
+    }
+
+    match action.as_str() {
+        "download" => {
+            check_params(&params, &["url", "preset"])?;
+
+            let url = params.remove("url").ok_or("Parâmetro obrigatório ausente: url")?;
+            let url = check_url(&url)?;
+
+            let preset = params.remove("preset");
+            if let Some(preset) = &preset {
+                if !is_safe_name(preset) {
+                    return Err(format!("Preset inválido: {}", preset));
+                }
+            }
+
+            Ok(DeepLinkAction::Download {
+                url,
+                preset,
+            })
+        }
+        "convert" => {
+            check_params(&params, &["path", "format"])?;
+
+            let path = params.remove("path").ok_or("Parâmetro obrigatório ausente: path")?;
+            let format = params
+                .remove("format")
+                .ok_or("Parâmetro obrigatório ausente: format")?
+                .to_lowercase();
+
+            let input = PathBuf::from(&path);
+            if !input.is_absolute() {
+                return Err("O caminho do arquivo deve ser absoluto".to_string());
+            }
+            if !input.is_file() {
+                return Err("Arquivo de entrada não encontrado".to_string());
+            }
+            if format.is_empty() || format.len() > 5 || !format.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(format!("Formato inválido: {}", format));
+            }
+            // Converters write next to the input and overwrite what is there
+            let output = input.with_extension(&format);
+            if output.exists() {
+                return Err(format!("O arquivo de saída já existe: {}", output.to_string_lossy()));
+            }
+
+            Ok(DeepLinkAction::Convert { path, format })
+        }
+        "" => Err("Link sem ação".to_string()),
+        other => Err(format!("Ação desconhecida: {}", other)),
+    }
+}
+
+fn check_params(params: &HashMap<String, String>, allowed: &[&str]) -> Result<(), String> {
+    match params.keys().find(|k| !allowed.contains(&k.as_str())) {
+        Some(unknown) => Err(format!("Parâmetro desconhecido: {}", unknown)),
+        None => Ok(()),
+    }
+}
+
+fn is_safe_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ' '))
+}
@@ -64,7 +64,7 @@ impl DocumentConverter {
         let raw_pixels = rgb_img.as_raw().clone();
         
         // Create image for PDF
-        let pdf_image = Image::try_from(ImageXObject {
+        let pdf_image = Image::from(ImageXObject {
             width: Px(img.width() as usize),
             height: Px(img.height() as usize),
             color_space: ColorSpace::Rgb,
@@ -74,7 +74,7 @@ impl DocumentConverter {
             image_filter: None,
             smask: None,
             clipping_bbox: None,
-        }).map_err(|e| format!("Falha ao criar imagem PDF: {:?}", e))?;
+        });
         
         // Add image to PDF
         pdf_image.add_to_layer(
@@ -0,0 +1,290 @@
+use crate::events::EventSink;
+use crate::ffmpeg::FFmpeg;
+use crate::hooks::{HookContext, HookResult, PostProcessor};
+use crate::ytdlp::{DownloadProgress, DownloadRequest, OutputFile, YtDlp};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, Mutex as TokioMutex, Semaphore};
+
+const MAX_CONCURRENT_DOWNLOADS: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub request: DownloadRequest,
+    // "queued", then the DownloadProgress statuses up to "completed", "error" or "cancelled"
+    pub status: String,
+    pub progress: f64,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub finished_at: Option<i64>,
+    // What the download produced, once it completes
+    #[serde(default)]
+    pub files: Vec<OutputFile>,
+    // Outcome of each post-download action that ran
+    #[serde(default)]
+    pub hooks: Vec<HookResult>,
+}
+
+type ProgressSink = Arc<dyn Fn(DownloadProgress) + Send + Sync>;
+
+// Queues download requests and runs a limited number of them at a time
+#[derive(Clone)]
+pub struct DownloadManager {
+    ytdlp: Arc<TokioMutex<YtDlp>>,
+    ffmpeg: Arc<FFmpeg>,
+    post_processor: PostProcessor,
+    jobs: Arc<Mutex<Vec<Job>>>,
+    slots: Arc<Semaphore>,
+    on_progress: ProgressSink,
+    // Copies of every progress update for extra listeners such as the control API
+    events: broadcast::Sender<DownloadProgress>,
+}
+
+impl DownloadManager {
+    pub fn new<S>(
+        ytdlp: Arc<TokioMutex<YtDlp>>,
+        ffmpeg: Arc<FFmpeg>,
+        post_processor: PostProcessor,
+        sink: S,
+    ) -> Self
+    where
+        S: EventSink + 'static,
+    {
+        let (events, _) = broadcast::channel(256);
+        let events_sender = events.clone();
+
+        Self {
+            ytdlp,
+            ffmpeg,
+            post_processor,
+            jobs: Arc::new(Mutex::new(Vec::new())),
+            slots: Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS)),
+            on_progress: Arc::new(move |progress: DownloadProgress| {
+                sink.download_progress(progress.clone());
+                let _ = events_sender.send(progress);
+            }),
+            events,
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DownloadProgress> {
+        self.events.subscribe()
+    }
+
+    pub fn enqueue(&self, request: DownloadRequest) -> String {
+        let id = new_job_id();
+
+        self.jobs.lock().unwrap().push(Job {
+            id: id.clone(),
+            request: request.clone(),
+            status: "queued".to_string(),
+            progress: 0.0,
+            error: None,
+            created_at: unix_now(),
+            finished_at: None,
+            files: Vec::new(),
+            hooks: Vec::new(),
+        });
+        (self.on_progress)(status_progress(&id, "queued", 0.0));
+
+        let manager = self.clone();
+        let job_id = id.clone();
+        tokio::spawn(async move {
+            manager.run(job_id, request).await;
+        });
+
+        id
+    }
+
+    pub fn list_jobs(&self) -> Vec<Job> {
+        self.jobs.lock().unwrap().clone()
+    }
+
+    pub fn get_job(&self, id: &str) -> Option<Job> {
+        self.jobs.lock().unwrap().iter().find(|j| j.id == id).cloned()
+    }
+
+    pub async fn cancel(&self, id: &str) -> Result<(), String> {
+        // Jobs still waiting for a slot are dropped before they start
+        let was_queued = self.update_job(id, |job| {
+            if job.status == "queued" {
+                job.status = "cancelled".to_string();
+                job.finished_at = Some(unix_now());
+                true
+            } else {
+                false
+            }
+        });
+
+        if was_queued == Some(true) {
+            (self.on_progress)(status_progress(id, "cancelled", 0.0));
+            return Ok(());
+        }
+
+        let ytdlp = self.ytdlp.lock().await.clone();
+        ytdlp.cancel_download(id).await
+    }
+
+    // Removes finished jobs from the list
+    pub fn clear_finished(&self) {
+        self.jobs
+            .lock()
+            .unwrap()
+            .retain(|j| !matches!(j.status.as_str(), "completed" | "error" | "cancelled"));
+    }
+
+    async fn run(&self, id: String, request: DownloadRequest) {
+        let permit = match self.slots.clone().acquire_owned().await {
+            Ok(permit) => permit,
+            Err(_) => return,
+        };
+
+        if self.get_job(&id).map(|j| j.status == "cancelled").unwrap_or(true) {
+            return;
+        }
+
+        let ytdlp = {
+            let mut ytdlp = self.ytdlp.lock().await;
+            if let Err(e) = ytdlp.ensure_ytdlp_exists().await {
+                self.finish(&id, Err(e));
+                return;
+            }
+            ytdlp.clone()
+        };
+
+        self.update_job(&id, |job| job.status = "downloading".to_string());
+
+        let jobs = self.jobs.clone();
+        let on_progress = self.on_progress.clone();
+        let result = ytdlp
+            .start_download(id.clone(), &request, move |progress: DownloadProgress| {
+                if let Some(job) = jobs
+                    .lock()
+                    .unwrap()
+                    .iter_mut()
+                    .find(|j| j.id == progress.download_id)
+                {
+                    job.status = progress.status.clone();
+                    job.progress = progress.progress;
+                }
+                on_progress(progress);
+            })
+            .await;
+
+        // Post-download actions don't hold up other downloads
+        drop(permit);
+        let result = match result {
+            Ok(mut paths) => {
+                if !request.post_actions.is_empty() {
+                    paths = self.run_post_actions(&id, &request, paths).await;
+                }
+                Ok(OutputFile::inspect_all(&paths, &self.ffmpeg).await)
+            }
+            Err(e) => Err(e),
+        };
+
+        self.finish(&id, result);
+    }
+
+    // Runs the actions on each downloaded file and returns where the files
+    // ended up
+    async fn run_post_actions(&self, id: &str, request: &DownloadRequest, paths: Vec<String>) -> Vec<String> {
+        if paths.is_empty() {
+            self.update_job(id, |job| {
+                job.hooks = vec![HookResult {
+                    action: "post_actions".to_string(),
+                    success: false,
+                    output: None,
+                    error: Some("O yt-dlp não informou o arquivo baixado".to_string()),
+                }]
+            });
+            return paths;
+        }
+
+        self.update_job(id, |job| job.status = "processing".to_string());
+        (self.on_progress)(status_progress(id, "processing", 100.0));
+
+        let mut hooks = Vec::new();
+        let mut final_paths = Vec::new();
+        for path in paths {
+            let context = HookContext {
+                id: id.to_string(),
+                url: request.url.clone(),
+                path: path.into(),
+            };
+            let (results, final_path) = self.post_processor.run(&request.post_actions, &context).await;
+            final_paths.push(final_path.to_string_lossy().to_string());
+            hooks.extend(results);
+        }
+
+        self.update_job(id, |job| job.hooks = hooks);
+        final_paths
+    }
+
+    fn finish(&self, id: &str, result: Result<Vec<OutputFile>, String>) {
+        let cancelled = self
+            .get_job(id)
+            .map(|j| j.status == "cancelled")
+            .unwrap_or(false);
+
+        match result {
+            Ok(files) => {
+                self.update_job(id, |job| {
+                    job.status = "completed".to_string();
+                    job.progress = 100.0;
+                    job.finished_at = Some(unix_now());
+                    job.files = files.clone();
+                });
+                (self.on_progress)(DownloadProgress::completed(id, files));
+            }
+            Err(_) if cancelled => {}
+            Err(e) => {
+                let status = if e == "Download cancelado" { "cancelled" } else { "error" };
+                self.update_job(id, |job| {
+                    job.status = status.to_string();
+                    job.error = (status == "error").then(|| e.clone());
+                    job.finished_at = Some(unix_now());
+                });
+                (self.on_progress)(status_progress(id, status, 0.0));
+            }
+        }
+    }
+
+    fn update_job<T>(&self, id: &str, f: impl FnOnce(&mut Job) -> T) -> Option<T> {
+        self.jobs.lock().unwrap().iter_mut().find(|j| j.id == id).map(f)
+    }
+}
+
+fn status_progress(id: &str, status: &str, progress: f64) -> DownloadProgress {
+    DownloadProgress {
+        download_id: id.to_string(),
+        status: status.to_string(),
+        progress,
+        speed: None,
+        eta: None,
+        filename: None,
+        elapsed: None,
+        downloaded_bytes: None,
+        files: Vec::new(),
+    }
+}
+
+fn new_job_id() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!("job-{}-{}", millis, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
@@ -0,0 +1,854 @@
+use crate::media_info::{MediaInfo, StreamInfo};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+// Encoding parameters for FFmpeg conversions. Anything left unset keeps the
+// output format's default recipe
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConversionOptions {
+    // "h264", "hevc", "vp9", "av1" or "copy"
+    pub video_codec: Option<String>,
+    // Constant quality, lower is better. Can't be combined with video_bitrate
+    pub crf: Option<u32>,
+    // kbit/s
+    pub video_bitrate: Option<u32>,
+    // x264/x265 speed preset, e.g. "fast" or "slow"
+    pub encoder_preset: Option<String>,
+    // Setting only one side keeps the aspect ratio
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<f64>,
+    // "aac", "mp3", "opus", "vorbis", "flac", "pcm" or "copy"
+    pub audio_codec: Option<String>,
+    // kbit/s
+    pub audio_bitrate: Option<u32>,
+    // Hz
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    // Added to the output as selectable tracks
+    pub subtitle_files: Vec<SubtitleFile>,
+    // Drawn into the picture, which means re-encoding the video
+    pub burn_subtitle: Option<BurnSubtitle>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubtitleFile {
+    // .srt, .vtt or .ass
+    pub path: String,
+    // ISO 639 code such as "por" or "en"
+    pub language: Option<String>,
+    // Shown in the players' track menu
+    pub title: Option<String>,
+}
+
+// One subtitle track of the input, or a file, with its style
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BurnSubtitle {
+    // The index ffprobe gave one of the input's subtitle streams
+    pub stream: Option<u32>,
+    // .srt, .vtt or .ass
+    pub file: Option<String>,
+    pub font: Option<String>,
+    // In libass units, where .srt and .vtt pictures are 288 tall
+    pub font_size: Option<u32>,
+    // "#RRGGBB"
+    pub color: Option<String>,
+    // Border around the letters
+    pub outline: Option<u32>,
+    pub bold: bool,
+}
+
+// A named set of options saved in the settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversionPreset {
+    pub name: String,
+    #[serde(default)]
+    pub options: ConversionOptions,
+}
+
+// (name, encoder, highest CRF)
+const VIDEO_CODECS: &[(&str, &str, u32)] = &[
+    ("h264", "libx264", 51),
+    ("hevc", "libx265", 51),
+    ("vp9", "libvpx-vp9", 63),
+    ("av1", "libaom-av1", 63),
+];
+
+// (name, encoder)
+const AUDIO_CODECS: &[(&str, &str)] = &[
+    ("aac", "aac"),
+    ("mp3", "libmp3lame"),
+    ("opus", "libopus"),
+    ("vorbis", "libvorbis"),
+    ("flac", "flac"),
+    ("pcm", "pcm_s16le"),
+];
+
+const ENCODER_PRESETS: &[&str] = &[
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+];
+
+const SAMPLE_RATES: &[u32] = &[8000, 11025, 12000, 16000, 22050, 24000, 32000, 44100, 48000, 88200, 96000];
+
+// (extension, ffprobe codec) of the subtitle files that can be added or burned
+const SUBTITLE_FILES: &[(&str, &str)] = &[("srt", "subrip"), ("vtt", "webvtt"), ("ass", "ass"), ("ssa", "ass")];
+
+// Lossless codecs have no bitrate to choose
+const LOSSLESS_AUDIO: &[&str] = &["flac", "pcm"];
+
+// What each output format can hold. The first codec of each list is the one
+// used when the options need a re-encode and don't name a codec
+struct Container {
+    formats: &'static [&'static str],
+    // Empty for audio-only formats
+    video: &'static [&'static str],
+    audio: &'static [&'static str],
+    default_video: &'static str,
+    default_audio: &'static str,
+}
+
+const CONTAINERS: &[Container] = &[
+    Container {
+        formats: &["mp3"],
+        video: &[],
+        audio: &["mp3"],
+        default_video: "",
+        default_audio: "mp3",
+    },
+    Container {
+        formats: &["m4a", "aac"],
+        video: &[],
+        audio: &["aac"],
+        default_video: "",
+        default_audio: "aac",
+    },
+    Container {
+        formats: &["wav"],
+        video: &[],
+        audio: &["pcm"],
+        default_video: "",
+        default_audio: "pcm",
+    },
+    Container {
+        formats: &["flac"],
+        video: &[],
+        audio: &["flac"],
+        default_video: "",
+        default_audio: "flac",
+    },
+    Container {
+        formats: &["opus"],
+        video: &[],
+        audio: &["opus"],
+        default_video: "",
+        default_audio: "opus",
+    },
+    Container {
+        formats: &["ogg"],
+        video: &[],
+        audio: &["vorbis", "opus", "flac"],
+        default_video: "",
+        default_audio: "vorbis",
+    },
+    Container {
+        formats: &["mp4", "mov"],
+        video: &["h264", "hevc", "av1"],
+        audio: &["aac", "mp3", "opus"],
+        default_video: "h264",
+        default_audio: "aac",
+    },
+    Container {
+        formats: &["mkv"],
+        video: &["h264", "hevc", "vp9", "av1"],
+        audio: &["aac", "opus", "mp3", "vorbis", "flac", "pcm"],
+        default_video: "copy",
+        default_audio: "copy",
+    },
+    Container {
+        formats: &["webm"],
+        video: &["vp9", "av1"],
+        audio: &["opus", "vorbis"],
+        default_video: "vp9",
+        default_audio: "opus",
+    },
+];
+
+impl ConversionOptions {
+    fn changes_video(&self) -> bool {
+        self.crf.is_some()
+            || self.video_bitrate.is_some()
+            || self.encoder_preset.is_some()
+            || self.width.is_some()
+            || self.height.is_some()
+            || self.fps.is_some()
+            || self.burn_subtitle.is_some()
+    }
+
+    fn changes_audio(&self) -> bool {
+        self.audio_bitrate.is_some() || self.sample_rate.is_some() || self.channels.is_some()
+    }
+
+    // Checks the values on their own; encoding_args also checks them against
+    // the codecs and the output format
+    pub fn validate(&self) -> Result<(), String> {
+        if self.crf.is_some() && self.video_bitrate.is_some() {
+            return Err("Use CRF ou bitrate de vídeo, não os dois".to_string());
+        }
+        if let Some(bitrate) = self.video_bitrate {
+            if !(100..=100_000).contains(&bitrate) {
+                return Err(format!("Bitrate de vídeo inválido: {} kbit/s (use de 100 a 100000)", bitrate));
+            }
+        }
+        if let Some(preset) = &self.encoder_preset {
+            if !ENCODER_PRESETS.contains(&preset.as_str()) {
+                return Err(format!("Preset do encoder inválido: {} (use {})", preset, ENCODER_PRESETS.join(", ")));
+            }
+        }
+        for (name, side) in [("Largura", self.width), ("Altura", self.height)] {
+            if let Some(side) = side {
+                if !(16..=7680).contains(&side) || side % 2 != 0 {
+                    return Err(format!("{} inválida: {} (use um número par de 16 a 7680)", name, side));
+                }
+            }
+        }
+        if let Some(fps) = self.fps {
+            if !(fps > 0.0 && fps <= 240.0) {
+                return Err(format!("Taxa de quadros inválida: {} (use até 240)", fps));
+            }
+        }
+        if let Some(bitrate) = self.audio_bitrate {
+            if !(8..=512).contains(&bitrate) {
+                return Err(format!("Bitrate de áudio inválido: {} kbit/s (use de 8 a 512)", bitrate));
+            }
+        }
+        if let Some(rate) = self.sample_rate {
+            if !SAMPLE_RATES.contains(&rate) {
+                return Err(format!("Taxa de amostragem não suportada: {} Hz", rate));
+            }
+        }
+        if let Some(channels) = self.channels {
+            if !(1..=8).contains(&channels) {
+                return Err(format!("Número de canais inválido: {} (use de 1 a 8)", channels));
+            }
+        }
+        for file in &self.subtitle_files {
+            subtitle_file_codec(&file.path)?;
+            if let Some(language) = &file.language {
+                if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_lowercase()) {
+                    return Err(format!("Idioma inválido: {} (use um código como por ou en)", language));
+                }
+            }
+        }
+        if let Some(burn) = &self.burn_subtitle {
+            match (burn.stream, &burn.file) {
+                (Some(_), None) => {}
+                (None, Some(file)) => {
+                    subtitle_file_codec(file)?;
+                }
+                _ => {
+                    return Err(
+                        "Escolha uma faixa de legenda do arquivo ou um arquivo de legenda para queimar".to_string()
+                    );
+                }
+            }
+            if let Some(size) = burn.font_size {
+                if !(8..=200).contains(&size) {
+                    return Err(format!("Tamanho da fonte inválido: {} (use de 8 a 200)", size));
+                }
+            }
+            if let Some(outline) = burn.outline {
+                if outline > 10 {
+                    return Err(format!("Contorno inválido: {} (use de 0 a 10)", outline));
+                }
+            }
+            if let Some(color) = &burn.color {
+                parse_color(color)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// How a conversion handles each kind of stream, and the ffmpeg arguments
+// that do it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversionPlan {
+    pub args: Vec<String>,
+    pub streams: Vec<StreamPlan>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamPlan {
+    // "video", "audio" or "subtitle"
+    pub kind: String,
+    // The input stream's codec, as ffprobe names it
+    pub source_codec: Option<String>,
+    // "copy", "encode" or "drop"
+    pub action: String,
+    // The ffmpeg encoder, for "encode"
+    pub encoder: Option<String>,
+}
+
+// Image-based subtitles can't be turned into text ones
+const BITMAP_SUBTITLES: &[&str] = &["hdmv_pgs_subtitle", "dvd_subtitle", "dvb_subtitle"];
+
+// The codec arguments for an output format, between the input and the output.
+// With the probed input, streams the output can hold as they are are copied
+// instead of re-encoded; without it every stream gets the default recipe.
+// Subtitle files are extra inputs at the start. Burning a subtitle track
+// needs the input's path
+pub(crate) fn encoding_args(
+    input_path: Option<&str>,
+    output_format: &str,
+    options: &ConversionOptions,
+    media: Option<&MediaInfo>,
+) -> Result<ConversionPlan, String> {
+    options.validate()?;
+    let burn = match (&options.burn_subtitle, input_path) {
+        (Some(burn), Some(input_path)) => Some(burn_filter(burn, input_path, media)?),
+        (Some(_), None) => return Err("Esta operação não queima legendas".to_string()),
+        (None, _) => None,
+    };
+
+    // ffmpeg's default mapping takes one stream of each kind
+    let video = media.and_then(|m| m.video());
+    let audio = media.and_then(|m| m.audio());
+    let subtitle = media.and_then(|m| m.streams_of("subtitle").next());
+
+    let Some(container) = CONTAINERS.iter().find(|c| c.formats.contains(&output_format)) else {
+        // Unknown formats only get their streams copied
+        if *options != ConversionOptions::default() {
+            return Err(format!("Parâmetros de codificação não são suportados para {}", output_format));
+        }
+        return Ok(ConversionPlan {
+            args: vec!["-c".to_string(), "copy".to_string()],
+            streams: [video, audio, subtitle]
+                .into_iter()
+                .flatten()
+                .map(|s| stream_plan(s, "copy", None))
+                .collect(),
+        });
+    };
+
+    let mut plan = ConversionPlan {
+        args: Vec::new(),
+        streams: Vec::new(),
+    };
+    if container.video.is_empty() {
+        if options.video_codec.is_some() || options.changes_video() {
+            return Err(format!("{} é um formato só de áudio; parâmetros de vídeo não se aplicam", output_format));
+        }
+        if !options.subtitle_files.is_empty() {
+            return Err(format!("{} é um formato só de áudio; legendas não se aplicam", output_format));
+        }
+        plan.args.push("-vn".to_string());
+        plan.streams.extend(video.map(|s| stream_plan(s, "drop", None)));
+    } else {
+        let (args, action) = video_args(container, output_format, options, video, burn.as_deref())?;
+        plan.args.extend(args);
+        plan.streams.extend(video.map(|s| stream_plan(s, action.0, action.1)));
+    }
+
+    let (args, action) = audio_args(container, output_format, options, audio)?;
+    plan.args.extend(args);
+    plan.streams.extend(audio.map(|s| stream_plan(s, action.0, action.1)));
+
+    // A track burned into the picture isn't kept as a subtitle too
+    let burned_input = options.burn_subtitle.as_ref().is_some_and(|b| b.stream.is_some());
+    if !options.subtitle_files.is_empty() {
+        if burned_input {
+            plan.streams.extend(subtitle.map(|s| stream_plan(s, "drop", None)));
+        }
+        let subtitle = subtitle.filter(|_| !burned_input);
+        add_subtitle_files(&mut plan, output_format, &options.subtitle_files, media, subtitle)?;
+    } else if let Some(subtitle) = subtitle {
+        if container.video.is_empty() {
+            plan.streams.push(stream_plan(subtitle, "drop", None));
+        } else if burned_input {
+            plan.args.push("-sn".to_string());
+            plan.streams.push(stream_plan(subtitle, "drop", None));
+        } else {
+            let (args, action) = subtitle_args(output_format, subtitle);
+            plan.args.extend(args);
+            plan.streams.push(stream_plan(subtitle, action.0, action.1));
+        }
+    }
+    Ok(plan)
+}
+
+// Maps every stream explicitly, since ffmpeg's default mapping would only
+// take one subtitle from all the inputs, and sets each subtitle's codec and
+// tags on its own. The files' inputs go first
+fn add_subtitle_files(
+    plan: &mut ConversionPlan,
+    output_format: &str,
+    files: &[SubtitleFile],
+    media: Option<&MediaInfo>,
+    input_subtitle: Option<&StreamInfo>,
+) -> Result<(), String> {
+    let mut args = Vec::new();
+    for file in files {
+        if !Path::new(&file.path).exists() {
+            return Err(format!("Arquivo de legenda não encontrado: {}", file.path));
+        }
+        args.extend(["-i".to_string(), file.path.clone()]);
+    }
+
+    let pick = |stream: Option<&StreamInfo>, fallback: &str| match (media, stream) {
+        (_, Some(stream)) => Some(format!("0:{}", stream.index)),
+        (None, None) => Some(fallback.to_string()),
+        (Some(_), None) => None,
+    };
+    let video = pick(media.and_then(|m| m.video()), "0:V:0?");
+    let audio = pick(media.and_then(|m| m.audio()), "0:a:0?");
+    for map in [video, audio].into_iter().flatten() {
+        args.extend(["-map".to_string(), map]);
+    }
+
+    // The codec of each output subtitle and the file it came from. Without
+    // the probed streams only the files' subtitles are kept
+    let mut subtitles = Vec::new();
+    if let Some(source) = input_subtitle {
+        let action = source
+            .codec
+            .as_deref()
+            .map(|codec| subtitle_action(output_format, codec))
+            .unwrap_or(("encode", None));
+        if action.0 == "drop" {
+            plan.streams.push(stream_plan(source, "drop", None));
+        } else {
+            args.extend(["-map".to_string(), format!("0:{}", source.index)]);
+            subtitles.push((action, None));
+            plan.streams.push(stream_plan(source, action.0, action.1));
+        }
+    }
+    for (i, file) in files.iter().enumerate() {
+        // Text, so never dropped
+        let codec = subtitle_file_codec(&file.path)?;
+        let action = subtitle_action(output_format, codec);
+        args.extend(["-map".to_string(), format!("{}:0", i + 1)]);
+        subtitles.push((action, Some(file)));
+        plan.streams.push(StreamPlan {
+            kind: "subtitle".to_string(),
+            source_codec: Some(codec.to_string()),
+            action: action.0.to_string(),
+            encoder: action.1.map(String::from),
+        });
+    }
+
+    for (k, (action, file)) in subtitles.into_iter().enumerate() {
+        // An unknown codec is left to ffmpeg's default encoder for the format
+        let codec = if action.0 == "copy" { Some("copy") } else { action.1 };
+        if let Some(codec) = codec {
+            args.extend([format!("-c:s:{}", k), codec.to_string()]);
+        }
+        let Some(file) = file else {
+            continue;
+        };
+        if let Some(language) = &file.language {
+            args.extend([format!("-metadata:s:s:{}", k), format!("language={}", language)]);
+        }
+        if let Some(title) = &file.title {
+            args.extend([format!("-metadata:s:s:{}", k), format!("title={}", title)]);
+        }
+    }
+
+    args.append(&mut plan.args);
+    plan.args = args;
+    Ok(())
+}
+
+// The subtitles filter drawing `burn` onto the video of `input_path`
+fn burn_filter(burn: &BurnSubtitle, input_path: &str, media: Option<&MediaInfo>) -> Result<String, String> {
+    let mut filter = match (burn.stream, &burn.file) {
+        (Some(index), _) => {
+            let media = media.ok_or("Não foi possível ler as faixas de legenda do arquivo")?;
+            // The filter counts subtitle streams only
+            let (position, stream) = media
+                .streams_of("subtitle")
+                .enumerate()
+                .find(|(_, s)| s.index == index)
+                .ok_or_else(|| format!("A faixa {} não é uma legenda do arquivo", index))?;
+            if let Some(codec) = stream.codec.as_deref().filter(|c| BITMAP_SUBTITLES.contains(c)) {
+                return Err(format!(
+                    "Legendas em imagem ({}) não podem ser queimadas; escolha uma legenda em texto",
+                    codec
+                ));
+            }
+            format!("subtitles=filename={}:si={}", filter_value(input_path), position)
+        }
+        (None, Some(file)) => {
+            if !Path::new(file).exists() {
+                return Err(format!("Arquivo de legenda não encontrado: {}", file));
+            }
+            format!("subtitles=filename={}", filter_value(file))
+        }
+        (None, None) => {
+            return Err("Escolha uma faixa de legenda do arquivo ou um arquivo de legenda para queimar".to_string());
+        }
+    };
+
+    let mut style = Vec::new();
+    if let Some(font) = &burn.font {
+        style.push(format!("FontName={}", font));
+    }
+    if let Some(size) = burn.font_size {
+        style.push(format!("FontSize={}", size));
+    }
+    if let Some(color) = &burn.color {
+        // ASS colors are &HAABBGGRR
+        let [r, g, b] = parse_color(color)?;
+        style.push(format!("PrimaryColour=&H00{:02X}{:02X}{:02X}", b, g, r));
+    }
+    if let Some(outline) = burn.outline {
+        style.push(format!("Outline={}", outline));
+    }
+    if burn.bold {
+        style.push("Bold=1".to_string());
+    }
+    if !style.is_empty() {
+        filter.push_str(&format!(":force_style={}", filter_value(&style.join(","))));
+    }
+    Ok(filter)
+}
+
+fn subtitle_file_codec(path: &str) -> Result<&'static str, String> {
+    let extension = Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    SUBTITLE_FILES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, codec)| *codec)
+        .ok_or_else(|| format!("Formato de legenda não suportado: {} (use srt, vtt ou ass)", path))
+}
+
+fn parse_color(color: &str) -> Result<[u8; 3], String> {
+    let invalid = || format!("Cor inválida: {} (use #RRGGBB)", color);
+    let hex = color.strip_prefix('#').filter(|h| h.len() == 6).ok_or_else(invalid)?;
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+    Ok([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// Escapes a filter option value twice, for the option parser and then for
+// the filtergraph parser, so paths with ':' or ',' survive both
+fn filter_value(value: &str) -> String {
+    let escape = |text: &str, special: &str| {
+        text.chars().fold(String::new(), |mut escaped, c| {
+            if special.contains(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+    };
+    escape(&escape(value, "\\':"), "\\'[],;")
+}
+
+// mp3, flac, wav...
+pub(crate) fn is_audio_format(output_format: &str) -> bool {
+    CONTAINERS
+        .iter()
+        .any(|c| c.formats.contains(&output_format) && c.video.is_empty())
+}
+
+// Like encoding_args, but never copies video or audio, for cuts that must
+// land on exact frames
+pub(crate) fn reencode_args(
+    output_format: &str,
+    options: &ConversionOptions,
+    media: Option<&MediaInfo>,
+) -> Result<ConversionPlan, String> {
+    let container = CONTAINERS
+        .iter()
+        .find(|c| c.formats.contains(&output_format))
+        .ok_or_else(|| format!("Não é possível recodificar para {}", output_format))?;
+    if options.video_codec.as_deref() == Some("copy") || options.audio_codec.as_deref() == Some("copy") {
+        return Err("Esta operação precisa recodificar; copy não é permitido".to_string());
+    }
+
+    let encoded = |default: &'static str, codecs: &[&'static str]| {
+        if default == "copy" {
+            codecs[0].to_string()
+        } else {
+            default.to_string()
+        }
+    };
+    let mut options = options.clone();
+    if !container.video.is_empty() && options.video_codec.is_none() {
+        options.video_codec = Some(encoded(container.default_video, container.video));
+    }
+    if options.audio_codec.is_none() {
+        options.audio_codec = Some(encoded(container.default_audio, container.audio));
+    }
+    encoding_args(None, output_format, &options, media)
+}
+
+// Like encoding_args, but re-encodes the audio, in its own codec when the
+// output can hold it, for filters that change the sound. Video and subtitles
+// are copied when they can be
+pub(crate) fn audio_filter_args(output_format: &str, media: Option<&MediaInfo>) -> Result<ConversionPlan, String> {
+    let container = CONTAINERS
+        .iter()
+        .find(|c| c.formats.contains(&output_format))
+        .ok_or_else(|| format!("Não é possível recodificar para {}", output_format))?;
+    let source = media.and_then(|m| m.audio());
+    if media.is_some() && source.is_none() {
+        return Err("O arquivo não tem áudio".to_string());
+    }
+
+    let audio_codec = source
+        .and_then(|s| s.codec.as_deref())
+        .map(|codec| if codec.starts_with("pcm_") { "pcm" } else { codec })
+        .filter(|codec| container.audio.contains(codec));
+    let encoded = match (audio_codec, container.default_audio) {
+        (Some(codec), _) => codec,
+        (None, "copy") => container.audio[0],
+        (None, default) => default,
+    };
+    // Filters like loudnorm resample to 192 kHz; keep the source rate, except
+    // for Opus, which is always 48 kHz
+    let sample_rate = match source.and_then(|s| s.sample_rate) {
+        Some(rate) if encoded != "opus" && SAMPLE_RATES.contains(&rate) => rate,
+        _ => 48000,
+    };
+    let options = ConversionOptions {
+        audio_codec: audio_codec.map(String::from),
+        sample_rate: Some(sample_rate),
+        ..ConversionOptions::default()
+    };
+    encoding_args(None, output_format, &options, media)
+}
+
+// (action, encoder)
+type StreamAction = (&'static str, Option<&'static str>);
+
+fn stream_plan(source: &StreamInfo, action: &str, encoder: Option<&str>) -> StreamPlan {
+    StreamPlan {
+        kind: source.kind.clone(),
+        source_codec: source.codec.clone(),
+        action: action.to_string(),
+        encoder: encoder.map(String::from),
+    }
+}
+
+// Whether a stream can go into the output format without re-encoding
+pub(crate) fn can_copy(output_format: &str, kind: &str, codec: &str) -> bool {
+    match (output_format, kind) {
+        ("mkv", "subtitle") => codec != "mov_text",
+        ("mkv", _) => true,
+        ("mp4" | "mov", "video") => ["h264", "hevc", "av1", "vp9", "mpeg4"].contains(&codec),
+        ("mp4" | "mov", "audio") => ["aac", "mp3", "ac3", "eac3", "opus", "alac", "flac"].contains(&codec),
+        ("mp4" | "mov", "subtitle") => codec == "mov_text",
+        ("webm", "video") => ["vp8", "vp9", "av1"].contains(&codec),
+        ("webm", "audio") => ["opus", "vorbis"].contains(&codec),
+        ("webm", "subtitle") => codec == "webvtt",
+        ("m4a", "audio") => ["aac", "alac"].contains(&codec),
+        ("wav", "audio") => codec.starts_with("pcm_"),
+        ("ogg", "audio") => ["vorbis", "opus", "flac"].contains(&codec),
+        // mp3, aac, flac and opus hold their own codec
+        (format, "audio") => codec == format,
+        _ => false,
+    }
+}
+
+// Copies when nothing asks for a re-encode and the output can hold the
+// source codec. Without a probed codec, falls back to the format's default
+fn should_copy(
+    requested: Option<&str>,
+    changes: bool,
+    default: &str,
+    output_format: &str,
+    source: Option<&StreamInfo>,
+) -> Result<bool, String> {
+    let source_codec = source.and_then(|s| s.codec.as_deref());
+    match requested {
+        Some("copy") => match (source, source_codec) {
+            (Some(source), Some(codec)) if !can_copy(output_format, &source.kind, codec) => Err(format!(
+                "{} não aceita {} {} sem recodificar",
+                output_format,
+                if source.kind == "video" { "vídeo" } else { "áudio" },
+                codec
+            )),
+            _ => Ok(true),
+        },
+        Some(_) => Ok(false),
+        None if changes => Ok(false),
+        None => Ok(match (source, source_codec) {
+            (Some(source), Some(codec)) => can_copy(output_format, &source.kind, codec),
+            _ => default == "copy",
+        }),
+    }
+}
+
+fn video_args(
+    container: &Container,
+    output_format: &str,
+    options: &ConversionOptions,
+    source: Option<&StreamInfo>,
+    burn: Option<&str>,
+) -> Result<(Vec<String>, StreamAction), String> {
+    let requested = options.video_codec.as_deref();
+    if should_copy(requested, options.changes_video(), container.default_video, output_format, source)? {
+        if burn.is_some() {
+            return Err("Queimar legendas exige recodificar o vídeo".to_string());
+        }
+        if options.changes_video() {
+            return Err("Copiar o vídeo não permite mudar qualidade, tamanho ou fps".to_string());
+        }
+        return Ok((vec!["-c:v".to_string(), "copy".to_string()], ("copy", None)));
+    }
+
+    let codec = match requested {
+        Some(codec) => codec,
+        // Stream copy can't scale or change quality
+        None if container.default_video == "copy" => container.video[0],
+        None => container.default_video,
+    };
+    let &(_, encoder, max_crf) = VIDEO_CODECS
+        .iter()
+        .find(|(name, _, _)| *name == codec)
+        .ok_or_else(|| format!("Codec de vídeo desconhecido: {}", codec))?;
+    if !container.video.contains(&codec) {
+        return Err(format!("{} não suporta vídeo {}", output_format, codec));
+    }
+
+    let mut args = vec!["-c:v".to_string(), encoder.to_string()];
+
+    if let Some(crf) = options.crf {
+        if crf > max_crf {
+            return Err(format!("CRF inválido para {}: {} (use de 0 a {})", codec, crf, max_crf));
+        }
+        args.extend(["-crf".to_string(), crf.to_string()]);
+        // VP9 and AV1 only use constant quality with the bitrate cap off
+        if codec == "vp9" || codec == "av1" {
+            args.extend(["-b:v".to_string(), "0".to_string()]);
+        }
+    } else if let Some(bitrate) = options.video_bitrate {
+        args.extend(["-b:v".to_string(), format!("{}k", bitrate)]);
+    } else if codec == "vp9" {
+        args.extend(["-b:v".to_string(), "2M".to_string()]);
+    }
+
+    match (&options.encoder_preset, codec) {
+        (Some(preset), "h264" | "hevc") => args.extend(["-preset".to_string(), preset.clone()]),
+        (Some(_), _) => return Err(format!("Preset do encoder só se aplica a h264 e hevc, não a {}", codec)),
+        (None, "h264") => args.extend(["-preset".to_string(), "fast".to_string()]),
+        (None, _) => {}
+    }
+
+    let mut filters = Vec::new();
+    if options.width.is_some() || options.height.is_some() {
+        // -2 keeps the aspect ratio with an even size
+        let side = |value: Option<u32>| value.map(|v| v.to_string()).unwrap_or_else(|| "-2".to_string());
+        filters.push(format!("scale={}:{}", side(options.width), side(options.height)));
+    }
+    // After scaling, so the letters are drawn at the output size
+    filters.extend(burn.map(String::from));
+    if !filters.is_empty() {
+        args.extend(["-vf".to_string(), filters.join(",")]);
+    }
+    if let Some(fps) = options.fps {
+        args.extend(["-r".to_string(), fps.to_string()]);
+    }
+    Ok((args, ("encode", Some(encoder))))
+}
+
+fn audio_args(
+    container: &Container,
+    output_format: &str,
+    options: &ConversionOptions,
+    source: Option<&StreamInfo>,
+) -> Result<(Vec<String>, StreamAction), String> {
+    let requested = options.audio_codec.as_deref();
+    if should_copy(requested, options.changes_audio(), container.default_audio, output_format, source)? {
+        if options.changes_audio() {
+            return Err("Copiar o áudio não permite mudar bitrate, taxa de amostragem ou canais".to_string());
+        }
+        return Ok((vec!["-c:a".to_string(), "copy".to_string()], ("copy", None)));
+    }
+
+    let codec = match requested {
+        Some(codec) => codec,
+        None if container.default_audio == "copy" => container.audio[0],
+        None => container.default_audio,
+    };
+    let &(_, encoder) = AUDIO_CODECS
+        .iter()
+        .find(|(name, _)| *name == codec)
+        .ok_or_else(|| format!("Codec de áudio desconhecido: {}", codec))?;
+    if !container.audio.contains(&codec) {
+        return Err(format!("{} não suporta áudio {}", output_format, codec));
+    }
+
+    let mut args = vec!["-c:a".to_string(), encoder.to_string()];
+
+    match options.audio_bitrate {
+        Some(_) if LOSSLESS_AUDIO.contains(&codec) => {
+            return Err(format!("O codec {} é sem perdas e não usa bitrate", codec));
+        }
+        Some(bitrate) => args.extend(["-b:a".to_string(), format!("{}k", bitrate)]),
+        // Best VBR quality
+        None if codec == "mp3" => args.extend(["-q:a".to_string(), "0".to_string()]),
+        None if codec == "aac" => args.extend(["-b:a".to_string(), "256k".to_string()]),
+        None => {}
+    }
+
+    if let Some(rate) = options.sample_rate {
+        // Opus only encodes at 48 kHz and below
+        if codec == "opus" && ![8000, 12000, 16000, 24000, 48000].contains(&rate) {
+            return Err(format!("Opus não suporta {} Hz", rate));
+        }
+        args.extend(["-ar".to_string(), rate.to_string()]);
+    }
+    if let Some(channels) = options.channels {
+        if codec == "mp3" && channels > 2 {
+            return Err("MP3 suporta no máximo 2 canais".to_string());
+        }
+        args.extend(["-ac".to_string(), channels.to_string()]);
+    }
+    Ok((args, ("encode", Some(encoder))))
+}
+
+// Text subtitles are converted to what the output holds; image-based ones
+// are dropped when they can't be copied
+fn subtitle_args(output_format: &str, source: &StreamInfo) -> (Vec<String>, StreamAction) {
+    // Left to ffmpeg's default encoder for the format
+    let Some(codec) = source.codec.as_deref() else {
+        return (Vec::new(), ("encode", None));
+    };
+    let action = subtitle_action(output_format, codec);
+    let args = match action {
+        ("copy", _) => vec!["-c:s".to_string(), "copy".to_string()],
+        ("drop", _) => vec!["-sn".to_string()],
+        (_, encoder) => vec!["-c:s".to_string(), encoder.unwrap_or_default().to_string()],
+    };
+    (args, action)
+}
+
+fn subtitle_action(output_format: &str, codec: &str) -> StreamAction {
+    if can_copy(output_format, "subtitle", codec) {
+        return ("copy", None);
+    }
+    if BITMAP_SUBTITLES.contains(&codec) {
+        return ("drop", None);
+    }
+
+    let encoder = match output_format {
+        "mp4" | "mov" => "mov_text",
+        "webm" => "webvtt",
+        _ => "srt",
+    };
+    ("encode", Some(encoder))
+}
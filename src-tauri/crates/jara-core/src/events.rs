@@ -0,0 +1,42 @@
+use crate::ffmpeg::ConvertProgress;
+use crate::ytdlp::DownloadProgress;
+
+// Where the services report what they are doing. The desktop app forwards
+// events to the webview, the CLI prints them and tests collect them
+pub trait EventSink: Send + Sync {
+    fn download_progress(&self, progress: DownloadProgress);
+
+    fn convert_progress(&self, _progress: ConvertProgress) {}
+}
+
+// Plain closures work as sinks
+impl<F> EventSink for F
+where
+    F: Fn(DownloadProgress) + Send + Sync,
+{
+    fn download_progress(&self, progress: DownloadProgress) {
+        self(progress)
+    }
+}
+
+// For work nobody follows the progress of
+pub struct NoEvents;
+
+impl EventSink for NoEvents {
+    fn download_progress(&self, _progress: DownloadProgress) {}
+}
+
+// None drops every event
+impl<S: EventSink> EventSink for Option<S> {
+    fn download_progress(&self, progress: DownloadProgress) {
+        if let Some(sink) = self {
+            sink.download_progress(progress);
+        }
+    }
+
+    fn convert_progress(&self, progress: ConvertProgress) {
+        if let Some(sink) = self {
+            sink.convert_progress(progress);
+        }
+    }
+}
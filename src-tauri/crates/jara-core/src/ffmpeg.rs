@@ -0,0 +1,387 @@
+use crate::animation::AnimationRequest;
+use crate::encoding::{encoding_args, ConversionOptions, ConversionPlan, StreamPlan};
+use crate::events::{EventSink, NoEvents};
+use crate::media_info::{parse_ffprobe, MediaInfo};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvertProgress {
+    pub conversion_id: String,
+    // "converting" or "completed"
+    pub status: String,
+    // 0 to 100; stays at 0 when the input's duration is unknown
+    pub progress: f64,
+    pub fps: Option<f64>,
+    // Times real time, e.g. 2.5 for 2.5x
+    pub speed: Option<f64>,
+    // Seconds left
+    pub eta: Option<f64>,
+    pub output: Option<String>,
+    // Which streams are copied and which are re-encoded
+    #[serde(default)]
+    pub streams: Vec<StreamPlan>,
+}
+
+pub struct FFmpeg {
+    exe_path: PathBuf,
+    ffprobe_path: PathBuf,
+    active_conversions: Arc<Mutex<HashMap<String, Child>>>,
+}
+
+impl FFmpeg {
+    pub fn new() -> Self {
+        Self::with_paths(Self::default_path(), Self::default_ffprobe_path())
+    }
+
+    pub fn with_path(exe_path: PathBuf) -> Self {
+        let ffprobe_path = Self::ffprobe_beside(&exe_path);
+        Self::with_paths(exe_path, ffprobe_path)
+    }
+
+    pub fn with_paths(exe_path: PathBuf, ffprobe_path: PathBuf) -> Self {
+        Self {
+            exe_path,
+            ffprobe_path,
+            active_conversions: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        // First try to find bundled ffmpeg in resources
+        if let Ok(exe_path) = std::env::current_exe() {
+            let resources_path = exe_path
+                .parent()
+                .map(|p| p.join("ffmpeg.exe"))
+                .unwrap_or_default();
+            if resources_path.exists() {
+                return resources_path;
+            }
+        }
+
+        // Fallback to system PATH
+        PathBuf::from("ffmpeg")
+    }
+
+    // ffmpeg builds ship ffprobe in the same folder
+    pub fn ffprobe_beside(ffmpeg_path: &Path) -> PathBuf {
+        match ffmpeg_path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => {
+                dir.join(format!("ffprobe{}", std::env::consts::EXE_SUFFIX))
+            }
+            _ => PathBuf::from("ffprobe"),
+        }
+    }
+
+    pub fn default_ffprobe_path() -> PathBuf {
+        if let Ok(exe_path) = std::env::current_exe() {
+            let resources_path = exe_path
+                .parent()
+                .map(|p| p.join("ffprobe.exe"))
+                .unwrap_or_default();
+            if resources_path.exists() {
+                return resources_path;
+            }
+        }
+
+        PathBuf::from("ffprobe")
+    }
+
+    pub async fn convert(
+        &self,
+        input_path: &str,
+        output_format: &str,
+    ) -> Result<String, String> {
+        self.convert_with_options(input_path, output_format, &ConversionOptions::default())
+            .await
+    }
+
+    pub async fn convert_with_options(
+        &self,
+        input_path: &str,
+        output_format: &str,
+        options: &ConversionOptions,
+    ) -> Result<String, String> {
+        self.start_conversion(new_conversion_id(), input_path, output_format, options, NoEvents)
+            .await
+    }
+
+    // The exact arguments start_conversion passes to ffmpeg and what happens
+    // to each stream, so they can be reviewed without converting anything
+    pub async fn plan_conversion(
+        &self,
+        input_path: &str,
+        output_format: &str,
+        options: &ConversionOptions,
+    ) -> Result<ConversionPlan, String> {
+        let (plan, _) = self.plan_with_media(input_path, output_format, options).await?;
+        Ok(plan)
+    }
+
+    async fn plan_with_media(
+        &self,
+        input_path: &str,
+        output_format: &str,
+        options: &ConversionOptions,
+    ) -> Result<(ConversionPlan, Option<MediaInfo>), String> {
+        let input = PathBuf::from(input_path);
+        if !input.exists() {
+            return Err("Arquivo de entrada não encontrado".to_string());
+        }
+        options.validate()?;
+        if output_format == "gif" {
+            return Err("GIFs são feitos em duas passadas, com video_to_animation".to_string());
+        }
+
+        // Without ffprobe nothing is known to be safe to copy
+        let media = self.probe_media(input_path).await.ok();
+        let mut plan = encoding_args(Some(input_path), output_format, options, media.as_ref())?;
+
+        let mut args = vec!["-i".to_string(), input_path.to_string(), "-y".to_string()];
+        args.append(&mut plan.args);
+        // key=value blocks on stdout, each ending with a progress= line
+        args.extend(["-progress".to_string(), "pipe:1".to_string(), "-nostats".to_string()]);
+        args.push(input.with_extension(output_format).to_string_lossy().to_string());
+        plan.args = args;
+        Ok((plan, media))
+    }
+
+    // Reports "convert-progress" events while ffmpeg runs. cancel_conversion
+    // stops it with the same id
+    pub async fn start_conversion<S>(
+        &self,
+        conversion_id: String,
+        input_path: &str,
+        output_format: &str,
+        options: &ConversionOptions,
+        events: S,
+    ) -> Result<String, String>
+    where
+        S: EventSink,
+    {
+        // A palette pass first, with the animation defaults
+        if output_format == "gif" && *options == ConversionOptions::default() {
+            let request = AnimationRequest {
+                input_path: input_path.to_string(),
+                ..AnimationRequest::default()
+            };
+            return self
+                .start_animation(conversion_id, &request, events)
+                .await
+                .map(|result| result.output);
+        }
+
+        let input = PathBuf::from(input_path);
+        let output = input.with_extension(output_format);
+        let output_str = output.to_string_lossy().to_string();
+
+        let (plan, media) = self.plan_with_media(input_path, output_format, options).await?;
+        let duration = media.and_then(|info| info.duration);
+
+        if let Err(e) = self
+            .run_ffmpeg(&conversion_id, &plan.args, 0.0, duration, &plan.streams, &events)
+            .await
+        {
+            remove_partial_output(&input, &output);
+            return Err(e);
+        }
+
+        events.convert_progress(ConvertProgress {
+            conversion_id,
+            status: "completed".to_string(),
+            progress: 100.0,
+            fps: None,
+            speed: None,
+            eta: None,
+            output: Some(output_str.clone()),
+            streams: plan.streams,
+        });
+        Ok(output_str)
+    }
+
+    // Runs ffmpeg with "-progress pipe:1" among the args and reports
+    // "converting" events against `total` seconds, `done` of them covered by
+    // earlier runs of the same job. Returns what ffmpeg printed on stderr.
+    // Leaves the output to the caller, also when cancel_conversion stops it
+    pub(crate) async fn run_ffmpeg<S>(
+        &self,
+        conversion_id: &str,
+        args: &[String],
+        done: f64,
+        total: Option<f64>,
+        streams: &[StreamPlan],
+        events: &S,
+    ) -> Result<String, String>
+    where
+        S: EventSink,
+    {
+        let mut child = Command::new(&self.exe_path)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Falha ao executar ffmpeg: {}. Certifique-se de que o ffmpeg está instalado.", e))?;
+
+        let stdout = child.stdout.take().ok_or("Falha ao capturar stdout")?;
+        let mut stderr = child.stderr.take().ok_or("Falha ao capturar stderr")?;
+        self.active_conversions
+            .lock()
+            .await
+            .insert(conversion_id.to_string(), child);
+
+        let stderr_handle = tokio::spawn(async move {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output).await;
+            output
+        });
+
+        let mut lines = BufReader::new(stdout).lines();
+        let mut fps = None;
+        let mut speed = None;
+        let mut out_time = None;
+        while let Ok(Some(line)) = lines.next_line().await {
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "fps" => fps = value.parse::<f64>().ok(),
+                "speed" => speed = value.trim_end_matches('x').parse::<f64>().ok(),
+                // out_time_ms is in microseconds too
+                "out_time_us" | "out_time_ms" => {
+                    out_time = value.parse::<f64>().ok().map(|us| done + us / 1_000_000.0)
+                }
+                "progress" => events.convert_progress(ConvertProgress {
+                    conversion_id: conversion_id.to_string(),
+                    status: "converting".to_string(),
+                    progress: match (out_time, total) {
+                        (Some(time), Some(total)) if total > 0.0 => (time / total * 100.0).clamp(0.0, 100.0),
+                        _ => 0.0,
+                    },
+                    fps,
+                    speed,
+                    eta: match (out_time, total, speed) {
+                        (Some(time), Some(total), Some(speed)) if speed > 0.0 => {
+                            Some(((total - time) / speed).max(0.0))
+                        }
+                        _ => None,
+                    },
+                    output: None,
+                    streams: streams.to_vec(),
+                }),
+                _ => {}
+            }
+        }
+        let error_output = stderr_handle.await.unwrap_or_default();
+
+        let status = {
+            let mut active = self.active_conversions.lock().await;
+            match active.remove(conversion_id) {
+                Some(mut child) => child.wait().await,
+                None => return Err("Conversão cancelada".to_string()),
+            }
+        };
+
+        match status {
+            Ok(exit_status) if exit_status.success() => Ok(error_output),
+            Ok(_) => Err(format!("Erro na conversão: {}", error_output)),
+            Err(e) => Err(format!("Erro ao aguardar ffmpeg: {}", e)),
+        }
+    }
+
+    pub async fn cancel_conversion(&self, conversion_id: &str) -> Result<(), String> {
+        let mut active = self.active_conversions.lock().await;
+        if let Some(mut child) = active.remove(conversion_id) {
+            child
+                .kill()
+                .await
+                .map_err(|e| format!("Falha ao cancelar: {}", e))?;
+            Ok(())
+        } else {
+            Err("Conversão não encontrada".to_string())
+        }
+    }
+
+    pub async fn probe_media(&self, input_path: &str) -> Result<MediaInfo, String> {
+        if !PathBuf::from(input_path).exists() {
+            return Err("Arquivo de entrada não encontrado".to_string());
+        }
+
+        let output = Command::new(&self.ffprobe_path)
+            .args([
+                "-v",
+                "error",
+                "-print_format",
+                "json",
+                "-show_format",
+                "-show_streams",
+                "-show_chapters",
+                input_path,
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .await
+            .map_err(|e| format!("Falha ao executar ffprobe: {}. Certifique-se de que o ffmpeg está instalado.", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("Falha ao analisar {}: {}", input_path, stderr.trim()));
+        }
+        parse_ffprobe(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+impl Default for FFmpeg {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Converting a file to its own format fails before anything is written, and
+// the "partial output" is then the input
+pub(crate) fn remove_partial_output(input: &Path, output: &Path) {
+    if output != input {
+        let _ = std::fs::remove_file(output);
+    }
+}
+
+// Input for the concat demuxer, which joins same-codec files without
+// re-encoding
+pub(crate) fn write_concat_list(list_path: &Path, parts: &[PathBuf]) -> Result<(), String> {
+    let list: String = parts
+        .iter()
+        .map(|part| format!("file '{}'\n", part.to_string_lossy().replace('\'', r"'\''")))
+        .collect();
+    std::fs::write(list_path, list).map_err(|e| format!("Falha ao criar lista de arquivos: {}", e))
+}
+
+// Millisecond precision, without float noise like 3.4999999999999996
+pub(crate) fn decimal_arg(value: f64) -> String {
+    let text = format!("{:.3}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+// The caller's id and sink, so it can follow and cancel the work, or a fresh
+// id whose events nobody gets
+pub fn conversion_events<S: EventSink>(conversion_id: Option<String>, events: S) -> (String, Option<S>) {
+    match conversion_id {
+        Some(id) => (id, Some(events)),
+        None => (new_conversion_id(), None),
+    }
+}
+
+pub(crate) fn new_conversion_id() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    format!("conversion-{}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
@@ -0,0 +1,285 @@
+use crate::events::NoEvents;
+use crate::ffmpeg::{decimal_arg, new_conversion_id, FFmpeg};
+use crate::image_convert::{image_format, save_image};
+use image::{imageops, DynamicImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// Formats frames and contact sheets can be saved as
+const FRAME_FORMATS: &[&str] = &["jpg", "png", "webp"];
+const MAX_FRAMES: u32 = 100;
+// Space around the tiles of a contact sheet, in pixels
+const SHEET_GAP: u32 = 4;
+const SHEET_BACKGROUND: Rgb<u8> = Rgb([17, 17, 17]);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrameOptions {
+    // "jpg", "png" or "webp"; jpg when unset
+    pub format: Option<String>,
+    // The video's own size when unset; the height follows
+    pub width: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContactSheetOptions {
+    // 4 x 4 when unset
+    pub columns: Option<u32>,
+    pub rows: Option<u32>,
+    // Width of each frame, 320 px when unset
+    pub tile_width: Option<u32>,
+    // Leaves out the time written in each frame's corner
+    pub hide_timestamps: bool,
+    // "jpg", "png" or "webp"; jpg when unset
+    pub format: Option<String>,
+}
+
+impl FrameOptions {
+    fn validate(&self) -> Result<&str, String> {
+        if let Some(width) = self.width {
+            if !(16..=3840).contains(&width) {
+                return Err(format!("Largura inválida: {} (use de 16 a 3840)", width));
+            }
+        }
+        frame_format(self.format.as_deref())
+    }
+}
+
+impl FFmpeg {
+    // Saves the frame at `at` seconds as "<name>-frame-<at>s.<format>"
+    pub async fn extract_frame(&self, input_path: &str, at: f64, options: &FrameOptions) -> Result<String, String> {
+        let format = options.validate()?;
+        let input = PathBuf::from(input_path);
+        let stem = video_stem(&input)?;
+
+        let duration = self.probe_media(input_path).await.ok().and_then(|m| m.duration);
+        if at < 0.0 || duration.is_some_and(|total| at >= total) {
+            return Err(format!("O vídeo não tem quadro em {}s", decimal_arg(at)));
+        }
+
+        let output = input.with_file_name(format!("{}-frame-{}s.{}", stem, decimal_arg(at), format));
+        self.save_frame(input_path, at, options.width, &output).await?;
+        Ok(output.to_string_lossy().to_string())
+    }
+
+    // Saves `count` frames spread evenly over the video as
+    // "<name>-frame-01.<format>", "<name>-frame-02.<format>"...
+    pub async fn extract_frames(&self, input_path: &str, count: u32, options: &FrameOptions) -> Result<Vec<String>, String> {
+        let format = options.validate()?;
+        let input = PathBuf::from(input_path);
+        let stem = video_stem(&input)?;
+        let times = spread(self, input_path, count).await?;
+
+        let digits = count.to_string().len();
+        let mut outputs = Vec::new();
+        for (i, at) in times.into_iter().enumerate() {
+            let output = input.with_file_name(format!("{}-frame-{:0digits$}.{}", stem, i + 1, format));
+            if let Err(e) = self.save_frame(input_path, at, options.width, &output).await {
+                for written in &outputs {
+                    let _ = std::fs::remove_file(written);
+                }
+                return Err(e);
+            }
+            outputs.push(output.to_string_lossy().to_string());
+        }
+        Ok(outputs)
+    }
+
+    // Tiles frames spread evenly over the video into "<name>-sheet.<format>"
+    pub async fn contact_sheet(&self, input_path: &str, options: &ContactSheetOptions) -> Result<String, String> {
+        let format = frame_format(options.format.as_deref())?;
+        let columns = options.columns.unwrap_or(4);
+        let rows = options.rows.unwrap_or(4);
+        if !(1..=10).contains(&columns) || !(1..=10).contains(&rows) {
+            return Err(format!("Grade inválida: {}x{} (use até 10 colunas e 10 linhas)", columns, rows));
+        }
+        let tile_width = options.tile_width.unwrap_or(320);
+        if !(64..=1280).contains(&tile_width) {
+            return Err(format!("Largura dos quadros inválida: {} (use de 64 a 1280)", tile_width));
+        }
+
+        let input = PathBuf::from(input_path);
+        let stem = video_stem(&input)?;
+        let times = spread(self, input_path, columns * rows).await?;
+        let output = input.with_file_name(format!("{}-sheet.{}", stem, format));
+
+        // Hidden until they are tiled
+        let mut tiles = Vec::new();
+        let mut result = Ok(());
+        for (i, at) in times.iter().enumerate() {
+            let tile = input.with_file_name(format!(".{}-sheet-{}.png", stem, i + 1));
+            result = self.save_frame(input_path, *at, Some(tile_width), &tile).await;
+            if result.is_err() {
+                break;
+            }
+            tiles.push((tile, *at));
+        }
+
+        if result.is_ok() {
+            let tiles = tiles.clone();
+            let output = output.clone();
+            let timestamps = !options.hide_timestamps;
+            result = tokio::task::spawn_blocking(move || tile_sheet(&tiles, columns, timestamps, &output))
+                .await
+                .map_err(|e| format!("Falha ao montar a folha de contato: {}", e))
+                .and_then(|r| r);
+        }
+        for (tile, _) in &tiles {
+            let _ = std::fs::remove_file(tile);
+        }
+        result.map(|()| output.to_string_lossy().to_string())
+    }
+
+    async fn save_frame(&self, input_path: &str, at: f64, width: Option<u32>, output: &Path) -> Result<(), String> {
+        let mut args = vec!["-ss".to_string(), decimal_arg(at), "-i".to_string(), input_path.to_string()];
+        args.extend(["-frames:v".to_string(), "1".to_string()]);
+        if let Some(width) = width {
+            args.extend(["-vf".to_string(), format!("scale={}:-2", width)]);
+        }
+        // Best JPEG quality
+        if output.extension().is_some_and(|ext| ext == "jpg") {
+            args.extend(["-q:v".to_string(), "2".to_string()]);
+        }
+        args.extend(["-y".to_string(), output.to_string_lossy().to_string()]);
+
+        // Seeking past the last frame writes nothing and still succeeds, so an
+        // older file must not pass for the frame
+        let _ = std::fs::remove_file(output);
+        self.run_ffmpeg(&new_conversion_id(), &args, 0.0, None, &[], &NoEvents)
+            .await?;
+        if !output.exists() {
+            return Err(format!("O vídeo não tem quadro em {}s", decimal_arg(at)));
+        }
+        Ok(())
+    }
+}
+
+fn frame_format(format: Option<&str>) -> Result<&str, String> {
+    let format = format.unwrap_or("jpg");
+    if !FRAME_FORMATS.contains(&format) {
+        return Err(format!("Formato de imagem inválido: {} (use {})", format, FRAME_FORMATS.join(", ")));
+    }
+    Ok(format)
+}
+
+fn video_stem(input: &Path) -> Result<String, String> {
+    if !input.exists() {
+        return Err("Arquivo de entrada não encontrado".to_string());
+    }
+    input
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .ok_or_else(|| "Arquivo de entrada inválido".to_string())
+}
+
+// The middle of `count` equal parts of the video, so neither the first
+// frame, often black, nor the end is picked
+async fn spread(ffmpeg: &FFmpeg, input_path: &str, count: u32) -> Result<Vec<f64>, String> {
+    if !(1..=MAX_FRAMES).contains(&count) {
+        return Err(format!("Número de quadros inválido: {} (use de 1 a {})", count, MAX_FRAMES));
+    }
+    let duration = ffmpeg
+        .probe_media(input_path)
+        .await?
+        .duration
+        .filter(|d| *d > 0.0)
+        .ok_or("Não foi possível saber a duração do vídeo")?;
+    Ok((0..count)
+        .map(|i| duration * (i as f64 + 0.5) / count as f64)
+        .collect())
+}
+
+fn tile_sheet(tiles: &[(PathBuf, f64)], columns: u32, timestamps: bool, output: &Path) -> Result<(), String> {
+    let frames = tiles
+        .iter()
+        .map(|(path, at)| {
+            image::open(path)
+                .map(|frame| (frame.to_rgb8(), *at))
+                .map_err(|e| format!("Falha ao abrir quadro: {}", e))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let (tile_width, tile_height) = frames
+        .iter()
+        .fold((0, 0), |(w, h), (frame, _)| (w.max(frame.width()), h.max(frame.height())));
+
+    let rows = (frames.len() as u32).div_ceil(columns);
+    let mut sheet = RgbImage::from_pixel(
+        columns * (tile_width + SHEET_GAP) + SHEET_GAP,
+        rows * (tile_height + SHEET_GAP) + SHEET_GAP,
+        SHEET_BACKGROUND,
+    );
+    for (i, (frame, at)) in frames.iter().enumerate() {
+        let x = SHEET_GAP + (i as u32 % columns) * (tile_width + SHEET_GAP);
+        let y = SHEET_GAP + (i as u32 / columns) * (tile_height + SHEET_GAP);
+        imageops::replace(&mut sheet, frame, x as i64, y as i64);
+        let scale = (tile_width / 160).max(1);
+        let label = clock(*at);
+        let label_width = label.len() as u32 * 4 * scale + scale;
+        // Very wide videos can make tiles too short for it
+        if timestamps && label_width <= tile_width && 7 * scale <= tile_height {
+            draw_label(&mut sheet, x + tile_width - label_width, y + tile_height - 7 * scale, &label, scale);
+        }
+    }
+
+    let format = output
+        .extension()
+        .and_then(|e| image_format(&e.to_string_lossy()))
+        .ok_or("Formato de imagem inválido")?;
+    save_image(&DynamicImage::ImageRgb8(sheet), output, format)
+}
+
+// "1:05" or "1:02:05"
+fn clock(seconds: f64) -> String {
+    let whole = seconds as u64;
+    if whole >= 3600 {
+        format!("{}:{:02}:{:02}", whole / 3600, whole / 60 % 60, whole % 60)
+    } else {
+        format!("{}:{:02}", whole / 60, whole % 60)
+    }
+}
+
+// 3x5 glyphs for the characters of a clock, one row per byte
+fn glyph(c: char) -> [u8; 5] {
+    match c {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        _ => [0; 5],
+    }
+}
+
+// White text on a black box whose top left corner is at (x, y)
+fn draw_label(image: &mut RgbImage, x: u32, y: u32, text: &str, scale: u32) {
+    let width = text.len() as u32 * 4 * scale + scale;
+    for dy in 0..7 * scale {
+        for dx in 0..width {
+            image.put_pixel(x + dx, y + dy, Rgb([0, 0, 0]));
+        }
+    }
+    for (i, c) in text.chars().enumerate() {
+        let left = x + scale + i as u32 * 4 * scale;
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) == 0 {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = left + col * scale + dx;
+                        let py = y + scale + row as u32 * scale + dy;
+                        image.put_pixel(px, py, Rgb([255, 255, 255]));
+                    }
+                }
+            }
+        }
+    }
+}
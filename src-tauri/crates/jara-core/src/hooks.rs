@@ -0,0 +1,326 @@
+use crate::ffmpeg::FFmpeg;
+use crate::image_convert::{ImageConverter, IMAGE_EXTENSIONS};
+use crate::loudness::{LoudnessOptions, LoudnessRequest};
+use crate::ytdlp::create_hidden_command;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Commands that run longer than this are killed
+const DEFAULT_COMMAND_TIMEOUT_SECS: u64 = 600;
+// Only the end of a command's output is kept on the job
+const MAX_OUTPUT_CHARS: usize = 4000;
+
+// Something to do with a file once its download completes. Actions run in
+// order and each one sees the file where the previous one left it.
+// Arguments, destinations and names may use {path}, {dir}, {filename},
+// {title}, {ext}, {id} and {url}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PostAction {
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+        timeout_secs: Option<u64>,
+    },
+    // Into the destination folder, keeping the name unless `rename` is set
+    Move {
+        destination: String,
+        rename: Option<String>,
+    },
+    Copy {
+        destination: String,
+        rename: Option<String>,
+    },
+    // Images go through ImageConverter, everything else through ffmpeg
+    Convert {
+        format: String,
+        #[serde(default)]
+        keep_original: bool,
+    },
+    // EBU R128 loudness normalization, in place unless `keep_original` is
+    // set, which leaves the result beside it as "<name>-normalized.<ext>"
+    Normalize {
+        #[serde(default, flatten)]
+        options: LoudnessOptions,
+        #[serde(default)]
+        keep_original: bool,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookResult {
+    // "command", "move", "copy", "convert" or "normalize"
+    pub action: String,
+    pub success: bool,
+    // What the command printed, or the path the file ended up at
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+// The finished download the actions run on
+#[derive(Debug, Clone)]
+pub struct HookContext {
+    pub id: String,
+    pub url: String,
+    pub path: PathBuf,
+}
+
+// Runs post-download actions for the download manager
+#[derive(Clone)]
+pub struct PostProcessor {
+    ffmpeg: Arc<FFmpeg>,
+    images: Arc<ImageConverter>,
+}
+
+impl PostProcessor {
+    pub fn new(ffmpeg: Arc<FFmpeg>, images: Arc<ImageConverter>) -> Self {
+        Self { ffmpeg, images }
+    }
+
+    // Stops at the first failure, since later actions expect the file the
+    // failed one should have produced. Returns the results and where the file
+    // is after the actions that succeeded
+    pub async fn run(&self, actions: &[PostAction], context: &HookContext) -> (Vec<HookResult>, PathBuf) {
+        let mut path = context.path.clone();
+        let mut results = Vec::new();
+
+        for action in actions {
+            let vars = TemplateVars::new(context, &path);
+            let result = match action {
+                PostAction::Command {
+                    program,
+                    args,
+                    timeout_secs,
+                } => {
+                    let args: Vec<String> = args.iter().map(|a| vars.expand(a)).collect();
+                    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_COMMAND_TIMEOUT_SECS));
+                    run_command(program, &args, timeout).await
+                }
+                PostAction::Move { destination, rename } => {
+                    transfer(&path, &vars, destination, rename.as_deref(), true).map(|moved| {
+                        path = moved.clone();
+                        moved.to_string_lossy().to_string()
+                    })
+                }
+                PostAction::Copy { destination, rename } => {
+                    transfer(&path, &vars, destination, rename.as_deref(), false)
+                        .map(|copied| copied.to_string_lossy().to_string())
+                }
+                PostAction::Convert { format, keep_original } => {
+                    self.convert(&path, format, *keep_original)
+                        .await
+                        .inspect(|converted| path = PathBuf::from(converted))
+                }
+                PostAction::Normalize { options, keep_original } => {
+                    self.normalize(&path, options, *keep_original)
+                        .await
+                        .inspect(|normalized| path = PathBuf::from(normalized))
+                }
+            };
+
+            let failed = result.is_err();
+            results.push(match result {
+                Ok(output) => HookResult {
+                    action: action.name().to_string(),
+                    success: true,
+                    output: Some(output),
+                    error: None,
+                },
+                Err(e) => HookResult {
+                    action: action.name().to_string(),
+                    success: false,
+                    output: None,
+                    error: Some(e),
+                },
+            });
+            if failed {
+                break;
+            }
+        }
+
+        (results, path)
+    }
+
+    async fn convert(&self, input: &Path, format: &str, keep_original: bool) -> Result<String, String> {
+        let input_ext = extension(input);
+        let input_str = input.to_string_lossy().to_string();
+
+        let output = if IMAGE_EXTENSIONS.contains(&input_ext.as_str()) && IMAGE_EXTENSIONS.contains(&format) {
+            let images = self.images.clone();
+            let format = format.to_string();
+            let input_str = input_str.clone();
+            tokio::task::spawn_blocking(move || images.convert(&input_str, &format))
+                .await
+                .map_err(|e| format!("Falha na conversão: {}", e))??
+        } else {
+            self.ffmpeg.convert(&input_str, format).await?
+        };
+
+        if !keep_original && Path::new(&output) != input {
+            let _ = std::fs::remove_file(input);
+        }
+        Ok(output)
+    }
+
+    async fn normalize(&self, input: &Path, options: &LoudnessOptions, keep_original: bool) -> Result<String, String> {
+        let request = LoudnessRequest {
+            input_paths: vec![input.to_string_lossy().to_string()],
+            options: options.clone(),
+        };
+        let outcome = self.ffmpeg.normalize_loudness(&request).await?.remove(0);
+        let output = match (outcome.result, outcome.error) {
+            (Some(result), _) => PathBuf::from(result.output),
+            (None, error) => return Err(error.unwrap_or_default()),
+        };
+
+        if keep_original {
+            return Ok(output.to_string_lossy().to_string());
+        }
+        std::fs::rename(&output, input).map_err(|e| format!("Falha ao substituir o arquivo original: {}", e))?;
+        Ok(input.to_string_lossy().to_string())
+    }
+}
+
+impl PostAction {
+    fn name(&self) -> &'static str {
+        match self {
+            PostAction::Command { .. } => "command",
+            PostAction::Move { .. } => "move",
+            PostAction::Copy { .. } => "copy",
+            PostAction::Convert { .. } => "convert",
+            PostAction::Normalize { .. } => "normalize",
+        }
+    }
+}
+
+struct TemplateVars {
+    values: Vec<(&'static str, String)>,
+}
+
+impl TemplateVars {
+    fn new(context: &HookContext, path: &Path) -> Self {
+        let text = |value: Option<&std::ffi::OsStr>| {
+            value.map(|v| v.to_string_lossy().to_string()).unwrap_or_default()
+        };
+
+        Self {
+            values: vec![
+                ("path", path.to_string_lossy().to_string()),
+                ("dir", text(path.parent().map(|p| p.as_os_str()))),
+                ("filename", text(path.file_name())),
+                // The output template names downloads after the title
+                ("title", text(context.path.file_stem())),
+                ("ext", text(path.extension())),
+                ("id", context.id.clone()),
+                ("url", context.url.clone()),
+            ],
+        }
+    }
+
+    // In one pass, so a title containing "{id}" stays as it is
+    fn expand(&self, template: &str) -> String {
+        let mut result = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            result.push_str(&rest[..start]);
+            let placeholder = &rest[start + 1..];
+            let known = self.values.iter().find(|(name, _)| {
+                placeholder.starts_with(name) && placeholder[name.len()..].starts_with('}')
+            });
+            match known {
+                Some((name, value)) => {
+                    result.push_str(value);
+                    rest = &placeholder[name.len() + 1..];
+                }
+                None => {
+                    result.push('{');
+                    rest = placeholder;
+                }
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+async fn run_command(program: &str, args: &[String], timeout: Duration) -> Result<String, String> {
+    let child = create_hidden_command(&PathBuf::from(program))
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Falha ao executar {}: {}", program, e))?;
+
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| format!("{} excedeu o tempo limite de {}s", program, timeout.as_secs()))?
+        .map_err(|e| format!("Falha ao executar {}: {}", program, e))?;
+
+    let mut printed = String::from_utf8_lossy(&output.stdout).to_string();
+    printed.push_str(&String::from_utf8_lossy(&output.stderr));
+    let printed = tail(printed.trim(), MAX_OUTPUT_CHARS);
+
+    if output.status.success() {
+        Ok(printed)
+    } else {
+        Err(format!("{} terminou com {}: {}", program, output.status, printed))
+    }
+}
+
+// Moves or copies the file into the destination folder and returns where it
+// ended up. Existing files are never overwritten
+fn transfer(
+    path: &Path,
+    vars: &TemplateVars,
+    destination: &str,
+    rename: Option<&str>,
+    remove_source: bool,
+) -> Result<PathBuf, String> {
+    if !path.exists() {
+        return Err(format!("Arquivo não encontrado: {}", path.display()));
+    }
+
+    let folder = PathBuf::from(vars.expand(destination));
+    let name = match rename {
+        Some(template) => vars.expand(template),
+        None => path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+    };
+    let target = folder.join(name);
+    if target.exists() {
+        return Err(format!("Já existe um arquivo em {}", target.display()));
+    }
+
+    std::fs::create_dir_all(&folder).map_err(|e| format!("Falha ao criar diretório: {}", e))?;
+
+    // rename fails across file systems, e.g. onto a network share
+    if remove_source && std::fs::rename(path, &target).is_ok() {
+        return Ok(target);
+    }
+    std::fs::copy(path, &target).map_err(|e| format!("Falha ao copiar arquivo: {}", e))?;
+    if remove_source {
+        std::fs::remove_file(path).map_err(|e| format!("Falha ao remover arquivo original: {}", e))?;
+    }
+    Ok(target)
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+fn tail(text: &str, max_chars: usize) -> String {
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text.to_string();
+    }
+    text.chars().skip(count - max_chars).collect()
+}
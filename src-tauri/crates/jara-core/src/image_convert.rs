@@ -0,0 +1,120 @@
+use image::{DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImageOptions {
+    // Fit the image inside a max_size x max_size box
+    pub max_size: Option<u32>,
+    // Center-crop to a square first, e.g. for album art
+    #[serde(default)]
+    pub square: bool,
+}
+
+// Formats ImageConverter reads and writes
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp", "ico", "tiff"];
+
+pub struct ImageConverter;
+
+impl ImageConverter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn convert(&self, input_path: &str, output_format: &str) -> Result<String, String> {
+        self.convert_with_options(input_path, output_format, &ImageOptions::default())
+    }
+
+    pub fn convert_with_options(
+        &self,
+        input_path: &str,
+        output_format: &str,
+        options: &ImageOptions,
+    ) -> Result<String, String> {
+        let input = PathBuf::from(input_path);
+        
+        if !input.exists() {
+            return Err("Arquivo de entrada não encontrado".to_string());
+        }
+
+        // Load image
+        let mut img = image::open(&input)
+            .map_err(|e| format!("Falha ao abrir imagem: {}", e))?;
+
+        if options.square {
+            let side = img.width().min(img.height());
+            img = img.crop_imm((img.width() - side) / 2, (img.height() - side) / 2, side, side);
+        }
+
+        if let Some(max_size) = options.max_size {
+            if max_size == 0 {
+                return Err("Tamanho inválido".to_string());
+            }
+            // Only shrink; an image that already fits keeps its size
+            if img.width() > max_size || img.height() > max_size {
+                img = img.resize(max_size, max_size, image::imageops::FilterType::Lanczos3);
+            }
+        }
+
+        // Determine output format
+        let format = image_format(output_format)
+            .ok_or_else(|| format!("Formato não suportado: {}", output_format))?;
+
+        // Generate output path
+        let output = input.with_extension(output_format.to_lowercase());
+        let output_str = output.to_string_lossy().to_string();
+
+        // Handle ICO specially (needs resizing)
+        let img_to_save: DynamicImage = if format == ImageFormat::Ico {
+            img.resize(256, 256, image::imageops::FilterType::Lanczos3)
+        } else {
+            img
+        };
+
+        save_image(&img_to_save, &output, format)?;
+
+        Ok(output_str)
+    }
+}
+
+pub(crate) fn image_format(extension: &str) -> Option<ImageFormat> {
+    match extension.to_lowercase().as_str() {
+        "png" => Some(ImageFormat::Png),
+        "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+        "webp" => Some(ImageFormat::WebP),
+        "gif" => Some(ImageFormat::Gif),
+        "bmp" => Some(ImageFormat::Bmp),
+        "ico" => Some(ImageFormat::Ico),
+        "tiff" => Some(ImageFormat::Tiff),
+        _ => None,
+    }
+}
+
+// Saves with appropriate quality
+pub(crate) fn save_image(img: &DynamicImage, output: &Path, format: ImageFormat) -> Result<(), String> {
+    match format {
+        ImageFormat::Jpeg => {
+            let rgb_img = img.to_rgb8();
+            let mut output_file = std::fs::File::create(output)
+                .map_err(|e| format!("Falha ao criar arquivo: {}", e))?;
+            
+            let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut output_file, 90);
+            encoder.encode(
+                rgb_img.as_raw(),
+                rgb_img.width(),
+                rgb_img.height(),
+                image::ExtendedColorType::Rgb8
+            ).map_err(|e| format!("Falha ao salvar JPEG: {}", e))
+        }
+        _ => {
+            img.save_with_format(output, format)
+                .map_err(|e| format!("Falha ao salvar imagem: {}", e))
+        }
+    }
+}
+
+impl Default for ImageConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
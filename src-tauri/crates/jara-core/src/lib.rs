@@ -0,0 +1,26 @@
+pub mod animation;
+pub mod bulk_import;
+pub mod clipboard_watch;
+pub mod comment_export;
+pub mod concat;
+#[cfg(feature = "control-api")]
+pub mod control_api;
+pub mod deep_link;
+pub mod document_convert;
+pub mod download_manager;
+pub mod encoding;
+pub mod events;
+pub mod ffmpeg;
+pub mod frames;
+pub mod hooks;
+pub mod image_convert;
+pub mod loudness;
+pub mod media_info;
+pub mod services;
+pub mod settings;
+pub mod subtitle_convert;
+pub mod trim;
+pub mod ytdlp;
+
+pub use events::EventSink;
+pub use services::{BinaryPaths, Services};
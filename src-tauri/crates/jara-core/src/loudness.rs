@@ -0,0 +1,324 @@
+use crate::encoding::audio_filter_args;
+use crate::events::{EventSink, NoEvents};
+use crate::ffmpeg::{decimal_arg, new_conversion_id, remove_partial_output, ConvertProgress, FFmpeg};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// (name, integrated LUFS, true peak dBTP, loudness range LU)
+pub const LOUDNESS_PRESETS: &[(&str, f64, f64, f64)] = &[
+    ("podcast", -16.0, -1.5, 11.0),
+    ("streaming", -14.0, -1.0, 11.0),
+    // EBU R128
+    ("broadcast", -23.0, -1.0, 20.0),
+];
+
+const DEFAULT_LOUDNESS_PRESET: &str = "streaming";
+
+// EBU R128 normalization target. Values left unset come from the preset
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoudnessOptions {
+    // "podcast", "streaming" or "broadcast"; streaming when unset
+    pub preset: Option<String>,
+    // LUFS
+    pub integrated: Option<f64>,
+    // dBTP
+    pub true_peak: Option<f64>,
+    // LU
+    pub lra: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessTarget {
+    pub integrated: f64,
+    pub true_peak: f64,
+    pub lra: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoudnessRequest {
+    pub input_paths: Vec<String>,
+    #[serde(default)]
+    pub options: LoudnessOptions,
+}
+
+// What loudnorm measured on one side of the correction
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LoudnessMeasurement {
+    // LUFS
+    pub integrated: f64,
+    // dBTP
+    pub true_peak: f64,
+    // LU
+    pub lra: f64,
+    // LUFS; quieter parts don't count towards the integrated loudness
+    pub threshold: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoudnessResult {
+    pub input: String,
+    pub output: String,
+    pub target: LoudnessTarget,
+    // The input, from the first pass
+    pub measured: LoudnessMeasurement,
+    // The output, as the second pass reports it
+    pub normalized: LoudnessMeasurement,
+    // False when the target could only be reached by compressing the
+    // dynamics instead of changing the gain
+    pub linear: bool,
+}
+
+// One file of a batch; a failure doesn't stop the files after it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoudnessOutcome {
+    pub input: String,
+    pub result: Option<LoudnessResult>,
+    pub error: Option<String>,
+}
+
+// The JSON loudnorm prints at the end of a run, all values as strings
+#[derive(Deserialize)]
+struct LoudnormReport {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+    output_i: String,
+    output_tp: String,
+    output_lra: String,
+    output_thresh: String,
+    normalization_type: String,
+    target_offset: String,
+}
+
+impl LoudnessOptions {
+    pub fn target(&self) -> Result<LoudnessTarget, String> {
+        let name = self.preset.as_deref().unwrap_or(DEFAULT_LOUDNESS_PRESET);
+        let &(_, integrated, true_peak, lra) = LOUDNESS_PRESETS
+            .iter()
+            .find(|(preset, ..)| *preset == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = LOUDNESS_PRESETS.iter().map(|(preset, ..)| *preset).collect();
+                format!("Preset de volume desconhecido: {} (use {})", name, names.join(", "))
+            })?;
+        let target = LoudnessTarget {
+            integrated: self.integrated.unwrap_or(integrated),
+            true_peak: self.true_peak.unwrap_or(true_peak),
+            lra: self.lra.unwrap_or(lra),
+        };
+
+        // The ranges loudnorm accepts
+        if !(-70.0..=-5.0).contains(&target.integrated) {
+            return Err(format!("Volume alvo inválido: {} LUFS (use de -70 a -5)", target.integrated));
+        }
+        if !(-9.0..=0.0).contains(&target.true_peak) {
+            return Err(format!("Pico máximo inválido: {} dBTP (use de -9 a 0)", target.true_peak));
+        }
+        if !(1.0..=20.0).contains(&target.lra) {
+            return Err(format!("Faixa de volume inválida: {} LU (use de 1 a 20)", target.lra));
+        }
+        Ok(target)
+    }
+}
+
+impl FFmpeg {
+    pub async fn normalize_loudness(&self, request: &LoudnessRequest) -> Result<Vec<LoudnessOutcome>, String> {
+        self.start_normalize(new_conversion_id(), request, NoEvents).await
+    }
+
+    // Writes "<name>-normalized.<ext>" next to each input, measuring it
+    // first and then correcting it to the target in a second pass. Reports
+    // "convert-progress" events over the whole batch; cancel_conversion
+    // stops it with the same id
+    pub async fn start_normalize<S>(
+        &self,
+        conversion_id: String,
+        request: &LoudnessRequest,
+        events: S,
+    ) -> Result<Vec<LoudnessOutcome>, String>
+    where
+        S: EventSink,
+    {
+        if request.input_paths.is_empty() {
+            return Err("Informe ao menos um arquivo".to_string());
+        }
+        let target = request.options.target()?;
+
+        let mut probed = Vec::new();
+        for input in &request.input_paths {
+            probed.push(self.probe_media(input).await.ok().and_then(|m| m.duration));
+        }
+        // Both passes read each file once
+        let total: Option<f64> = probed.iter().map(|d| d.map(|d| d * 2.0)).sum();
+
+        let mut outcomes = Vec::new();
+        let mut done = 0.0;
+        let mut cancelled = false;
+        for (input, duration) in request.input_paths.iter().zip(&probed) {
+            let result = if cancelled {
+                Err("Conversão cancelada".to_string())
+            } else {
+                self.normalize_file(&conversion_id, input, &target, (done, total), &events)
+                    .await
+            };
+            cancelled = cancelled || result.as_ref().is_err_and(|e| e == "Conversão cancelada");
+            done += duration.unwrap_or(0.0) * 2.0;
+
+            outcomes.push(match result {
+                Ok(result) => LoudnessOutcome {
+                    input: input.clone(),
+                    result: Some(result),
+                    error: None,
+                },
+                Err(e) => LoudnessOutcome {
+                    input: input.clone(),
+                    result: None,
+                    error: Some(e),
+                },
+            });
+        }
+
+        let outputs: Vec<&str> = outcomes
+            .iter()
+            .filter_map(|o| o.result.as_ref().map(|r| r.output.as_str()))
+            .collect();
+        events.convert_progress(ConvertProgress {
+            conversion_id,
+            status: "completed".to_string(),
+            progress: 100.0,
+            fps: None,
+            speed: None,
+            eta: None,
+            output: (outputs.len() == 1).then(|| outputs[0].to_string()),
+            streams: Vec::new(),
+        });
+        Ok(outcomes)
+    }
+
+    // Both loudnorm passes over one file. `progress` is the seconds the batch
+    // already covered and its total
+    async fn normalize_file<S>(
+        &self,
+        conversion_id: &str,
+        input_path: &str,
+        target: &LoudnessTarget,
+        progress: (f64, Option<f64>),
+        events: &S,
+    ) -> Result<LoudnessResult, String>
+    where
+        S: EventSink,
+    {
+        let input = PathBuf::from(input_path);
+        if !input.exists() {
+            return Err("Arquivo de entrada não encontrado".to_string());
+        }
+        let (Some(stem), Some(ext)) = (
+            input.file_stem().map(|s| s.to_string_lossy().to_string()),
+            input.extension().map(|e| e.to_string_lossy().to_string()),
+        ) else {
+            return Err("O arquivo de entrada precisa ter uma extensão".to_string());
+        };
+        let output = input
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(format!("{}-normalized.{}", stem, ext));
+
+        let media = self.probe_media(input_path).await.ok();
+        let plan = audio_filter_args(&ext.to_lowercase(), media.as_ref())?;
+        let duration = media.as_ref().and_then(|m| m.duration);
+        let (done, total) = progress;
+
+        let target_filter = format!(
+            "loudnorm=I={}:TP={}:LRA={}",
+            decimal_arg(target.integrated),
+            decimal_arg(target.true_peak),
+            decimal_arg(target.lra)
+        );
+        let mut args: Vec<String> = vec!["-i".to_string(), input_path.to_string()];
+        args.extend(["-vn", "-sn", "-dn", "-af"].map(String::from));
+        args.push(format!("{}:print_format=json", target_filter));
+        args.extend(["-f", "null", "-progress", "pipe:1", "-nostats", "-"].map(String::from));
+        let stderr = self
+            .run_ffmpeg(conversion_id, &args, done, total, &[], events)
+            .await?;
+        let first = parse_loudnorm(&stderr)?;
+        let measured = first.input()?;
+        if !measured.integrated.is_finite() {
+            return Err("O áudio está em silêncio; não há volume para normalizar".to_string());
+        }
+
+        let measured_filter = format!(
+            "{}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true:print_format=json",
+            target_filter,
+            decimal_arg(measured.integrated),
+            decimal_arg(measured.true_peak),
+            decimal_arg(measured.lra),
+            decimal_arg(measured.threshold),
+            decimal_arg(number(&first.target_offset)?)
+        );
+        let mut args = vec!["-i".to_string(), input_path.to_string(), "-y".to_string()];
+        args.extend(plan.args.iter().cloned());
+        args.extend(["-af".to_string(), measured_filter]);
+        args.extend(["-progress", "pipe:1", "-nostats"].map(String::from));
+        args.push(output.to_string_lossy().to_string());
+
+        let second_done = done + duration.unwrap_or(0.0);
+        let second = match self
+            .run_ffmpeg(conversion_id, &args, second_done, total, &plan.streams, events)
+            .await
+            .and_then(|stderr| parse_loudnorm(&stderr))
+        {
+            Ok(report) => report,
+            Err(e) => {
+                remove_partial_output(&input, &output);
+                return Err(e);
+            }
+        };
+
+        Ok(LoudnessResult {
+            input: input_path.to_string(),
+            output: output.to_string_lossy().to_string(),
+            target: *target,
+            measured,
+            normalized: second.output()?,
+            linear: second.normalization_type == "linear",
+        })
+    }
+}
+
+impl LoudnormReport {
+    fn input(&self) -> Result<LoudnessMeasurement, String> {
+        Ok(LoudnessMeasurement {
+            integrated: number(&self.input_i)?,
+            true_peak: number(&self.input_tp)?,
+            lra: number(&self.input_lra)?,
+            threshold: number(&self.input_thresh)?,
+        })
+    }
+
+    fn output(&self) -> Result<LoudnessMeasurement, String> {
+        Ok(LoudnessMeasurement {
+            integrated: number(&self.output_i)?,
+            true_peak: number(&self.output_tp)?,
+            lra: number(&self.output_lra)?,
+            threshold: number(&self.output_thresh)?,
+        })
+    }
+}
+
+// The report is the last JSON object in ffmpeg's log
+fn parse_loudnorm(stderr: &str) -> Result<LoudnormReport, String> {
+    let missing = || "O ffmpeg não informou a medição de volume".to_string();
+    let end = stderr.rfind('}').ok_or_else(missing)?;
+    let start = stderr[..end].rfind('{').ok_or_else(missing)?;
+    serde_json::from_str(&stderr[start..=end]).map_err(|_| missing())
+}
+
+// Silence is measured as "-inf"
+fn number(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("Medição de volume inválida: {}", value))
+}
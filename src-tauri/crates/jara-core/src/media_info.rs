@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+// What ffprobe reports about a media file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaInfo {
+    // ffprobe's format_name, e.g. "matroska,webm" or "mov,mp4,m4a,3gp,3g2,mj2"
+    pub container: String,
+    pub container_name: Option<String>,
+    // Seconds
+    pub duration: Option<f64>,
+    // Bits per second, for the whole file
+    pub bitrate: Option<u64>,
+    pub size: Option<u64>,
+    pub streams: Vec<StreamInfo>,
+    pub chapters: Vec<ChapterInfo>,
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamInfo {
+    pub index: u32,
+    // "video", "audio", "subtitle", "data" or "attachment"
+    pub kind: String,
+    pub codec: Option<String>,
+    pub codec_name: Option<String>,
+    pub profile: Option<String>,
+    pub bitrate: Option<u64>,
+    pub duration: Option<f64>,
+    pub language: Option<String>,
+    pub title: Option<String>,
+    pub default: bool,
+    // Cover art is stored as a one-frame video stream
+    pub attached_pic: bool,
+    // Video
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fps: Option<f64>,
+    pub pixel_format: Option<String>,
+    pub color_transfer: Option<String>,
+    pub color_primaries: Option<String>,
+    // "hdr10", "hlg" or "dolby_vision"
+    pub hdr: Option<String>,
+    // Audio
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+    pub tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterInfo {
+    // Seconds
+    pub start: f64,
+    pub end: f64,
+    pub title: Option<String>,
+}
+
+impl MediaInfo {
+    pub fn streams_of<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a StreamInfo> {
+        self.streams.iter().filter(move |s| s.kind == kind)
+    }
+
+    // The first video stream that isn't cover art
+    pub fn video(&self) -> Option<&StreamInfo> {
+        self.streams_of("video").find(|s| !s.attached_pic)
+    }
+
+    pub fn audio(&self) -> Option<&StreamInfo> {
+        self.streams_of("audio").next()
+    }
+}
+
+// Parses `ffprobe -print_format json -show_format -show_streams -show_chapters`
+pub(crate) fn parse_ffprobe(json: &str) -> Result<MediaInfo, String> {
+    let raw: Value = serde_json::from_str(json).map_err(|e| format!("Falha ao parsear JSON: {}", e))?;
+    let format = &raw["format"];
+    if !format.is_object() {
+        return Err("O ffprobe não reconheceu o arquivo".to_string());
+    }
+
+    let streams = raw["streams"]
+        .as_array()
+        .map(|arr| arr.iter().map(parse_stream).collect())
+        .unwrap_or_default();
+
+    let chapters = raw["chapters"]
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .map(|c| ChapterInfo {
+                    start: number(&c["start_time"]).unwrap_or(0.0),
+                    end: number(&c["end_time"]).unwrap_or(0.0),
+                    title: c["tags"]["title"].as_str().map(String::from),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(MediaInfo {
+        container: format["format_name"].as_str().unwrap_or("").to_string(),
+        container_name: format["format_long_name"].as_str().map(String::from),
+        duration: number(&format["duration"]),
+        bitrate: number(&format["bit_rate"]).map(|b| b as u64),
+        size: number(&format["size"]).map(|s| s as u64),
+        streams,
+        chapters,
+        tags: tags(&format["tags"]),
+    })
+}
+
+fn parse_stream(s: &Value) -> StreamInfo {
+    let text = |key: &str| s[key].as_str().filter(|v| !v.is_empty() && *v != "unknown").map(String::from);
+    let color_transfer = text("color_transfer");
+
+    // Dolby Vision files usually carry an HDR10 or HLG base layer too
+    let dolby_vision = s["side_data_list"]
+        .as_array()
+        .map(|list| {
+            list.iter()
+                .any(|d| d["side_data_type"].as_str() == Some("DOVI configuration record"))
+        })
+        .unwrap_or(false);
+    let hdr = if dolby_vision {
+        Some("dolby_vision".to_string())
+    } else {
+        match color_transfer.as_deref() {
+            Some("smpte2084") => Some("hdr10".to_string()),
+            Some("arib-std-b67") => Some("hlg".to_string()),
+            _ => None,
+        }
+    };
+
+    StreamInfo {
+        index: s["index"].as_u64().unwrap_or(0) as u32,
+        kind: s["codec_type"].as_str().unwrap_or("data").to_string(),
+        codec: text("codec_name"),
+        codec_name: text("codec_long_name"),
+        profile: text("profile"),
+        bitrate: number(&s["bit_rate"]).map(|b| b as u64),
+        duration: number(&s["duration"]),
+        language: s["tags"]["language"].as_str().filter(|l| *l != "und").map(String::from),
+        title: s["tags"]["title"].as_str().map(String::from),
+        default: s["disposition"]["default"].as_i64() == Some(1),
+        attached_pic: s["disposition"]["attached_pic"].as_i64() == Some(1),
+        width: s["width"].as_u64().map(|w| w as u32),
+        height: s["height"].as_u64().map(|h| h as u32),
+        fps: frame_rate(&s["avg_frame_rate"]).or_else(|| frame_rate(&s["r_frame_rate"])),
+        pixel_format: text("pix_fmt"),
+        color_transfer,
+        color_primaries: text("color_primaries"),
+        hdr,
+        sample_rate: number(&s["sample_rate"]).map(|r| r as u32),
+        channels: s["channels"].as_u64().map(|c| c as u32),
+        channel_layout: text("channel_layout"),
+        tags: tags(&s["tags"]),
+    }
+}
+
+// ffprobe writes most numbers as strings
+fn number(value: &Value) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_str().and_then(|v| v.parse().ok()))
+}
+
+// "30000/1001"; audio streams and unknown rates report "0/0"
+fn frame_rate(value: &Value) -> Option<f64> {
+    let (num, den) = value.as_str()?.split_once('/')?;
+    let num: f64 = num.parse().ok()?;
+    let den: f64 = den.parse().ok()?;
+    (num > 0.0 && den > 0.0).then(|| num / den)
+}
+
+fn tags(value: &Value) -> BTreeMap<String, String> {
+    value
+        .as_object()
+        .map(|tags| {
+            tags.iter()
+                .filter_map(|(k, v)| Some((k.to_lowercase(), v.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
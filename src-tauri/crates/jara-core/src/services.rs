@@ -0,0 +1,165 @@
+use crate::bulk_import::{BulkImporter, ImportDefaults, ImportReport};
+use crate::comment_export::CommentExporter;
+use crate::document_convert::DocumentConverter;
+use crate::download_manager::DownloadManager;
+use crate::encoding::ConversionPreset;
+use crate::events::EventSink;
+use crate::ffmpeg::FFmpeg;
+use crate::hooks::PostProcessor;
+use crate::image_convert::{ImageConverter, IMAGE_EXTENSIONS};
+use crate::settings::{DownloadPreset, Settings};
+use crate::subtitle_convert::SubtitleConverter;
+use crate::ytdlp::{check_url, DownloadRequest, YtDlp};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Mutex as TokioMutex;
+
+// External programs the services run
+#[derive(Debug, Clone)]
+pub struct BinaryPaths {
+    pub ytdlp: PathBuf,
+    pub ffmpeg: PathBuf,
+    pub ffprobe: PathBuf,
+}
+
+impl BinaryPaths {
+    // Bundled copies next to the executable first, then the system PATH
+    pub fn detect() -> Self {
+        Self {
+            ytdlp: YtDlp::default_path(),
+            ffmpeg: FFmpeg::default_path(),
+            ffprobe: FFmpeg::default_ffprobe_path(),
+        }
+    }
+}
+
+// Every backend service, wired once and shared by the desktop app, the CLI
+// and the servers. Cloning is cheap and clones share state
+#[derive(Clone)]
+pub struct Services {
+    pub ytdlp: Arc<TokioMutex<YtDlp>>,
+    pub ffmpeg: Arc<FFmpeg>,
+    pub images: Arc<ImageConverter>,
+    pub documents: Arc<DocumentConverter>,
+    pub subtitles: Arc<SubtitleConverter>,
+    pub comments: Arc<CommentExporter>,
+    pub importer: Arc<BulkImporter>,
+    pub downloads: DownloadManager,
+    pub settings: Arc<Mutex<Settings>>,
+}
+
+impl Services {
+    pub fn new<S>(paths: BinaryPaths, settings: Settings, events: S) -> Self
+    where
+        S: EventSink + 'static,
+    {
+        let ytdlp = Arc::new(TokioMutex::new(YtDlp::with_path(paths.ytdlp)));
+        let ffmpeg = Arc::new(FFmpeg::with_paths(paths.ffmpeg, paths.ffprobe));
+        let images = Arc::new(ImageConverter::new());
+        let post_processor = PostProcessor::new(ffmpeg.clone(), images.clone());
+
+        Self {
+            downloads: DownloadManager::new(ytdlp.clone(), ffmpeg.clone(), post_processor, events),
+            ytdlp,
+            ffmpeg,
+            images,
+            documents: Arc::new(DocumentConverter::new()),
+            subtitles: Arc::new(SubtitleConverter::new()),
+            comments: Arc::new(CommentExporter::new()),
+            importer: Arc::new(BulkImporter::new()),
+            settings: Arc::new(Mutex::new(settings)),
+        }
+    }
+
+    // A yt-dlp handle that is known to work. It is cloned out of the lock so
+    // long downloads don't block cancel_download and stop_recording
+    pub async fn ytdlp(&self) -> Result<YtDlp, String> {
+        let mut ytdlp = self.ytdlp.lock().await;
+        ytdlp.ensure_ytdlp_exists().await?;
+        Ok(ytdlp.clone())
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub fn save_settings(&self, settings: Settings) -> Result<(), String> {
+        for preset in &settings.conversion_presets {
+            preset
+                .options
+                .validate()
+                .map_err(|e| format!("Preset de conversão {}: {}", preset.name, e))?;
+        }
+        settings.save()?;
+        *self.settings.lock().unwrap() = settings;
+        Ok(())
+    }
+
+    pub fn list_presets(&self) -> Vec<DownloadPreset> {
+        self.settings.lock().unwrap().all_presets()
+    }
+
+    pub fn list_conversion_presets(&self) -> Vec<ConversionPreset> {
+        self.settings.lock().unwrap().conversion_presets.clone()
+    }
+
+    // Queue a URL with a named preset, or the default one.
+    // Must run inside the Tokio runtime, like DownloadManager::enqueue
+    pub fn enqueue_url(&self, url: &str, preset: Option<&str>) -> Result<String, String> {
+        let url = check_url(url)?;
+        let request = {
+            let settings = self.settings.lock().unwrap();
+            settings
+                .find_preset(preset)?
+                .to_request(&url, &settings.output_path())
+        };
+        Ok(self.downloads.enqueue(request))
+    }
+
+    pub fn import_urls(&self, input_path: &str, defaults: &ImportDefaults) -> Result<ImportReport, String> {
+        let mut report = self.importer.parse(input_path, defaults)?;
+
+        report.job_ids = report
+            .entries
+            .iter()
+            .map(|entry| {
+                self.downloads.enqueue(DownloadRequest {
+                    url: entry.url.clone(),
+                    format_id: entry.format_id.clone(),
+                    output_path: entry.output_path.clone(),
+                    audio_only: entry.audio_only,
+                    download_subs: false,
+                    sub_lang: None,
+                    live: None,
+                    metadata: None,
+                    sections: entry.sections.clone(),
+                    download_archive: None,
+                    post_actions: Vec::new(),
+                    no_playlist: false,
+                    max_filesize: None,
+                })
+            })
+            .collect();
+
+        Ok(report)
+    }
+
+    // Picks the converter from the input and output formats
+    pub async fn convert_by_extension(&self, input_path: &str, output_format: &str) -> Result<String, String> {
+        let input_ext = Path::new(input_path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        if output_format == "pdf" {
+            self.documents.convert(input_path, output_format)
+        } else if IMAGE_EXTENSIONS.contains(&input_ext.as_str())
+            && IMAGE_EXTENSIONS.contains(&output_format)
+        {
+            self.images.convert(input_path, output_format)
+        } else {
+            self.ffmpeg.convert(input_path, output_format).await
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::encoding::ConversionPreset;
+use crate::hooks::PostAction;
+use crate::ytdlp::DownloadRequest;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadPreset {
+    pub name: String,
+    pub format_id: Option<String>,
+    #[serde(default)]
+    pub audio_only: bool,
+    #[serde(default)]
+    pub download_subs: bool,
+    pub sub_lang: Option<String>,
+    #[serde(default)]
+    pub post_actions: Vec<PostAction>,
+}
+
+impl DownloadPreset {
+    pub fn to_request(&self, url: &str, output_path: &str) -> DownloadRequest {
+        DownloadRequest {
+            url: url.to_string(),
+            format_id: self.format_id.clone(),
+            output_path: output_path.to_string(),
+            audio_only: self.audio_only,
+            download_subs: self.download_subs,
+            sub_lang: self.sub_lang.clone(),
+            live: None,
+            metadata: None,
+            sections: Vec::new(),
+            download_archive: None,
+            post_actions: self.post_actions.clone(),
+            no_playlist: false,
+            max_filesize: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiSettings {
+    pub enabled: bool,
+    pub port: u16,
+    // Generated the first time the API is enabled
+    pub token: Option<String>,
+}
+
+impl Default for ApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 47821,
+            token: None,
+        }
+    }
+}
+
+// Backend settings, kept next to the downloaded yt-dlp in the Jara data folder.
+// The frontend keeps its own UI preferences separately
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // Falls back to the system Downloads folder
+    pub default_output_path: Option<String>,
+    pub default_preset: String,
+    // User presets; a preset named like a built-in one replaces it
+    pub presets: Vec<DownloadPreset>,
+    // Named encoding parameters for media conversions
+    pub conversion_presets: Vec<ConversionPreset>,
+    pub clipboard_watcher: bool,
+    // Local control API for browser extensions and scripts
+    pub api: ApiSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            default_output_path: None,
+            default_preset: "video".to_string(),
+            presets: Vec::new(),
+            conversion_presets: Vec::new(),
+            clipboard_watcher: false,
+            api: ApiSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        Self::path()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("Pasta de dados do aplicativo não encontrada")?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Falha ao criar diretório: {}", e))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Falha ao salvar configurações: {}", e))?;
+        std::fs::write(&path, content).map_err(|e| format!("Falha ao salvar configurações: {}", e))
+    }
+
+    fn path() -> Option<PathBuf> {
+        dirs::data_local_dir().map(|dir| dir.join("Jara").join("settings.json"))
+    }
+
+    pub fn output_path(&self) -> String {
+        self.default_output_path
+            .clone()
+            .or_else(|| dirs::download_dir().map(|d| d.to_string_lossy().to_string()))
+            .unwrap_or_else(|| ".".to_string())
+    }
+
+    pub fn all_presets(&self) -> Vec<DownloadPreset> {
+        let mut presets: Vec<DownloadPreset> = builtin_presets()
+            .into_iter()
+            .filter(|b| !self.presets.iter().any(|p| p.name == b.name))
+            .collect();
+        presets.extend(self.presets.iter().cloned());
+        presets
+    }
+
+    pub fn find_preset(&self, name: Option<&str>) -> Result<DownloadPreset, String> {
+        let name = name.unwrap_or(&self.default_preset);
+        self.all_presets()
+            .into_iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("Preset não encontrado: {}", name))
+    }
+
+    pub fn find_conversion_preset(&self, name: &str) -> Result<ConversionPreset, String> {
+        self.conversion_presets
+            .iter()
+            .find(|p| p.name == name)
+            .cloned()
+            .ok_or_else(|| format!("Preset de conversão não encontrado: {}", name))
+    }
+}
+
+fn builtin_presets() -> Vec<DownloadPreset> {
+    vec![
+        DownloadPreset {
+            name: "video".to_string(),
+            format_id: None,
+            audio_only: false,
+            download_subs: false,
+            sub_lang: None,
+            post_actions: Vec::new(),
+        },
+        DownloadPreset {
+            name: "audio".to_string(),
+            format_id: None,
+            audio_only: true,
+            download_subs: false,
+            sub_lang: None,
+            post_actions: Vec::new(),
+        },
+    ]
+}
@@ -0,0 +1,311 @@
+use regex::Regex;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+struct Cue {
+    start_ms: u64,
+    end_ms: u64,
+    text: String,
+}
+
+pub struct SubtitleConverter;
+
+impl SubtitleConverter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn convert(&self, input_path: &str, output_format: &str) -> Result<String, String> {
+        let input = PathBuf::from(input_path);
+
+        if !input.exists() {
+            return Err("Arquivo de entrada não encontrado".to_string());
+        }
+
+        let input_ext = input
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        let output_format = output_format.to_lowercase();
+
+        if !matches!(output_format.as_str(), "srt" | "vtt" | "ass" | "txt") {
+            return Err(format!("Formato não suportado: {}", output_format));
+        }
+        if input_ext == output_format {
+            return Err(format!("O arquivo já está no formato {}", output_format));
+        }
+
+        let content = std::fs::read_to_string(&input)
+            .map_err(|e| format!("Falha ao ler legenda: {}", e))?;
+        // Normalize BOM and Windows line endings before parsing
+        let content = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+
+        let cues = match input_ext.as_str() {
+            "srt" => parse_srt(&content),
+            "vtt" => parse_vtt(&content),
+            "ass" | "ssa" => parse_ass(&content),
+            _ => return Err(format!("Formato de legenda não suportado: {}", input_ext)),
+        };
+
+        if cues.is_empty() {
+            return Err("Nenhuma legenda encontrada no arquivo".to_string());
+        }
+
+        let rendered = match output_format.as_str() {
+            "srt" => write_srt(&cues),
+            "vtt" => write_vtt(&cues),
+            "ass" => write_ass(&cues),
+            _ => write_transcript(&cues),
+        };
+
+        let output = input.with_extension(&output_format);
+        let output_str = output.to_string_lossy().to_string();
+
+        std::fs::write(&output, rendered)
+            .map_err(|e| format!("Falha ao salvar legenda: {}", e))?;
+
+        Ok(output_str)
+    }
+}
+
+impl Default for SubtitleConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Accepts "HH:MM:SS,mmm", "HH:MM:SS.mmm", "MM:SS.mmm" and ASS "H:MM:SS.cc"
+fn parse_timestamp(value: &str) -> Option<u64> {
+    let value = value.trim().replace(',', ".");
+    let (clock, fraction) = value.split_once('.').unwrap_or((&value, "0"));
+
+    let parts: Vec<u64> = clock
+        .split(':')
+        .map(|p| p.parse().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (*h, *m, *s),
+        [m, s] => (0, *m, *s),
+        _ => return None,
+    };
+
+    // Fractions are milliseconds in SRT/VTT and centiseconds in ASS
+    let fraction = format!("{:0<3}", &fraction[..fraction.len().min(3)]);
+    let millis: u64 = fraction.parse().ok()?;
+
+    Some(((hours * 60 + minutes) * 60 + seconds) * 1000 + millis)
+}
+
+fn parse_timing_line(line: &str) -> Option<(u64, u64)> {
+    let (start, rest) = line.split_once("-->")?;
+    // VTT cue settings (align:start position:0%) follow the end time
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+fn parse_srt(content: &str) -> Vec<Cue> {
+    parse_blocks(content)
+}
+
+fn parse_vtt(content: &str) -> Vec<Cue> {
+    let body = content
+        .split("\n\n")
+        .filter(|block| {
+            let first = block.trim_start().lines().next().unwrap_or("");
+            !(first.starts_with("WEBVTT")
+                || first.starts_with("NOTE")
+                || first.starts_with("STYLE")
+                || first.starts_with("REGION"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    parse_blocks(&body)
+}
+
+// SRT and VTT share the same cue layout: an optional identifier line,
+// a timing line and the text lines until the next blank line
+fn parse_blocks(content: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+
+    for block in content.split("\n\n") {
+        let mut lines = block.lines().skip_while(|l| l.trim().is_empty());
+        let mut timing = None;
+
+        for line in lines.by_ref() {
+            if let Some(parsed) = parse_timing_line(line) {
+                timing = Some(parsed);
+                break;
+            }
+        }
+
+        if let Some((start_ms, end_ms)) = timing {
+            let text = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+            if !text.is_empty() {
+                cues.push(Cue { start_ms, end_ms, text });
+            }
+        }
+    }
+
+    cues
+}
+
+fn parse_ass(content: &str) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut in_events = false;
+    let mut fields: Vec<String> = Vec::new();
+    let override_regex = Regex::new(r"\{[^}]*\}").unwrap();
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.starts_with('[') {
+            in_events = line.eq_ignore_ascii_case("[events]");
+            continue;
+        }
+        if !in_events {
+            continue;
+        }
+
+        if let Some(format) = line.strip_prefix("Format:") {
+            fields = format.split(',').map(|f| f.trim().to_lowercase()).collect();
+        } else if let Some(dialogue) = line.strip_prefix("Dialogue:") {
+            if fields.is_empty() {
+                continue;
+            }
+            // Text is always the last field and may itself contain commas
+            let values: Vec<&str> = dialogue.trim().splitn(fields.len(), ',').collect();
+            let field = |name: &str| {
+                fields
+                    .iter()
+                    .position(|f| f == name)
+                    .and_then(|i| values.get(i).copied())
+            };
+
+            let (Some(start), Some(end), Some(text)) = (field("start"), field("end"), field("text"))
+            else {
+                continue;
+            };
+            let (Some(start_ms), Some(end_ms)) = (parse_timestamp(start), parse_timestamp(end)) else {
+                continue;
+            };
+
+            let text = override_regex
+                .replace_all(text, "")
+                .replace("\\N", "\n")
+                .replace("\\n", "\n")
+                .replace("\\h", " ");
+            let text = text.trim().to_string();
+
+            if !text.is_empty() {
+                cues.push(Cue { start_ms, end_ms, text });
+            }
+        }
+    }
+
+    cues.sort_by_key(|c| c.start_ms);
+    cues
+}
+
+fn format_timestamp(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+fn format_ass_timestamp(ms: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        ms / 3_600_000,
+        (ms / 60_000) % 60,
+        (ms / 1000) % 60,
+        (ms % 1000) / 10
+    )
+}
+
+// Removes VTT karaoke timestamps, class spans and any other markup
+fn strip_tags(text: &str) -> String {
+    let tag_regex = Regex::new(r"<[^>]*>").unwrap();
+    tag_regex
+        .replace_all(text, "")
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+}
+
+fn write_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!(
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(cue.start_ms, ','),
+            format_timestamp(cue.end_ms, ','),
+            strip_tags(&cue.text)
+        ));
+    }
+    out
+}
+
+fn write_vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        out.push_str(&format!(
+            "{} --> {}\n{}\n\n",
+            format_timestamp(cue.start_ms, '.'),
+            format_timestamp(cue.end_ms, '.'),
+            cue.text
+        ));
+    }
+    out
+}
+
+fn write_ass(cues: &[Cue]) -> String {
+    let mut out = String::from(
+        "[Script Info]\n\
+         ScriptType: v4.00+\n\
+         PlayResX: 1920\n\
+         PlayResY: 1080\n\
+         \n\
+         [V4+ Styles]\n\
+         Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding\n\
+         Style: Default,Arial,56,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,1,2,40,40,40,1\n\
+         \n\
+         [Events]\n\
+         Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n",
+    );
+    for cue in cues {
+        out.push_str(&format!(
+            "Dialogue: 0,{},{},Default,,0,0,0,,{}\n",
+            format_ass_timestamp(cue.start_ms),
+            format_ass_timestamp(cue.end_ms),
+            strip_tags(&cue.text).replace('\n', "\\N")
+        ));
+    }
+    out
+}
+
+fn write_transcript(cues: &[Cue]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+
+    for cue in cues {
+        for line in strip_tags(&cue.text).lines() {
+            let line = line.trim();
+            // Auto-captions repeat the previous line in every rolling cue
+            if !line.is_empty() && lines.last().map(|l| l != line).unwrap_or(true) {
+                lines.push(line.to_string());
+            }
+        }
+    }
+
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
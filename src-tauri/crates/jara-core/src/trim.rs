@@ -0,0 +1,255 @@
+use crate::encoding::{reencode_args, ConversionOptions, StreamPlan};
+use crate::events::{EventSink, NoEvents};
+use crate::ffmpeg::{
+    decimal_arg, new_conversion_id, remove_partial_output, write_concat_list, ConvertProgress, FFmpeg,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrimMode {
+    // Stream copy; each cut starts at the keyframe at or before its start
+    #[default]
+    Fast,
+    // Re-encodes so every cut lands on the exact frame
+    Precise,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    // Seconds
+    pub start: f64,
+    // None keeps everything until the end of the file
+    pub end: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrimRequest {
+    pub input_path: String,
+    // Parts to keep, in output order
+    pub ranges: Vec<TimeRange>,
+    #[serde(default)]
+    pub mode: TrimMode,
+    // One file with every range instead of one file per range
+    #[serde(default)]
+    pub join: bool,
+}
+
+impl TimeRange {
+    // "1:30-2:45", "90-120.5" or "1:00:00-" for everything after an hour
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || format!("Trecho inválido: {} (use início-fim, ex.: 0:30-1:45)", value);
+        let (start, end) = value.split_once('-').ok_or_else(invalid)?;
+        let start = parse_time(start).ok_or_else(invalid)?;
+        let end = match end.trim() {
+            "" => None,
+            end => Some(parse_time(end).ok_or_else(invalid)?),
+        };
+        Ok(Self { start, end })
+    }
+}
+
+// Seconds, "MM:SS" or "HH:MM:SS", with optional decimals on the seconds
+pub fn parse_time(value: &str) -> Option<f64> {
+    let parts: Vec<&str> = value.trim().split(':').collect();
+    if parts.len() > 3 {
+        return None;
+    }
+
+    let mut seconds = 0.0;
+    for (i, part) in parts.iter().enumerate() {
+        let last = i == parts.len() - 1;
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit() || (last && c == '.')) {
+            return None;
+        }
+        let number: f64 = part.parse().ok()?;
+        if i > 0 && number >= 60.0 {
+            return None;
+        }
+        seconds = seconds * 60.0 + number;
+    }
+    Some(seconds)
+}
+
+impl FFmpeg {
+    pub async fn trim_media(&self, request: &TrimRequest) -> Result<Vec<String>, String> {
+        self.start_trim(new_conversion_id(), request, NoEvents).await
+    }
+
+    // Writes "<name>-trim.<ext>", or "<name>-trim-N.<ext>" per range when the
+    // ranges aren't joined. Reports "convert-progress" events over the whole
+    // job; cancel_conversion stops it with the same id
+    pub async fn start_trim<S>(&self, conversion_id: String, request: &TrimRequest, events: S) -> Result<Vec<String>, String>
+    where
+        S: EventSink,
+    {
+        let input = PathBuf::from(&request.input_path);
+        if !input.exists() {
+            return Err("Arquivo de entrada não encontrado".to_string());
+        }
+        if request.ranges.is_empty() {
+            return Err("Informe ao menos um trecho".to_string());
+        }
+        let (Some(stem), Some(ext)) = (
+            input.file_stem().map(|s| s.to_string_lossy().to_string()),
+            input.extension().map(|e| e.to_string_lossy().to_string()),
+        ) else {
+            return Err("O arquivo de entrada precisa ter uma extensão".to_string());
+        };
+        let dir = input.parent().unwrap_or(Path::new("."));
+
+        let media = self.probe_media(&request.input_path).await.ok();
+        let duration = media.as_ref().and_then(|m| m.duration);
+        let ranges = resolve_ranges(&request.ranges, duration)?;
+
+        let (codec_args, streams) = match request.mode {
+            TrimMode::Fast => (
+                ["-map", "0", "-c", "copy", "-avoid_negative_ts", "make_zero"]
+                    .map(String::from)
+                    .to_vec(),
+                media
+                    .iter()
+                    .flat_map(|m| &m.streams)
+                    .map(|s| StreamPlan {
+                        kind: s.kind.clone(),
+                        source_codec: s.codec.clone(),
+                        action: "copy".to_string(),
+                        encoder: None,
+                    })
+                    .collect(),
+            ),
+            TrimMode::Precise => {
+                let plan = reencode_args(&ext.to_lowercase(), &ConversionOptions::default(), media.as_ref())?;
+                (plan.args, plan.streams)
+            }
+        };
+
+        let joined = request.join && ranges.len() > 1;
+        let output = dir.join(format!("{}-trim.{}", stem, ext));
+        let part_path = |i: usize| match (ranges.len(), joined) {
+            (1, _) => output.clone(),
+            // Hidden until they are joined
+            (_, true) => dir.join(format!(".{}-trim-{}.{}", stem, i + 1, ext)),
+            (_, false) => dir.join(format!("{}-trim-{}.{}", stem, i + 1, ext)),
+        };
+
+        // Joining copies everything once more
+        let kept: Option<f64> = ranges.iter().map(|(start, end)| end.map(|end| end - start)).sum();
+        let total = kept.map(|kept| if joined { kept * 2.0 } else { kept });
+
+        let mut parts = Vec::new();
+        let mut done = 0.0;
+        for (i, (start, end)) in ranges.iter().enumerate() {
+            let part = part_path(i);
+            let mut args = vec!["-ss".to_string(), decimal_arg(*start)];
+            if let Some(end) = end {
+                args.extend(["-t".to_string(), decimal_arg(end - start)]);
+            }
+            args.extend(["-i".to_string(), request.input_path.clone(), "-y".to_string()]);
+            args.extend(codec_args.iter().cloned());
+            args.extend(["-progress".to_string(), "pipe:1".to_string(), "-nostats".to_string()]);
+            args.push(part.to_string_lossy().to_string());
+
+            parts.push(part);
+            if let Err(e) = self
+                .run_ffmpeg(&conversion_id, &args, done, total, &streams, &events)
+                .await
+            {
+                remove_all(&input, &parts);
+                return Err(e);
+            }
+            done += end.map(|end| end - start).unwrap_or(0.0);
+        }
+
+        let outputs = if joined {
+            let list_path = output.with_extension("concat.txt");
+            let result = match write_concat_list(&list_path, &parts) {
+                Ok(()) => {
+                    self.run_ffmpeg(&conversion_id, &concat_args(&list_path, &output), done, total, &streams, &events)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            let _ = std::fs::remove_file(&list_path);
+            remove_all(&input, &parts);
+            if let Err(e) = result {
+                remove_partial_output(&input, &output);
+                return Err(e);
+            }
+            vec![output.to_string_lossy().to_string()]
+        } else {
+            parts.iter().map(|p| p.to_string_lossy().to_string()).collect()
+        };
+
+        events.convert_progress(ConvertProgress {
+            conversion_id,
+            status: "completed".to_string(),
+            progress: 100.0,
+            fps: None,
+            speed: None,
+            eta: None,
+            output: (outputs.len() == 1).then(|| outputs[0].clone()),
+            streams,
+        });
+        Ok(outputs)
+    }
+}
+
+// Checks the ranges against each other and the file, and clamps open or
+// overlong ends to the duration when it is known
+fn resolve_ranges(ranges: &[TimeRange], duration: Option<f64>) -> Result<Vec<(f64, Option<f64>)>, String> {
+    ranges
+        .iter()
+        .map(|range| {
+            if let Some(end) = range.end {
+                if end <= range.start {
+                    return Err(format!(
+                        "Trecho inválido: o fim ({}) precisa vir depois do início ({})",
+                        format_time(end),
+                        format_time(range.start)
+                    ));
+                }
+            }
+            let end = match (range.end, duration) {
+                (Some(end), Some(total)) => Some(end.min(total)),
+                (end, total) => end.or(total),
+            };
+            if let Some(total) = duration {
+                if range.start >= total {
+                    return Err(format!(
+                        "O trecho começa em {}, depois do fim do arquivo ({})",
+                        format_time(range.start),
+                        format_time(total)
+                    ));
+                }
+            }
+            Ok((range.start, end))
+        })
+        .collect()
+}
+
+fn concat_args(list_path: &Path, output: &Path) -> Vec<String> {
+    let mut args: Vec<String> = ["-f", "concat", "-safe", "0", "-i"].map(String::from).to_vec();
+    args.push(list_path.to_string_lossy().to_string());
+    args.extend(["-y", "-map", "0", "-c", "copy", "-progress", "pipe:1", "-nostats"].map(String::from));
+    args.push(output.to_string_lossy().to_string());
+    args
+}
+
+fn format_time(seconds: f64) -> String {
+    let whole = seconds as u64;
+    let fraction = seconds - whole as f64;
+    let clock = format!("{}:{:02}:{:02}", whole / 3600, whole / 60 % 60, whole % 60);
+    if fraction >= 0.001 {
+        format!("{}{}", clock, &format!("{:.3}", fraction)[1..])
+    } else {
+        clock
+    }
+}
+
+fn remove_all(input: &Path, files: &[PathBuf]) {
+    for file in files {
+        remove_partial_output(input, file);
+    }
+}
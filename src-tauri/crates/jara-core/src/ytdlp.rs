@@ -0,0 +1,1138 @@
+use futures_util::StreamExt;
+use crate::events::EventSink;
+use crate::ffmpeg::FFmpeg;
+use crate::media_info::MediaInfo;
+use crate::hooks::PostAction;
+use crate::image_convert::{ImageConverter, ImageOptions};
+use crate::subtitle_convert::SubtitleConverter;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::Mutex;
+
+const YTDLP_DOWNLOAD_URL: &str = "https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp.exe";
+
+// Windows: hide console window
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+// Helper to create command with hidden window on Windows
+pub(crate) fn create_hidden_command(program: &PathBuf) -> Command {
+    #[cfg_attr(not(windows), allow(unused_mut))]
+    let mut cmd = Command::new(program);
+    #[cfg(windows)]
+    cmd.creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+// URLs from outside the app must be http(s), so yt-dlp never reads one as an
+// option. Returns the URL normalized
+pub fn check_url(url: &str) -> Result<String, String> {
+    let target = url::Url::parse(url).map_err(|_| "URL de download inválida".to_string())?;
+    if !matches!(target.scheme(), "http" | "https") || target.host_str().is_none() {
+        return Err("A URL de download deve ser http ou https".to_string());
+    }
+    Ok(target.to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoInfo {
+    pub id: String,
+    pub title: String,
+    pub thumbnail: Option<String>,
+    pub duration: Option<f64>,
+    pub duration_string: Option<String>,
+    pub uploader: Option<String>,
+    pub view_count: Option<u64>,
+    pub formats: Vec<VideoFormat>,
+    pub thumbnails: Vec<Thumbnail>,
+    pub is_live: bool,
+    // "is_live", "is_upcoming", "was_live", "post_live" or "not_live"
+    pub live_status: Option<String>,
+    // Scheduled start of upcoming streams and premieres (unix timestamp)
+    pub release_timestamp: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoFormat {
+    pub format_id: String,
+    pub format_note: Option<String>,
+    pub ext: String,
+    pub resolution: Option<String>,
+    pub filesize: Option<u64>,
+    pub filesize_approx: Option<u64>,
+    pub vcodec: Option<String>,
+    pub acodec: Option<String>,
+    pub quality: Option<f64>,
+    pub fps: Option<f64>,
+    pub tbr: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Thumbnail {
+    pub id: String,
+    pub url: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub preference: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadProgress {
+    pub download_id: String,
+    pub status: String,
+    pub progress: f64,
+    pub speed: Option<String>,
+    pub eta: Option<String>,
+    pub filename: Option<String>,
+    // Live recordings have no known total, so they report these instead of a percentage
+    pub elapsed: Option<f64>,
+    pub downloaded_bytes: Option<u64>,
+    // Every file the download left behind, sent with "completed"
+    #[serde(default)]
+    pub files: Vec<OutputFile>,
+}
+
+impl DownloadProgress {
+    pub fn completed(download_id: &str, files: Vec<OutputFile>) -> Self {
+        Self {
+            download_id: download_id.to_string(),
+            status: "completed".to_string(),
+            progress: 100.0,
+            speed: None,
+            eta: None,
+            // The main file, for clients that only show one
+            filename: files.last().map(|f| f.path.clone()),
+            elapsed: None,
+            downloaded_bytes: None,
+            files,
+        }
+    }
+}
+
+// A finished file, after merging, audio extraction and post-download actions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputFile {
+    pub path: String,
+    pub size: u64,
+    // None when ffprobe is missing or can't read the file
+    pub media: Option<MediaInfo>,
+}
+
+impl OutputFile {
+    // Files that no longer exist are left out
+    pub async fn inspect_all(paths: &[String], ffmpeg: &FFmpeg) -> Vec<OutputFile> {
+        let mut files = Vec::new();
+        for path in paths {
+            let Ok(metadata) = std::fs::metadata(path) else {
+                continue;
+            };
+            files.push(OutputFile {
+                path: path.clone(),
+                size: metadata.len(),
+                media: ffmpeg.probe_media(path).await.ok(),
+            });
+        }
+        files
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LiveOptions {
+    // Record from the start of the stream when the site keeps the whole DVR window
+    #[serde(default)]
+    pub from_start: bool,
+    // Stop after this many seconds of recording
+    pub max_duration: Option<u64>,
+    // Stop at this wall-clock time (unix timestamp)
+    pub stop_at: Option<i64>,
+    // Wait for scheduled streams and premieres and start automatically
+    #[serde(default)]
+    pub wait_for_start: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetadataOptions {
+    #[serde(default)]
+    pub write_info_json: bool,
+    #[serde(default)]
+    pub write_description: bool,
+    // Comments are stored inside the info JSON
+    #[serde(default)]
+    pub write_comments: bool,
+    pub max_comments: Option<u32>,
+    // "top" or "new"
+    pub comment_sort: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadRequest {
+    pub url: String,
+    pub format_id: Option<String>,
+    pub output_path: String,
+    pub audio_only: bool,
+    #[serde(default)]
+    pub download_subs: bool,
+    pub sub_lang: Option<String>,
+    // Present when the request is a live stream recording
+    pub live: Option<LiveOptions>,
+    // Archival files written next to the media
+    pub metadata: Option<MetadataOptions>,
+    // yt-dlp --download-sections ranges, e.g. "*0:00-1:30"
+    #[serde(default)]
+    pub sections: Vec<String>,
+    // yt-dlp --download-archive file; URLs already listed there are skipped
+    #[serde(default)]
+    pub download_archive: Option<String>,
+    // Run in order once the download completes
+    #[serde(default)]
+    pub post_actions: Vec<PostAction>,
+    // Only the video, even when the URL also points into a playlist
+    #[serde(default)]
+    pub no_playlist: bool,
+    // yt-dlp --max-filesize in bytes; larger files are not downloaded
+    #[serde(default)]
+    pub max_filesize: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtitleRequest {
+    pub url: String,
+    pub output_path: String,
+    // yt-dlp language codes such as "pt", "en" or "all"
+    pub languages: Vec<String>,
+    // Fall back to automatically generated captions
+    #[serde(default)]
+    pub auto_captions: bool,
+    // "srt", "vtt", "ass" or "txt" for a plain-text transcript
+    pub format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThumbnailRequest {
+    pub url: String,
+    pub output_path: String,
+    // Thumbnail id from VideoInfo.thumbnails; the best one when absent
+    pub thumbnail_id: Option<String>,
+    // Output image format, as accepted by ImageConverter
+    pub format: String,
+    #[serde(flatten)]
+    pub options: ImageOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub duration: Option<f64>,
+    pub duration_string: Option<String>,
+    pub thumbnail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistInfo {
+    pub id: String,
+    pub title: String,
+    pub uploader: Option<String>,
+    pub entries: Vec<PlaylistEntry>,
+    pub entry_count: usize,
+}
+
+// A running yt-dlp process. stopped is set when the recording is stopped on
+// purpose, by its time limit or by stop_recording
+struct ActiveDownload {
+    child: tokio::process::Child,
+    stopped: Arc<AtomicBool>,
+}
+
+#[derive(Clone)]
+pub struct YtDlp {
+    exe_path: PathBuf,
+    active_downloads: Arc<Mutex<HashMap<String, ActiveDownload>>>,
+}
+
+impl YtDlp {
+    pub fn new() -> Self {
+        Self::with_path(Self::default_path())
+    }
+
+    pub fn with_path(exe_path: PathBuf) -> Self {
+        Self {
+            exe_path,
+            active_downloads: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn default_path() -> PathBuf {
+        // First try to find bundled yt-dlp next to the executable
+        if let Ok(exe_path) = std::env::current_exe() {
+            if let Some(exe_dir) = exe_path.parent() {
+                let ytdlp_path = exe_dir.join("yt-dlp.exe");
+                if ytdlp_path.exists() {
+                    return ytdlp_path;
+                }
+            }
+        }
+
+        // Try app data directory
+        if let Some(data_dir) = dirs::data_local_dir() {
+            let ytdlp_path = data_dir.join("Jara").join("yt-dlp.exe");
+            if ytdlp_path.exists() {
+                return ytdlp_path;
+            }
+        }
+
+        // Fallback to system PATH
+        PathBuf::from("yt-dlp")
+    }
+
+    fn get_download_path() -> PathBuf {
+        // Prefer app data directory for downloads
+        if let Some(data_dir) = dirs::data_local_dir() {
+            let jara_dir = data_dir.join("Jara");
+            if std::fs::create_dir_all(&jara_dir).is_ok() {
+                return jara_dir.join("yt-dlp.exe");
+            }
+        }
+
+        // Fallback to next to executable
+        if let Ok(exe_path) = std::env::current_exe() {
+            if let Some(exe_dir) = exe_path.parent() {
+                return exe_dir.join("yt-dlp.exe");
+            }
+        }
+
+        PathBuf::from("yt-dlp.exe")
+    }
+
+    pub async fn ensure_ytdlp_exists(&mut self) -> Result<(), String> {
+        // Check if yt-dlp already works
+        let test = create_hidden_command(&self.exe_path)
+            .arg("--version")
+            .output()
+            .await;
+
+        if test.is_ok() && test.unwrap().status.success() {
+            return Ok(());
+        }
+
+        // Need to download yt-dlp
+        println!("yt-dlp não encontrado, baixando...");
+        
+        let download_path = Self::get_download_path();
+        
+        // Download yt-dlp
+        let response = reqwest::get(YTDLP_DOWNLOAD_URL)
+            .await
+            .map_err(|e| format!("Falha ao baixar yt-dlp: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Falha ao baixar yt-dlp: HTTP {}", response.status()));
+        }
+
+        // Create parent directory if needed
+        if let Some(parent) = download_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Falha ao criar diretório: {}", e))?;
+        }
+
+        // Write to file
+        let mut file = tokio::fs::File::create(&download_path)
+            .await
+            .map_err(|e| format!("Falha ao criar arquivo: {}", e))?;
+
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| format!("Erro ao baixar: {}", e))?;
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Erro ao escrever: {}", e))?;
+        }
+
+        // Update exe_path
+        self.exe_path = download_path;
+        println!("yt-dlp baixado com sucesso!");
+
+        Ok(())
+    }
+
+    // Check if URL is a playlist
+    pub async fn is_playlist(&self, url: &str) -> bool {
+        url.contains("playlist") || url.contains("list=")
+    }
+
+    // Names of every extractor this yt-dlp build supports, e.g. "youtube", "vimeo:album"
+    pub async fn list_extractors(&self) -> Result<Vec<String>, String> {
+        let output = create_hidden_command(&self.exe_path)
+            .arg("--list-extractors")
+            .output()
+            .await
+            .map_err(|e| format!("Falha ao executar yt-dlp: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("yt-dlp error: {}", stderr));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect())
+    }
+
+    pub async fn get_playlist_info(&self, url: &str) -> Result<PlaylistInfo, String> {
+        let output = create_hidden_command(&self.exe_path)
+            .args([
+                "--flat-playlist",
+                "--dump-json",
+                "--no-warnings",
+                "--no-check-certificates",
+                "--",
+                url
+            ])
+            .output()
+            .await
+            .map_err(|e| format!("Falha ao executar yt-dlp: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("yt-dlp error: {}", stderr));
+        }
+
+        let json_str = String::from_utf8_lossy(&output.stdout);
+        let raw: serde_json::Value =
+            serde_json::from_str(&json_str).map_err(|e| format!("Falha ao parsear JSON: {}", e))?;
+
+        let entries: Vec<PlaylistEntry> = raw["entries"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .map(|e| PlaylistEntry {
+                        id: e["id"].as_str().unwrap_or("").to_string(),
+                        title: e["title"].as_str().unwrap_or("Sem título").to_string(),
+                        url: e["url"].as_str().or(e["webpage_url"].as_str()).unwrap_or("").to_string(),
+                        duration: e["duration"].as_f64(),
+                        duration_string: e["duration_string"].as_str().map(String::from),
+                        thumbnail: e["thumbnail"].as_str().map(String::from),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let entry_count = entries.len();
+
+        Ok(PlaylistInfo {
+            id: raw["id"].as_str().unwrap_or("").to_string(),
+            title: raw["title"].as_str().unwrap_or("Playlist").to_string(),
+            uploader: raw["uploader"].as_str().map(String::from),
+            entries,
+            entry_count,
+        })
+    }
+
+    pub async fn get_video_info(&self, url: &str) -> Result<VideoInfo, String> {
+        let output = create_hidden_command(&self.exe_path)
+            .args([
+                "--dump-json",
+                "--no-playlist",
+                "--no-warnings",
+                "--no-check-certificates",
+                "--prefer-free-formats",
+                "--socket-timeout", "10",
+                "--",
+                url
+            ])
+            .output()
+            .await
+            .map_err(|e| format!("Falha ao executar yt-dlp: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("yt-dlp error: {}", stderr));
+        }
+
+        let json_str = String::from_utf8_lossy(&output.stdout);
+        let raw: serde_json::Value =
+            serde_json::from_str(&json_str).map_err(|e| format!("Falha ao parsear JSON: {}", e))?;
+
+        let formats = raw["formats"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|f| {
+                        Some(VideoFormat {
+                            format_id: f["format_id"].as_str()?.to_string(),
+                            format_note: f["format_note"].as_str().map(String::from),
+                            ext: f["ext"].as_str().unwrap_or("mp4").to_string(),
+                            resolution: f["resolution"].as_str().map(String::from),
+                            filesize: f["filesize"].as_u64(),
+                            filesize_approx: f["filesize_approx"].as_u64(),
+                            vcodec: f["vcodec"].as_str().map(String::from),
+                            acodec: f["acodec"].as_str().map(String::from),
+                            quality: f["quality"].as_f64(),
+                            fps: f["fps"].as_f64(),
+                            tbr: f["tbr"].as_f64(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let thumbnails = raw["thumbnails"]
+            .as_array()
+            .map(|arr| {
+                arr.iter()
+                    .enumerate()
+                    .filter_map(|(i, t)| {
+                        Some(Thumbnail {
+                            id: t["id"].as_str().map(String::from).unwrap_or_else(|| i.to_string()),
+                            url: t["url"].as_str()?.to_string(),
+                            width: t["width"].as_u64().map(|w| w as u32),
+                            height: t["height"].as_u64().map(|h| h as u32),
+                            preference: t["preference"].as_i64(),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(VideoInfo {
+            id: raw["id"].as_str().unwrap_or("").to_string(),
+            title: raw["title"].as_str().unwrap_or("Sem título").to_string(),
+            thumbnail: raw["thumbnail"].as_str().map(String::from),
+            duration: raw["duration"].as_f64(),
+            duration_string: raw["duration_string"].as_str().map(String::from),
+            uploader: raw["uploader"].as_str().map(String::from),
+            view_count: raw["view_count"].as_u64(),
+            formats,
+            thumbnails,
+            is_live: raw["is_live"].as_bool().unwrap_or(false),
+            live_status: raw["live_status"].as_str().map(String::from),
+            release_timestamp: raw["release_timestamp"].as_i64(),
+        })
+    }
+
+    // Download only the subtitles of a video, converted to the requested format
+    pub async fn download_subtitles(&self, request: &SubtitleRequest) -> Result<Vec<String>, String> {
+        let format = request.format.to_lowercase();
+        // The transcript is produced from SRT by SubtitleConverter
+        let ytdlp_format = match format.as_str() {
+            "srt" | "vtt" | "ass" => format.as_str(),
+            "txt" => "srt",
+            _ => return Err(format!("Formato de legenda não suportado: {}", request.format)),
+        };
+
+        let languages: Vec<&str> = request
+            .languages
+            .iter()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect();
+        if languages.is_empty() {
+            return Err("Selecione pelo menos um idioma de legenda".to_string());
+        }
+
+        let mut args = vec![
+            "--skip-download".to_string(),
+            "--no-playlist".to_string(),
+            "--no-warnings".to_string(),
+            "--write-subs".to_string(),
+        ];
+        if request.auto_captions {
+            args.push("--write-auto-subs".to_string());
+        }
+        args.extend([
+            "--sub-langs".to_string(),
+            languages.join(","),
+            "--convert-subs".to_string(),
+            ytdlp_format.to_string(),
+            "-o".to_string(),
+            format!("{}/%(title)s.%(ext)s", request.output_path),
+            "--".to_string(),
+            request.url.clone(),
+        ]);
+
+        let output = create_hidden_command(&self.exe_path)
+            .args(&args)
+            .output()
+            .await
+            .map_err(|e| format!("Falha ao executar yt-dlp: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("yt-dlp error: {}", stderr));
+        }
+
+        // yt-dlp logs the original file; --convert-subs then swaps its extension
+        let written_regex = Regex::new(r"Writing video subtitles to: (.+)").unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let downloaded: Vec<PathBuf> = stdout
+            .lines()
+            .filter_map(|line| written_regex.captures(line))
+            .map(|caps| PathBuf::from(caps[1].trim()).with_extension(ytdlp_format))
+            .collect();
+
+        if downloaded.is_empty() {
+            return Err("Nenhuma legenda disponível para os idiomas selecionados".to_string());
+        }
+
+        if format != "txt" {
+            return Ok(downloaded
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect());
+        }
+
+        let converter = SubtitleConverter::new();
+        let mut transcripts = Vec::new();
+        for srt in downloaded {
+            let srt_path = srt.to_string_lossy().to_string();
+            transcripts.push(converter.convert(&srt_path, "txt")?);
+            let _ = std::fs::remove_file(&srt);
+        }
+
+        Ok(transcripts)
+    }
+
+    // Download a video's thumbnail and convert it to the requested format and size
+    pub async fn download_thumbnail(&self, request: &ThumbnailRequest) -> Result<String, String> {
+        let info = self.get_video_info(&request.url).await?;
+
+        let thumbnail = match &request.thumbnail_id {
+            Some(id) => info
+                .thumbnails
+                .iter()
+                .find(|t| &t.id == id)
+                .ok_or_else(|| format!("Miniatura não encontrada: {}", id))?,
+            // yt-dlp lists thumbnails from worst to best, but not every
+            // extractor fills in preference or dimensions
+            None => info
+                .thumbnails
+                .iter()
+                .enumerate()
+                .max_by_key(|(i, t)| {
+                    (
+                        t.preference.unwrap_or(0),
+                        t.width.unwrap_or(0) as u64 * t.height.unwrap_or(0) as u64,
+                        *i,
+                    )
+                })
+                .map(|(_, t)| t)
+                .ok_or("Este vídeo não possui miniaturas")?,
+        };
+
+        let response = reqwest::get(&thumbnail.url)
+            .await
+            .map_err(|e| format!("Falha ao baixar miniatura: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Falha ao baixar miniatura: HTTP {}", response.status()));
+        }
+
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Erro ao baixar: {}", e))?;
+
+        // Servers often send a wrong or generic Content-Type, so the bytes decide
+        let source_ext = image::guess_format(&bytes)
+            .ok()
+            .and_then(|format| format.extensions_str().first().copied())
+            .ok_or("A miniatura baixada não é uma imagem reconhecida")?;
+
+        let source = PathBuf::from(&request.output_path)
+            .join(format!("{}.{}", sanitize_filename(&info.title), source_ext));
+        tokio::fs::write(&source, &bytes)
+            .await
+            .map_err(|e| format!("Erro ao escrever: {}", e))?;
+
+        let source_str = source.to_string_lossy().to_string();
+        let result = ImageConverter::new().convert_with_options(
+            &source_str,
+            &request.format,
+            &request.options,
+        );
+
+        // Keep only the converted image unless it overwrote the source
+        let converted_in_place = result.as_deref().map(|p| p == source_str).unwrap_or(false);
+        if !converted_in_place {
+            let _ = std::fs::remove_file(&source);
+        }
+
+        result
+    }
+
+    // Returns the paths of the finished files. Callers report "completed",
+    // since they may still have work to do on the files
+    pub async fn start_download<S>(
+        &self,
+        download_id: String,
+        request: &DownloadRequest,
+        events: S,
+    ) -> Result<Vec<String>, String>
+    where
+        S: EventSink + 'static,
+    {
+        let on_progress = move |progress: DownloadProgress| events.download_progress(progress);
+        let live = request.live.clone();
+
+        if let Some(stop_at) = live.as_ref().and_then(|l| l.stop_at) {
+            if stop_at <= unix_now() {
+                return Err("O horário de término da gravação já passou".to_string());
+            }
+        }
+
+        let mut args = vec![
+            "--newline".to_string(),
+            "--progress".to_string(),
+            "-o".to_string(),
+            format!("{}/%(title)s.%(ext)s", request.output_path),
+        ];
+
+        if request.audio_only {
+            args.push("-x".to_string());
+            args.push("--audio-format".to_string());
+            args.push("mp3".to_string());
+            args.push("--audio-quality".to_string());
+            args.push("0".to_string());
+        } else if let Some(fmt) = &request.format_id {
+            args.push("-f".to_string());
+            args.push(fmt.clone());
+        }
+
+        // Subtitle options
+        if request.download_subs {
+            args.push("--write-subs".to_string());
+            args.push("--embed-subs".to_string());
+            if let Some(lang) = &request.sub_lang {
+                args.push("--sub-lang".to_string());
+                args.push(lang.clone());
+            } else {
+                args.push("--sub-lang".to_string());
+                args.push("pt,en".to_string());
+            }
+        }
+
+        if let Some(archive) = &request.download_archive {
+            args.push("--download-archive".to_string());
+            args.push(archive.clone());
+        }
+
+        if request.no_playlist {
+            args.push("--no-playlist".to_string());
+        }
+        if let Some(max) = request.max_filesize {
+            args.push("--max-filesize".to_string());
+            args.push(max.to_string());
+        }
+
+        for section in &request.sections {
+            args.push("--download-sections".to_string());
+            args.push(section.clone());
+        }
+
+        // Archival metadata options
+        if let Some(metadata) = &request.metadata {
+            if metadata.write_info_json || metadata.write_comments {
+                args.push("--write-info-json".to_string());
+            }
+            if metadata.write_description {
+                args.push("--write-description".to_string());
+            }
+            if metadata.write_comments {
+                args.push("--write-comments".to_string());
+
+                let mut extractor_args = Vec::new();
+                if let Some(max) = metadata.max_comments {
+                    extractor_args.push(format!("max_comments={}", max));
+                }
+                match metadata.comment_sort.as_deref() {
+                    Some(sort @ ("top" | "new")) => extractor_args.push(format!("comment_sort={}", sort)),
+                    Some(other) => return Err(format!("Ordenação de comentários inválida: {}", other)),
+                    None => {}
+                }
+                if !extractor_args.is_empty() {
+                    args.push("--extractor-args".to_string());
+                    args.push(format!("youtube:{}", extractor_args.join(";")));
+                }
+            }
+        }
+
+        // Live recording options
+        if let Some(live) = &live {
+            if live.from_start {
+                args.push("--live-from-start".to_string());
+            }
+            if live.wait_for_start {
+                args.push("--wait-for-video".to_string());
+                args.push("30".to_string());
+            }
+            // MPEG-TS stays playable if the recording is stopped mid-stream
+            args.push("--hls-use-mpegts".to_string());
+            args.push("--progress-template".to_string());
+            args.push(
+                "download:[live] %(progress.downloaded_bytes)s %(progress.elapsed)s %(progress._speed_str)s"
+                    .to_string(),
+            );
+        }
+
+        // Everything after "--" is a URL, even if it starts with a dash
+        args.push("--".to_string());
+        args.push(request.url.clone());
+
+        let mut child = create_hidden_command(&self.exe_path)
+            .args(&args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Falha ao iniciar download: {}", e))?;
+
+        let stdout = child.stdout.take().ok_or("Falha ao capturar stdout")?;
+        let stderr = child.stderr.take().ok_or("Falha ao capturar stderr")?;
+
+        // Store child process for potential cancellation
+        let stopped = Arc::new(AtomicBool::new(false));
+        {
+            let mut downloads = self.active_downloads.lock().await;
+            downloads.insert(
+                download_id.clone(),
+                ActiveDownload {
+                    child,
+                    stopped: stopped.clone(),
+                },
+            );
+        }
+
+        let progress_regex =
+            Regex::new(r"\[download\]\s+(\d+\.?\d*)%\s+of.*?at\s+(\S+(?: B/s)?)\s+ETA\s+(\S+)").unwrap();
+        let dest_regex = Regex::new(r"\[download\] Destination: (.+)").unwrap();
+        let merge_regex = Regex::new(r"\[Merger\] Merging formats into").unwrap();
+        let extract_regex = Regex::new(r"\[ExtractAudio\]").unwrap();
+        let live_regex = Regex::new(r"^\[live\] (\d+|NA) ([\d.]+|NA)\s*(.*)$").unwrap();
+        let ffmpeg_stats_regex =
+            Regex::new(r"size=\s*(\d+)(?:kB|KiB)\s+time=(\d+):(\d+):(\d+(?:\.\d+)?)").unwrap();
+
+        let download_id_clone = download_id.clone();
+        let on_progress = Arc::new(on_progress);
+        let on_progress_clone = on_progress.clone();
+
+        // Set once the first live progress line arrives, so max_duration
+        // doesn't count the time spent waiting for a premiere
+        let recording_started: Arc<std::sync::Mutex<Option<Instant>>> =
+            Arc::new(std::sync::Mutex::new(None));
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let watchdog = live.as_ref().and_then(|live| {
+            if live.max_duration.is_none() && live.stop_at.is_none() {
+                return None;
+            }
+            let max_duration = live.max_duration.map(Duration::from_secs);
+            let stop_at = live.stop_at;
+            let recording_started = recording_started.clone();
+            let finished = finished.clone();
+            let active_downloads = self.active_downloads.clone();
+            let download_id = download_id.clone();
+
+            Some(tokio::spawn(async move {
+                while !finished.load(Ordering::SeqCst) {
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+
+                    let duration_reached = match (max_duration, *recording_started.lock().unwrap()) {
+                        (Some(max), Some(started)) => started.elapsed() >= max,
+                        _ => false,
+                    };
+                    let deadline_reached = stop_at.map(|ts| unix_now() >= ts).unwrap_or(false);
+
+                    if duration_reached || deadline_reached {
+                        let mut downloads = active_downloads.lock().await;
+                        if let Some(download) = downloads.get_mut(&download_id) {
+                            download.stop_gracefully();
+                        }
+                        break;
+                    }
+                }
+            }))
+        });
+
+        let is_live = live.is_some();
+        let live_started = recording_started.clone();
+
+        // Read stdout
+        let stdout_handle = tokio::spawn(async move {
+            let reader = BufReader::new(stdout);
+            let mut lines = reader.lines();
+            let mut current_filename: Option<String> = None;
+            let mut waiting_reported = false;
+            let mut outputs = OutputTracker::new();
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                outputs.observe(&line);
+                if let Some(caps) = progress_regex.captures(&line) {
+                    let progress: f64 = caps[1].parse().unwrap_or(0.0);
+                    let speed = caps.get(2).map(|m| m.as_str().to_string());
+                    let eta = caps.get(3).map(|m| m.as_str().to_string());
+
+                    on_progress_clone(DownloadProgress {
+                        download_id: download_id_clone.clone(),
+                        status: "downloading".to_string(),
+                        progress,
+                        speed,
+                        eta,
+                        filename: current_filename.clone(),
+                        elapsed: None,
+                        downloaded_bytes: None,
+                        files: Vec::new(),
+                    });
+                } else if let Some(caps) = live_regex.captures(&line) {
+                    live_started.lock().unwrap().get_or_insert_with(Instant::now);
+                    let speed = caps[3].trim();
+
+                    on_progress_clone(DownloadProgress {
+                        download_id: download_id_clone.clone(),
+                        status: "recording".to_string(),
+                        progress: 0.0,
+                        speed: (!speed.is_empty() && speed != "NA").then(|| speed.to_string()),
+                        eta: None,
+                        filename: current_filename.clone(),
+                        elapsed: caps[2].parse().ok(),
+                        downloaded_bytes: caps[1].parse().ok(),
+                        files: Vec::new(),
+                    });
+                } else if let Some(caps) = dest_regex.captures(&line) {
+                    current_filename = Some(caps[1].to_string());
+                } else if line.contains("[wait]") && !waiting_reported {
+                    waiting_reported = true;
+                    on_progress_clone(DownloadProgress {
+                        download_id: download_id_clone.clone(),
+                        status: "waiting".to_string(),
+                        progress: 0.0,
+                        speed: None,
+                        eta: None,
+                        filename: None,
+                        elapsed: None,
+                        downloaded_bytes: None,
+                        files: Vec::new(),
+                    });
+                } else if merge_regex.is_match(&line) || extract_regex.is_match(&line) {
+                    on_progress_clone(DownloadProgress {
+                        download_id: download_id_clone.clone(),
+                        status: "processing".to_string(),
+                        progress: 100.0,
+                        speed: None,
+                        eta: None,
+                        filename: current_filename.clone(),
+                        elapsed: None,
+                        downloaded_bytes: None,
+                        files: Vec::new(),
+                    });
+                }
+            }
+            // stdout closes when yt-dlp exits, so the files are final
+            outputs.existing()
+        });
+
+        let stderr_download_id = download_id.clone();
+        let stderr_on_progress = on_progress.clone();
+        let stderr_started = recording_started.clone();
+
+        // Read stderr for errors (and ffmpeg stats when recording a live stream)
+        let stderr_handle = tokio::spawn(async move {
+            let reader = BufReader::new(stderr);
+            let mut lines = reader.lines();
+            let mut error_output = String::new();
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                if line.contains("ERROR") {
+                    error_output.push_str(&line);
+                    error_output.push('\n');
+                } else if is_live {
+                    if let Some(caps) = ffmpeg_stats_regex.captures(&line) {
+                        stderr_started.lock().unwrap().get_or_insert_with(Instant::now);
+                        let kilobytes: u64 = caps[1].parse().unwrap_or(0);
+                        let hours: f64 = caps[2].parse().unwrap_or(0.0);
+                        let minutes: f64 = caps[3].parse().unwrap_or(0.0);
+                        let seconds: f64 = caps[4].parse().unwrap_or(0.0);
+
+                        stderr_on_progress(DownloadProgress {
+                            download_id: stderr_download_id.clone(),
+                            status: "recording".to_string(),
+                            progress: 0.0,
+                            speed: None,
+                            eta: None,
+                            filename: None,
+                            elapsed: Some(hours * 3600.0 + minutes * 60.0 + seconds),
+                            downloaded_bytes: Some(kilobytes * 1024),
+                            files: Vec::new(),
+                        });
+                    }
+                }
+            }
+            error_output
+        });
+
+        // Wait for completion
+        let outputs = stdout_handle.await.unwrap_or_default();
+        let error_output = stderr_handle.await.unwrap_or_default();
+
+        finished.store(true, Ordering::SeqCst);
+        if let Some(watchdog) = watchdog {
+            let _ = watchdog.await;
+        }
+
+        // Remove from active downloads
+        let status = {
+            let mut downloads = self.active_downloads.lock().await;
+            if let Some(mut download) = downloads.remove(&download_id) {
+                download.child.wait().await
+            } else {
+                return Err("Download cancelado".to_string());
+            }
+        };
+
+        // A recording stopped by its time limit or by the user is a successful
+        // recording, whatever exit code the interrupted process reports
+        let stopped = stopped.load(Ordering::SeqCst);
+
+        match status {
+            Ok(exit_status) if exit_status.success() || stopped => Ok(outputs),
+            Ok(_) => Err(format!("Download falhou: {}", error_output)),
+            Err(e) => Err(format!("Erro ao aguardar processo: {}", e)),
+        }
+    }
+
+    // Stop a live recording and keep what was recorded so far
+    pub async fn stop_recording(&self, download_id: &str) -> Result<(), String> {
+        let mut downloads = self.active_downloads.lock().await;
+        if let Some(download) = downloads.get_mut(download_id) {
+            download.stop_gracefully();
+            Ok(())
+        } else {
+            Err("Download não encontrado".to_string())
+        }
+    }
+
+    pub async fn cancel_download(&self, download_id: &str) -> Result<(), String> {
+        let mut downloads = self.active_downloads.lock().await;
+        if let Some(mut download) = downloads.remove(download_id) {
+            download
+                .child
+                .kill()
+                .await
+                .map_err(|e| format!("Falha ao cancelar: {}", e))?;
+            Ok(())
+        } else {
+            Err("Download não encontrado".to_string())
+        }
+    }
+}
+
+// Follows yt-dlp's output to find the files a download leaves behind.
+// Merging and audio extraction write new files and yt-dlp then deletes the
+// ones they were made from
+struct OutputTracker {
+    written: Vec<Regex>,
+    deleted: Regex,
+    moved: Regex,
+    paths: Vec<String>,
+}
+
+impl OutputTracker {
+    fn new() -> Self {
+        let written = [
+            r"^\[(?:download|ExtractAudio)\] Destination: (.+)$",
+            r"^\[download\] (.+) has already been downloaded$",
+            r#"^\[Merger\] Merging formats into "(.+)"$"#,
+            // VideoRemuxer and VideoConvertor
+            r"^\[Video\w+\] .+; Destination: (.+)$",
+        ];
+
+        Self {
+            written: written.iter().map(|r| Regex::new(r).unwrap()).collect(),
+            deleted: Regex::new(r"Deleting original file (.+) \(pass -k to keep\)$").unwrap(),
+            moved: Regex::new(r#"^\[MoveFiles\] Moving file "(.+)" to "(.+)"$"#).unwrap(),
+            paths: Vec::new(),
+        }
+    }
+
+    fn observe(&mut self, line: &str) {
+        let line = line.trim_end();
+        if let Some(caps) = self.written.iter().find_map(|r| r.captures(line)) {
+            let path = caps[1].to_string();
+            if !self.paths.contains(&path) {
+                self.paths.push(path);
+            }
+        } else if let Some(caps) = self.deleted.captures(line) {
+            self.paths.retain(|p| p != &caps[1]);
+        } else if let Some(caps) = self.moved.captures(line) {
+            self.paths.retain(|p| p != &caps[1]);
+            self.paths.push(caps[2].to_string());
+        }
+    }
+
+    // Subtitles that were embedded are gone by now, and so is anything the
+    // log mentioned that yt-dlp cleaned up without saying
+    fn existing(&self) -> Vec<String> {
+        self.paths
+            .iter()
+            .filter(|p| std::path::Path::new(p).is_file())
+            .cloned()
+            .collect()
+    }
+}
+
+fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+impl ActiveDownload {
+    // yt-dlp finalizes live recordings on SIGINT; Windows has no equivalent for
+    // console-less processes, so the process is killed and the MPEG-TS output kept
+    fn stop_gracefully(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+
+        #[cfg(unix)]
+        if let Some(pid) = self.child.id() {
+            // SAFETY: kill only sends a signal to the process we spawned
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGINT);
+            }
+            return;
+        }
+
+        let _ = self.child.start_kill();
+    }
+}
+
+impl Default for YtDlp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
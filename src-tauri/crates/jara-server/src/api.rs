@@ -0,0 +1,546 @@
+use crate::db::{self, Database, JobRecord, User};
+use crate::jobs::is_finished;
+use crate::storage::{self, Storage};
+use axum::extract::{Multipart, Path, Query, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use futures_util::stream;
+use jara_core::image_convert::ImageOptions;
+use jara_core::ytdlp::{DownloadProgress, DownloadRequest};
+use jara_core::Services;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::RecvError;
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+
+const DAY_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub services: Services,
+    pub storage: Storage,
+    pub db: Database,
+    // Held while checking quotas and enqueueing, so two requests can't both
+    // squeeze under a limit
+    pub enqueue_lock: Arc<Mutex<()>>,
+}
+
+#[derive(Deserialize)]
+struct UrlBody {
+    #[serde(default)]
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct DownloadBody {
+    #[serde(default)]
+    url: String,
+    format_id: Option<String>,
+    #[serde(default)]
+    audio_only: bool,
+    #[serde(default)]
+    download_subs: bool,
+    sub_lang: Option<String>,
+    #[serde(default)]
+    sections: Vec<String>,
+}
+
+// Same routes as the old Node server, plus SSE progress. Every route needs
+// a user token
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/api/me", get(me))
+        .route("/api/video-info", post(video_info))
+        .route("/api/playlist-info", post(playlist_info))
+        .route("/api/download", post(start_download))
+        .route("/api/download/{id}", axum::routing::delete(cancel_download))
+        .route("/api/download/{id}/progress", get(download_progress))
+        .route("/api/download/{id}/events", get(download_events))
+        .route("/api/download/{id}/file", get(download_file))
+        .route("/api/files", get(list_files))
+        .route("/api/convert/image", post(convert_image))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_user))
+        .with_state(state)
+}
+
+// Errors go out as {"error": "..."}, like the old server's
+struct ApiError(StatusCode, String);
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self(status, message.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+type ApiResult = Result<Response, ApiError>;
+
+// yt-dlp takes the URL as its last argument, so anything that could be read
+// as an option is refused
+fn check_url(url: &str) -> Result<(), ApiError> {
+    if url.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "URL é obrigatória"));
+    }
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "A URL deve ser http ou https"));
+    }
+    Ok(())
+}
+
+// yt-dlp failures are server errors, as in the old server
+fn internal(message: String) -> ApiError {
+    ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, message)
+}
+
+// Bearer token, or ?token= for EventSource and download links, which can't
+// send headers
+async fn require_user(
+    State(state): State<AppState>,
+    Query(query): Query<HashMap<String, String>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(String::from);
+    let given = bearer.or_else(|| query.get("token").cloned()).unwrap_or_default();
+    if given.is_empty() {
+        return ApiError::new(StatusCode::UNAUTHORIZED, "Token obrigatório").into_response();
+    }
+
+    match state.db.find_user_by_token(&given) {
+        Ok(Some(user)) => {
+            request.extensions_mut().insert(user);
+            next.run(request).await
+        }
+        Ok(None) => ApiError::new(StatusCode::UNAUTHORIZED, "Token inválido").into_response(),
+        Err(e) => internal(e).into_response(),
+    }
+}
+
+// The user's limits and how much of them is used
+async fn me(State(state): State<AppState>, Extension(user): Extension<User>) -> ApiResult {
+    let active = state.db.active_job_count(user.id).map_err(internal)?;
+    let bytes_today = state
+        .db
+        .bytes_since(user.id, db::unix_now() - DAY_SECS)
+        .map_err(internal)?;
+
+    Ok(Json(json!({
+        "name": user.name,
+        "limits": user.limits,
+        "active_jobs": active,
+        "bytes_today": bytes_today,
+    }))
+    .into_response())
+}
+
+async fn video_info(State(state): State<AppState>, Json(body): Json<UrlBody>) -> ApiResult {
+    check_url(&body.url)?;
+
+    let ytdlp = state.services.ytdlp().await.map_err(internal)?;
+    let info = ytdlp.get_video_info(&body.url).await.map_err(internal)?;
+    Ok(Json(info).into_response())
+}
+
+async fn playlist_info(State(state): State<AppState>, Json(body): Json<UrlBody>) -> ApiResult {
+    check_url(&body.url)?;
+
+    let ytdlp = state.services.ytdlp().await.map_err(internal)?;
+    let info = ytdlp.get_playlist_info(&body.url).await.map_err(internal)?;
+    Ok(Json(info).into_response())
+}
+
+async fn start_download(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Json(body): Json<DownloadBody>,
+) -> ApiResult {
+    check_url(&body.url)?;
+
+    if let Some(max_secs) = user.limits.max_duration_secs {
+        let ytdlp = state.services.ytdlp().await.map_err(internal)?;
+        let info = ytdlp.get_video_info(&body.url).await.map_err(internal)?;
+        if info.is_live {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "Transmissões ao vivo não são permitidas para este usuário",
+            ));
+        }
+        let Some(duration) = info.duration else {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "Não foi possível saber a duração do vídeo",
+            ));
+        };
+        if duration > max_secs as f64 {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                format!("Vídeo mais longo que o permitido ({} min)", max_secs / 60),
+            ));
+        }
+    }
+
+    let id = reserve_job(&state, &user, body)?;
+    Ok(Json(json!({ "downloadId": id })).into_response())
+}
+
+// Checks the quotas, then enqueues the download and records it for the user
+fn reserve_job(state: &AppState, user: &User, body: DownloadBody) -> Result<String, ApiError> {
+    let _guard = state.enqueue_lock.lock().unwrap();
+
+    if let Some(max) = user.limits.max_concurrent {
+        if state.db.active_job_count(user.id).map_err(internal)? >= max {
+            return Err(ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "Limite de downloads simultâneos atingido",
+            ));
+        }
+    }
+    // What is left of the daily quota caps the size of this download, and is
+    // reserved for it until it finishes so concurrent downloads can't each
+    // take all of it
+    let mut max_filesize = None;
+    if let Some(max) = user.limits.max_bytes_per_day {
+        let used = state
+            .db
+            .bytes_since(user.id, db::unix_now() - DAY_SECS)
+            .map_err(internal)?;
+        if used >= max {
+            return Err(ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "Limite diário de download atingido",
+            ));
+        }
+        max_filesize = Some(max - used);
+    }
+
+    let in_use: HashSet<PathBuf> = state
+        .db
+        .unfinished_jobs()
+        .map_err(internal)?
+        .into_iter()
+        .map(|job| job.folder)
+        .collect();
+    state
+        .storage
+        .make_room(&in_use, |folder| {
+            let _ = state.db.mark_folder_deleted(folder);
+        })
+        .map_err(|e| ApiError::new(StatusCode::INSUFFICIENT_STORAGE, e))?;
+
+    let folder = state.storage.create_folder().map_err(internal)?;
+    let url = body.url.clone();
+
+    let id = state.services.downloads.enqueue(DownloadRequest {
+        url: body.url,
+        format_id: body.format_id,
+        output_path: folder.to_string_lossy().to_string(),
+        audio_only: body.audio_only,
+        download_subs: body.download_subs,
+        sub_lang: body.sub_lang,
+        live: None,
+        metadata: None,
+        sections: body.sections,
+        download_archive: None,
+        post_actions: Vec::new(),
+        // The duration check only looked at the video itself
+        no_playlist: user.limits.max_duration_secs.is_some(),
+        max_filesize,
+    });
+
+    let record = JobRecord {
+        id: id.clone(),
+        user_id: user.id,
+        url,
+        folder,
+        status: "queued".to_string(),
+        error: None,
+        bytes: max_filesize.unwrap_or(0),
+        created_at: db::unix_now(),
+        finished_at: None,
+        deleted_at: None,
+    };
+    if let Err(e) = state.db.insert_job(&record) {
+        // A job nobody owns could never be seen or cancelled
+        let downloads = state.services.downloads.clone();
+        let job_id = id.clone();
+        tokio::spawn(async move {
+            let _ = downloads.cancel(&job_id).await;
+        });
+        return Err(internal(e));
+    }
+
+    Ok(id)
+}
+
+// Other users' jobs look the same as missing ones
+fn owned_job(state: &AppState, user: &User, id: &str) -> Result<JobRecord, ApiError> {
+    state
+        .db
+        .get_job(id)
+        .map_err(internal)?
+        .filter(|job| job.user_id == user.id)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "Download não encontrado"))
+}
+
+// Live state from the download manager, or the stored one for jobs from
+// before a restart
+fn job_progress(state: &AppState, record: &JobRecord) -> DownloadProgress {
+    let (status, progress) = match state.services.downloads.get_job(&record.id) {
+        Some(job) => (job.status, job.progress),
+        None => {
+            let progress = if record.status == "completed" { 100.0 } else { 0.0 };
+            (record.status.clone(), progress)
+        }
+    };
+
+    DownloadProgress {
+        download_id: record.id.clone(),
+        filename: finished_file(record, &status).map(|file| storage::file_name(&file)),
+        status,
+        progress,
+        speed: None,
+        eta: None,
+        elapsed: None,
+        downloaded_bytes: None,
+        files: Vec::new(),
+    }
+}
+
+fn job_error(state: &AppState, record: &JobRecord) -> Option<String> {
+    match state.services.downloads.get_job(&record.id) {
+        Some(job) => job.error,
+        None => record.error.clone(),
+    }
+}
+
+fn finished_file(record: &JobRecord, status: &str) -> Option<PathBuf> {
+    if status != "completed" || record.deleted_at.is_some() {
+        return None;
+    }
+    storage::job_file(&record.folder)
+}
+
+async fn download_progress(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+) -> ApiResult {
+    let record = owned_job(&state, &user, &id)?;
+    let progress = job_progress(&state, &record);
+
+    Ok(Json(json!({
+        "progress": progress.progress,
+        "status": progress.status,
+        "filename": progress.filename,
+        "error": job_error(&state, &record),
+    }))
+    .into_response())
+}
+
+// Streams the job's DownloadProgress updates, starting with its current
+// state, and ends after the final one
+async fn download_events(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+) -> ApiResult {
+    // Subscribe first so nothing is missed between the snapshot and the stream
+    let events = state.services.downloads.subscribe();
+    let record = owned_job(&state, &user, &id)?;
+    let snapshot = job_progress(&state, &record);
+
+    let updates = stream::unfold(
+        (Some(snapshot), events, false),
+        move |(pending, mut events, done)| {
+            let id = id.clone();
+            async move {
+                if done {
+                    return None;
+                }
+                if let Some(progress) = pending {
+                    let done = is_finished(&progress.status);
+                    return Some((Event::default().json_data(&progress), (None, events, done)));
+                }
+                loop {
+                    match events.recv().await {
+                        Ok(progress) if progress.download_id == id => {
+                            let done = is_finished(&progress.status);
+                            let progress = public_progress(progress);
+                            return Some((Event::default().json_data(&progress), (None, events, done)));
+                        }
+                        Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(updates).keep_alive(KeepAlive::default()).into_response())
+}
+
+// Clients see file names, not where the server keeps the files
+fn public_progress(mut progress: DownloadProgress) -> DownloadProgress {
+    let name = |path: &str| storage::file_name(std::path::Path::new(path));
+    progress.filename = progress.filename.as_deref().map(name);
+    for file in &mut progress.files {
+        file.path = name(&file.path);
+    }
+    progress
+}
+
+async fn cancel_download(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+) -> ApiResult {
+    owned_job(&state, &user, &id)?;
+    state
+        .services
+        .downloads
+        .cancel(&id)
+        .await
+        .map_err(|e| ApiError::new(StatusCode::CONFLICT, e))?;
+    Ok(Json(json!({ "success": true })).into_response())
+}
+
+async fn download_file(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(id): Path<String>,
+    request: Request,
+) -> ApiResult {
+    let record = owned_job(&state, &user, &id)?;
+    let status = job_progress(&state, &record).status;
+    let file = finished_file(&record, &status)
+        .ok_or_else(|| ApiError::new(StatusCode::NOT_FOUND, "Arquivo não encontrado"))?;
+
+    let name = storage::file_name(&file);
+    let mut response = match ServeFile::new(&file).oneshot(request).await {
+        Ok(response) => response.into_response(),
+        Err(e) => return Err(internal(e.to_string())),
+    };
+    if let Ok(value) = HeaderValue::from_str(&content_disposition(&name)) {
+        response.headers_mut().insert(header::CONTENT_DISPOSITION, value);
+    }
+    Ok(response)
+}
+
+async fn list_files(State(state): State<AppState>, Extension(user): Extension<User>) -> ApiResult {
+    let folders: Vec<PathBuf> = state
+        .db
+        .user_jobs(user.id)
+        .map_err(internal)?
+        .into_iter()
+        .filter(|job| job.status == "completed" && job.deleted_at.is_none())
+        .map(|job| job.folder)
+        .collect();
+    Ok(Json(state.storage.list_files(&folders)).into_response())
+}
+
+// multipart/form-data with "file" and "format", plus optional "max_size"
+// and "square". Responds with the converted image
+async fn convert_image(State(state): State<AppState>, mut multipart: Multipart) -> ApiResult {
+    let mut upload: Option<(String, Vec<u8>)> = None;
+    let mut format = String::new();
+    let mut options = ImageOptions::default();
+
+    let bad_upload = |e: axum::extract::multipart::MultipartError| {
+        ApiError::new(StatusCode::BAD_REQUEST, e.body_text())
+    };
+
+    while let Some(field) = multipart.next_field().await.map_err(bad_upload)? {
+        let name = field.name().unwrap_or("").to_string();
+        let file_name = field.file_name().unwrap_or("imagem").to_string();
+        let data = field.bytes().await.map_err(bad_upload)?;
+        let text = String::from_utf8_lossy(&data).trim().to_string();
+
+        match name.as_str() {
+            "file" => upload = Some((file_name, data.to_vec())),
+            "format" => format = text.to_lowercase(),
+            "max_size" => options.max_size = text.parse().ok(),
+            "square" => options.square = matches!(text.as_str(), "true" | "1" | "on"),
+            _ => {}
+        }
+    }
+
+    let (file_name, data) =
+        upload.ok_or_else(|| ApiError::new(StatusCode::BAD_REQUEST, "Arquivo é obrigatório"))?;
+    if format.is_empty() || !format.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "Formato inválido"));
+    }
+
+    let folder = state.storage.create_folder().map_err(internal)?;
+
+    // Only the extension of the uploaded name is kept
+    let extension: String = std::path::Path::new(&file_name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    let input = folder.join(format!("imagem.{}", extension));
+    let stem = std::path::Path::new(&file_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "imagem".to_string());
+
+    let images = state.services.images.clone();
+    let output_format = format.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        std::fs::write(&input, &data).map_err(|e| format!("Falha ao salvar arquivo: {}", e))?;
+        let output = images.convert_with_options(&input.to_string_lossy(), &output_format, &options)?;
+        std::fs::read(&output).map_err(|e| format!("Falha ao ler arquivo convertido: {}", e))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+
+    let _ = std::fs::remove_dir_all(&folder);
+    let bytes = result.map_err(|e| ApiError::new(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+
+    let name = format!("{}.{}", stem, format);
+    let content_type = format!("image/{}", if format == "jpg" { "jpeg" } else { &format });
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, content_disposition(&name)),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+// Titles are often not ASCII, so the name also goes percent-encoded (RFC 6266)
+fn content_disposition(name: &str) -> String {
+    let fallback: String = name
+        .chars()
+        .map(|c| if c.is_ascii_graphic() && c != '"' && c != '\\' || c == ' ' { c } else { '_' })
+        .collect();
+    let encoded: String = name
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    format!("attachment; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded)
+}
@@ -0,0 +1,392 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Statuses after which a job no longer runs
+pub const FINISHED_STATUSES: &[&str] = &["completed", "error", "cancelled"];
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    token_hash TEXT NOT NULL UNIQUE,
+    max_concurrent INTEGER,
+    max_bytes_per_day INTEGER,
+    max_duration_secs INTEGER,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS jobs (
+    id TEXT PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    url TEXT NOT NULL,
+    folder TEXT NOT NULL,
+    status TEXT NOT NULL,
+    error TEXT,
+    bytes INTEGER NOT NULL DEFAULT 0,
+    created_at INTEGER NOT NULL,
+    finished_at INTEGER,
+    deleted_at INTEGER
+);
+CREATE INDEX IF NOT EXISTS jobs_user ON jobs(user_id, created_at);
+";
+
+// Per-user quotas; None means no limit
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Limits {
+    pub max_concurrent: Option<u32>,
+    pub max_bytes_per_day: Option<u64>,
+    pub max_duration_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct User {
+    pub id: i64,
+    pub name: String,
+    #[serde(flatten)]
+    pub limits: Limits,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobRecord {
+    pub id: String,
+    pub user_id: i64,
+    pub url: String,
+    pub folder: PathBuf,
+    pub status: String,
+    pub error: Option<String>,
+    // Size of the folder once finished; until then, the share of the daily
+    // quota reserved for the job
+    pub bytes: u64,
+    pub created_at: i64,
+    pub finished_at: Option<i64>,
+    // Set once retention or the storage limit removed the files
+    pub deleted_at: Option<i64>,
+}
+
+// Users, their jobs and what they downloaded, in a local SQLite file.
+// Cloning is cheap and clones share the connection
+#[derive(Clone)]
+pub struct Database {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Database {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| format!("Falha ao abrir banco de dados: {}", e))?;
+        // The user commands may write while the server runs
+        conn.busy_timeout(std::time::Duration::from_secs(5)).map_err(db_error)?;
+        conn.pragma_update(None, "journal_mode", "WAL").map_err(db_error)?;
+        conn.pragma_update(None, "foreign_keys", "ON").map_err(db_error)?;
+        conn.execute_batch(SCHEMA).map_err(db_error)?;
+
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    // Returns the new user's token, which is only stored hashed
+    pub fn add_user(&self, name: &str, limits: &Limits) -> Result<String, String> {
+        let token = generate_token()?;
+        let conn = self.conn.lock().unwrap();
+        let exists: bool = conn
+            .query_row("SELECT 1 FROM users WHERE name = ?1", [name], |_| Ok(true))
+            .optional()
+            .map_err(db_error)?
+            .unwrap_or(false);
+        if exists {
+            return Err(format!("Usuário já existe: {}", name));
+        }
+
+        conn.execute(
+            "INSERT INTO users (name, token_hash, max_concurrent, max_bytes_per_day, max_duration_secs, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                name,
+                hash_token(&token),
+                limits.max_concurrent,
+                limits.max_bytes_per_day,
+                limits.max_duration_secs,
+                unix_now()
+            ],
+        )
+        .map_err(db_error)?;
+        Ok(token)
+    }
+
+    pub fn remove_user(&self, name: &str) -> Result<(), String> {
+        let removed = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM users WHERE name = ?1", [name])
+            .map_err(db_error)?;
+        user_found(removed, name)
+    }
+
+    // Replaces the user's token; the old one stops working
+    pub fn reset_token(&self, name: &str) -> Result<String, String> {
+        let token = generate_token()?;
+        let updated = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE users SET token_hash = ?1 WHERE name = ?2",
+                params![hash_token(&token), name],
+            )
+            .map_err(db_error)?;
+        user_found(updated, name)?;
+        Ok(token)
+    }
+
+    pub fn set_limits(&self, name: &str, limits: &Limits) -> Result<(), String> {
+        let updated = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE users SET max_concurrent = ?1, max_bytes_per_day = ?2, max_duration_secs = ?3
+                 WHERE name = ?4",
+                params![
+                    limits.max_concurrent,
+                    limits.max_bytes_per_day,
+                    limits.max_duration_secs,
+                    name
+                ],
+            )
+            .map_err(db_error)?;
+        user_found(updated, name)
+    }
+
+    pub fn list_users(&self) -> Result<Vec<User>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn
+            .prepare(&format!("SELECT {} FROM users ORDER BY name", USER_COLUMNS))
+            .map_err(db_error)?;
+        let users = statement
+            .query_map([], user_from_row)
+            .map_err(db_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error)?;
+        Ok(users)
+    }
+
+    pub fn find_user_by_token(&self, token: &str) -> Result<Option<User>, String> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!("SELECT {} FROM users WHERE token_hash = ?1", USER_COLUMNS),
+                [hash_token(token)],
+                user_from_row,
+            )
+            .optional()
+            .map_err(db_error)
+    }
+
+    pub fn insert_job(&self, job: &JobRecord) -> Result<(), String> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO jobs (id, user_id, url, folder, status, error, bytes, created_at, finished_at, deleted_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    job.id,
+                    job.user_id,
+                    job.url,
+                    job.folder.to_string_lossy(),
+                    job.status,
+                    job.error,
+                    job.bytes,
+                    job.created_at,
+                    job.finished_at,
+                    job.deleted_at
+                ],
+            )
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    pub fn get_job(&self, id: &str) -> Result<Option<JobRecord>, String> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                &format!("SELECT {} FROM jobs WHERE id = ?1", JOB_COLUMNS),
+                [id],
+                job_from_row,
+            )
+            .optional()
+            .map_err(db_error)
+    }
+
+    pub fn user_jobs(&self, user_id: i64) -> Result<Vec<JobRecord>, String> {
+        self.query_jobs(
+            &format!("SELECT {} FROM jobs WHERE user_id = ?1 ORDER BY created_at", JOB_COLUMNS),
+            [user_id],
+        )
+    }
+
+    // Jobs that were queued or running, of every user
+    pub fn unfinished_jobs(&self) -> Result<Vec<JobRecord>, String> {
+        self.query_jobs(
+            &format!(
+                "SELECT {} FROM jobs WHERE status NOT IN ('completed', 'error', 'cancelled')",
+                JOB_COLUMNS
+            ),
+            [],
+        )
+    }
+
+    // Finished jobs whose files are still on disk, finished before `before`
+    pub fn expired_jobs(&self, before: i64) -> Result<Vec<JobRecord>, String> {
+        self.query_jobs(
+            &format!(
+                "SELECT {} FROM jobs WHERE deleted_at IS NULL AND finished_at < ?1",
+                JOB_COLUMNS
+            ),
+            [before],
+        )
+    }
+
+    pub fn finish_job(&self, id: &str, status: &str, error: Option<&str>, bytes: u64) -> Result<(), String> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE jobs SET status = ?1, error = ?2, bytes = ?3, finished_at = ?4 WHERE id = ?5",
+                params![status, error, bytes, unix_now(), id],
+            )
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    pub fn mark_deleted(&self, id: &str) -> Result<(), String> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE jobs SET deleted_at = ?1 WHERE id = ?2", params![unix_now(), id])
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    // For the storage limit, which removes folders rather than jobs
+    pub fn mark_folder_deleted(&self, folder: &Path) -> Result<(), String> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE jobs SET deleted_at = ?1 WHERE folder = ?2 AND deleted_at IS NULL",
+                params![unix_now(), folder.to_string_lossy()],
+            )
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    pub fn active_job_count(&self, user_id: i64) -> Result<u32, String> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM jobs WHERE user_id = ?1
+                 AND status NOT IN ('completed', 'error', 'cancelled')",
+                [user_id],
+                |row| row.get(0),
+            )
+            .map_err(db_error)
+    }
+
+    // Bytes the user downloaded, or reserved for jobs still running, in jobs
+    // started since `since`
+    pub fn bytes_since(&self, user_id: i64, since: i64) -> Result<u64, String> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COALESCE(SUM(bytes), 0) FROM jobs WHERE user_id = ?1 AND created_at >= ?2",
+                params![user_id, since],
+                |row| row.get(0),
+            )
+            .map_err(db_error)
+    }
+
+    fn query_jobs<P: rusqlite::Params>(&self, sql: &str, params: P) -> Result<Vec<JobRecord>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut statement = conn.prepare(sql).map_err(db_error)?;
+        let jobs = statement
+            .query_map(params, job_from_row)
+            .map_err(db_error)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(db_error)?;
+        Ok(jobs)
+    }
+}
+
+const USER_COLUMNS: &str = "id, name, max_concurrent, max_bytes_per_day, max_duration_secs, created_at";
+
+fn user_from_row(row: &Row) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        limits: Limits {
+            max_concurrent: row.get(2)?,
+            max_bytes_per_day: row.get(3)?,
+            max_duration_secs: row.get(4)?,
+        },
+        created_at: row.get(5)?,
+    })
+}
+
+const JOB_COLUMNS: &str = "id, user_id, url, folder, status, error, bytes, created_at, finished_at, deleted_at";
+
+fn job_from_row(row: &Row) -> rusqlite::Result<JobRecord> {
+    Ok(JobRecord {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        url: row.get(2)?,
+        folder: PathBuf::from(row.get::<_, String>(3)?),
+        status: row.get(4)?,
+        error: row.get(5)?,
+        bytes: row.get(6)?,
+        created_at: row.get(7)?,
+        finished_at: row.get(8)?,
+        deleted_at: row.get(9)?,
+    })
+}
+
+fn user_found(changed: usize, name: &str) -> Result<(), String> {
+    if changed == 0 {
+        Err(format!("Usuário não encontrado: {}", name))
+    } else {
+        Ok(())
+    }
+}
+
+fn db_error(e: rusqlite::Error) -> String {
+    format!("Erro no banco de dados: {}", e)
+}
+
+fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::fill(&mut bytes).map_err(|e| format!("Falha ao gerar token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+pub fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
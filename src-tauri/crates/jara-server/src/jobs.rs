@@ -0,0 +1,85 @@
+use crate::db::{self, Database, FINISHED_STATUSES};
+use crate::storage;
+use jara_core::Services;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn is_finished(status: &str) -> bool {
+    FINISHED_STATUSES.contains(&status)
+}
+
+// Stores each job's final status and size as the download manager reports it
+pub fn spawn_recorder(services: Services, db: Database) {
+    let mut events = services.downloads.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(progress) if is_finished(&progress.status) => {
+                    record(&services, &db, &progress.download_id);
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => reconcile(&services, &db),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+// Every minute, catches up on missed updates and deletes the files of jobs
+// that finished more than `retention` ago
+pub fn spawn_sweeper(services: Services, db: Database, retention: Option<Duration>) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(SWEEP_INTERVAL);
+        loop {
+            tick.tick().await;
+            reconcile(&services, &db);
+            if let Some(retention) = retention {
+                sweep(&db, retention);
+            }
+        }
+    });
+}
+
+fn record(services: &Services, db: &Database, id: &str) {
+    let (Some(job), Ok(Some(record))) = (services.downloads.get_job(id), db.get_job(id)) else {
+        return;
+    };
+    if is_finished(&job.status) {
+        let bytes = storage::dir_size(&record.folder);
+        let _ = db.finish_job(id, &job.status, job.error.as_deref(), bytes);
+    }
+}
+
+// Brings unfinished jobs in the database up to date. Jobs the download
+// manager doesn't know were lost when the server stopped
+pub fn reconcile(services: &Services, db: &Database) {
+    let Ok(jobs) = db.unfinished_jobs() else {
+        return;
+    };
+    for job in jobs {
+        if services.downloads.get_job(&job.id).is_some() {
+            record(services, db, &job.id);
+        } else {
+            let bytes = storage::dir_size(&job.folder);
+            let _ = db.finish_job(&job.id, "error", Some("Interrompido: o servidor foi reiniciado"), bytes);
+        }
+    }
+}
+
+fn sweep(db: &Database, retention: Duration) {
+    let before = db::unix_now() - retention.as_secs() as i64;
+    let Ok(jobs) = db.expired_jobs(before) else {
+        return;
+    };
+    for job in jobs {
+        let removed = match std::fs::remove_dir_all(&job.folder) {
+            Ok(()) => true,
+            Err(e) => e.kind() == std::io::ErrorKind::NotFound,
+        };
+        if removed {
+            let _ = db.mark_deleted(&job.id);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use axum::extract::DefaultBodyLimit;
+use clap::{Args, Parser, Subcommand};
+use jara_server::api::{self, AppState};
+use jara_server::db::{Database, Limits, User};
+use jara_server::jobs;
+use jara_server::storage::Storage;
+use jara_core::settings::Settings;
+use jara_core::ffmpeg::FFmpeg;
+use jara_core::{BinaryPaths, Services};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tower_http::cors::CorsLayer;
+use tower_http::services::{ServeDir, ServeFile};
+
+#[derive(Parser)]
+#[command(name = "jara-server", version, about = "Servidor web do Jara")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[arg(long, env = "JARA_DB", default_value = "jara.db", global = true, help = "Banco de dados de usuários e downloads")]
+    db: PathBuf,
+
+    #[command(flatten)]
+    config: Config,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    // Without a subcommand the server runs
+    #[command(subcommand, about = "Gerencia os usuários")]
+    User(UserCommand),
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    #[command(about = "Cria um usuário e mostra o token dele")]
+    Add {
+        name: String,
+        #[command(flatten)]
+        limits: LimitArgs,
+    },
+    #[command(about = "Lista os usuários e seus limites")]
+    List,
+    #[command(about = "Remove um usuário e o histórico dele")]
+    Remove { name: String },
+    #[command(about = "Gera um novo token; o anterior deixa de funcionar")]
+    Token { name: String },
+    #[command(about = "Substitui os limites do usuário; os omitidos ficam sem limite")]
+    Limits {
+        name: String,
+        #[command(flatten)]
+        limits: LimitArgs,
+    },
+}
+
+#[derive(Args)]
+struct LimitArgs {
+    #[arg(long, value_name = "N", help = "Downloads simultâneos")]
+    max_concurrent: Option<u32>,
+
+    #[arg(long, value_name = "MB", help = "MB baixados a cada 24 horas")]
+    max_mb_per_day: Option<u64>,
+
+    #[arg(long, value_name = "MIN", help = "Duração máxima dos vídeos, em minutos")]
+    max_minutes: Option<u64>,
+}
+
+impl LimitArgs {
+    fn to_limits(&self) -> Limits {
+        Limits {
+            max_concurrent: self.max_concurrent,
+            max_bytes_per_day: self.max_mb_per_day.map(|mb| mb * 1024 * 1024),
+            max_duration_secs: self.max_minutes.map(|min| min * 60),
+        }
+    }
+}
+
+#[derive(Args)]
+struct Config {
+    #[arg(long, env = "PORT", default_value_t = 3001)]
+    port: u16,
+
+    #[arg(long, env = "JARA_HOST", default_value = "0.0.0.0")]
+    host: String,
+
+    #[arg(long, env = "JARA_STORAGE", default_value = "downloads", help = "Pasta dos downloads")]
+    storage: PathBuf,
+
+    #[arg(long, env = "JARA_DIST", default_value = "dist", help = "Build do frontend (npm run build)")]
+    dist: PathBuf,
+
+    #[arg(
+        long,
+        env = "JARA_MAX_STORAGE_MB",
+        help = "Espaço máximo dos downloads; os mais antigos são apagados para abrir espaço"
+    )]
+    max_storage_mb: Option<u64>,
+
+    #[arg(long, env = "JARA_MAX_UPLOAD_MB", default_value_t = 50, help = "Tamanho máximo de upload")]
+    max_upload_mb: u64,
+
+    #[arg(
+        long,
+        env = "JARA_RETENTION_HOURS",
+        help = "Apaga os arquivos dos downloads terminados há mais tempo que isso"
+    )]
+    retention_hours: Option<u64>,
+
+    #[arg(long, env = "JARA_YT_DLP", value_name = "PATH")]
+    yt_dlp: Option<PathBuf>,
+
+    #[arg(long, env = "JARA_FFMPEG", value_name = "PATH")]
+    ffmpeg: Option<PathBuf>,
+
+    // Next to ffmpeg unless set
+    #[arg(long, env = "JARA_FFPROBE", value_name = "PATH")]
+    ffprobe: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match Database::open(&cli.db) {
+        Ok(db) => match cli.command {
+            Some(Command::User(command)) => manage_users(&db, command),
+            None => serve(cli.config, db).await,
+        },
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Erro: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn manage_users(db: &Database, command: UserCommand) -> Result<(), String> {
+    match command {
+        UserCommand::Add { name, limits } => {
+            let token = db.add_user(&name, &limits.to_limits())?;
+            println!("Usuário {} criado. Token (guarde, ele não é mostrado de novo):", name);
+            println!("{}", token);
+        }
+        UserCommand::List => {
+            let users = db.list_users()?;
+            if users.is_empty() {
+                println!("Nenhum usuário cadastrado");
+            }
+            for user in users {
+                println!("{}", describe_user(&user));
+            }
+        }
+        UserCommand::Remove { name } => {
+            db.remove_user(&name)?;
+            println!("Usuário {} removido", name);
+        }
+        UserCommand::Token { name } => {
+            let token = db.reset_token(&name)?;
+            println!("Novo token de {}:", name);
+            println!("{}", token);
+        }
+        UserCommand::Limits { name, limits } => {
+            db.set_limits(&name, &limits.to_limits())?;
+            println!("Limites de {} atualizados", name);
+        }
+    }
+    Ok(())
+}
+
+fn describe_user(user: &User) -> String {
+    let limit = |value: Option<String>| value.unwrap_or_else(|| "sem limite".to_string());
+    format!(
+        "{}: simultâneos {}, por dia {}, duração {}",
+        user.name,
+        limit(user.limits.max_concurrent.map(|n| n.to_string())),
+        limit(user.limits.max_bytes_per_day.map(|b| format!("{} MB", b / 1024 / 1024))),
+        limit(user.limits.max_duration_secs.map(|s| format!("{} min", s / 60))),
+    )
+}
+
+async fn serve(config: Config, db: Database) -> Result<(), String> {
+    let storage = Storage::new(config.storage, config.max_storage_mb.map(|mb| mb * 1024 * 1024))?;
+
+    let detected = BinaryPaths::detect();
+    let ffprobe = config
+        .ffprobe
+        .or_else(|| config.ffmpeg.as_deref().map(FFmpeg::ffprobe_beside))
+        .unwrap_or(detected.ffprobe);
+    let paths = BinaryPaths {
+        ytdlp: config.yt_dlp.unwrap_or(detected.ytdlp),
+        ffmpeg: config.ffmpeg.unwrap_or(detected.ffmpeg),
+        ffprobe,
+    };
+    // Progress reaches clients through the download manager's broadcast
+    let services = Services::new(paths, Settings::default(), |_| {});
+
+    // Jobs still running when the server last stopped are marked as failed
+    jobs::reconcile(&services, &db);
+    jobs::spawn_recorder(services.clone(), db.clone());
+    jobs::spawn_sweeper(
+        services.clone(),
+        db.clone(),
+        config.retention_hours.map(|hours| Duration::from_secs(hours * 60 * 60)),
+    );
+
+    if db.list_users()?.is_empty() {
+        println!("Nenhum usuário cadastrado. Crie um com: jara-server user add <nome>");
+    }
+
+    let state = AppState {
+        services,
+        storage,
+        db,
+        enqueue_lock: Arc::new(Mutex::new(())),
+    };
+
+    // Unknown paths get index.html so the SPA can route them
+    let frontend = ServeDir::new(&config.dist).fallback(ServeFile::new(config.dist.join("index.html")));
+
+    let app = api::router(state)
+        .fallback_service(frontend)
+        .layer(DefaultBodyLimit::max(config.max_upload_mb as usize * 1024 * 1024))
+        .layer(CorsLayer::permissive());
+
+    let listener = tokio::net::TcpListener::bind((config.host.as_str(), config.port))
+        .await
+        .map_err(|e| format!("Falha ao abrir a porta {}: {}", config.port, e))?;
+    println!("Jara server rodando em http://{}:{}", config.host, config.port);
+
+    axum::serve(listener, app).await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,155 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Partial and temporary files yt-dlp leaves while it works
+const TEMP_EXTENSIONS: &[&str] = &["part", "ytdl", "temp", "tmp"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredFile {
+    pub name: String,
+    // Relative to the storage root: "<folder>/<name>"
+    pub path: String,
+    pub size: u64,
+}
+
+// Each download gets its own folder under the root, so its files can be
+// served and removed without touching other jobs
+#[derive(Debug, Clone)]
+pub struct Storage {
+    root: PathBuf,
+    max_bytes: Option<u64>,
+}
+
+impl Storage {
+    pub fn new(root: PathBuf, max_bytes: Option<u64>) -> Result<Self, String> {
+        std::fs::create_dir_all(&root).map_err(|e| format!("Falha ao criar diretório: {}", e))?;
+        let root = root
+            .canonicalize()
+            .map_err(|e| format!("Diretório de downloads inválido: {}", e))?;
+        Ok(Self { root, max_bytes })
+    }
+
+    pub fn create_folder(&self) -> Result<PathBuf, String> {
+        let folder = self.root.join(new_folder_name());
+        std::fs::create_dir_all(&folder).map_err(|e| format!("Falha ao criar diretório: {}", e))?;
+        Ok(folder)
+    }
+
+    // Deletes the oldest folders not in `in_use` until the storage is under
+    // its limit, passing each one to `removed`. Fails when only folders in use
+    // are left
+    pub fn make_room(&self, in_use: &HashSet<PathBuf>, mut removed: impl FnMut(&Path)) -> Result<(), String> {
+        let Some(max_bytes) = self.max_bytes else {
+            return Ok(());
+        };
+
+        let mut folders: Vec<(PathBuf, u64, SystemTime)> = std::fs::read_dir(&self.root)
+            .map_err(|e| format!("Falha ao ler diretório: {}", e))?
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| {
+                let modified = entry
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .unwrap_or(UNIX_EPOCH);
+                (entry.path(), dir_size(&entry.path()), modified)
+            })
+            .collect();
+        folders.sort_by_key(|(_, _, modified)| *modified);
+
+        let mut total: u64 = folders.iter().map(|(_, size, _)| size).sum();
+        for (folder, size, _) in &folders {
+            if total < max_bytes {
+                break;
+            }
+            if in_use.contains(folder) {
+                continue;
+            }
+            if std::fs::remove_dir_all(folder).is_ok() {
+                total = total.saturating_sub(*size);
+                removed(folder);
+            }
+        }
+
+        if total < max_bytes {
+            Ok(())
+        } else {
+            Err("Limite de armazenamento atingido, tente novamente mais tarde".to_string())
+        }
+    }
+
+    // Files in the given job folders
+    pub fn list_files(&self, folders: &[PathBuf]) -> Vec<StoredFile> {
+        let mut files = Vec::new();
+        for folder in folders {
+            let relative = folder.strip_prefix(&self.root).unwrap_or(folder);
+            for file in media_files(folder) {
+                let name = file_name(&file);
+                files.push(StoredFile {
+                    path: format!("{}/{}", relative.to_string_lossy(), name),
+                    size: std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0),
+                    name,
+                });
+            }
+        }
+        files
+    }
+}
+
+// The downloaded file of a job: the largest finished file in its folder,
+// which skips thumbnails, subtitles and info files written next to it
+pub fn job_file(folder: &Path) -> Option<PathBuf> {
+    media_files(folder)
+        .into_iter()
+        .max_by_key(|file| std::fs::metadata(file).map(|m| m.len()).unwrap_or(0))
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn media_files(folder: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let ext = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or("")
+                .to_lowercase();
+            !TEMP_EXTENSIONS.contains(&ext.as_str())
+        })
+        .collect()
+}
+
+pub fn dir_size(path: &Path) -> u64 {
+    std::fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn new_folder_name() -> String {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    format!("{}-{}", millis, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
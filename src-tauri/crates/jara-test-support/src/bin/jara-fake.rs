@@ -0,0 +1,75 @@
+// Plays the scenario saved next to this executable. Installed by FakeTool as
+// yt-dlp or ffmpeg
+use jara_test_support::scenario::{Scenario, Step};
+use jara_test_support::tool::{calls_path, scenario_path};
+use std::io::Write;
+use std::process::ExitCode;
+use std::time::Duration;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return fail(&format!("executável desconhecido: {}", e)),
+    };
+
+    record_call(&calls_path(&exe), &args);
+
+    let scenario = match Scenario::from_file(scenario_path(&exe)) {
+        Ok(scenario) => scenario,
+        Err(e) => return fail(&e),
+    };
+    let Some(rule) = scenario.find_rule(&args) else {
+        return fail(&format!("nenhuma regra para {:?}", args));
+    };
+
+    for step in &rule.steps {
+        match step {
+            Step::Stdout { line } => println!("{}", scenario.expand(line, &args)),
+            Step::Stderr { line } => eprintln!("{}", scenario.expand(line, &args)),
+            Step::Sleep { ms } => std::thread::sleep(Duration::from_millis(*ms)),
+            Step::WriteFile { path, size } => {
+                let path = scenario.expand(path, &args);
+                if let Err(e) = write_file(&path, *size) {
+                    return fail(&format!("falha ao escrever {}: {}", path, e));
+                }
+            }
+            Step::CopyFile { from, to } => {
+                let to = scenario.expand(to, &args);
+                if let Err(e) = std::fs::copy(from, &to) {
+                    return fail(&format!("falha ao copiar {} para {}: {}", from, to, e));
+                }
+            }
+        }
+    }
+
+    if rule.hang {
+        loop {
+            std::thread::sleep(Duration::from_secs(60));
+        }
+    }
+    ExitCode::from(rule.exit_code as u8)
+}
+
+fn record_call(path: &std::path::Path, args: &[String]) {
+    let Ok(mut file) = std::fs::OpenOptions::new().create(true).append(true).open(path) else {
+        return;
+    };
+    if let Ok(line) = serde_json::to_string(args) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+fn write_file(path: &str, size: u64) -> std::io::Result<()> {
+    let path = std::path::Path::new(path);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, vec![0u8; size as usize])
+}
+
+// Reported the way yt-dlp reports errors, so the backend picks it up
+fn fail(message: &str) -> ExitCode {
+    eprintln!("ERROR: jara-fake: {}", message);
+    ExitCode::from(2)
+}
@@ -0,0 +1,9 @@
+// Scriptable stand-ins for yt-dlp, ffmpeg and ffprobe. A test describes what the
+// program should print, write and exit with in a Scenario, installs the
+// jara-fake binary under the program's name with FakeTool, and points the
+// backend at it through BinaryPaths
+pub mod scenario;
+pub mod tool;
+
+pub use scenario::{Rule, Scenario, Step};
+pub use tool::FakeTool;
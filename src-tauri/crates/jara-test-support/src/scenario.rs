@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// What a fake program does when it runs. Rules are tried in order and the
+// first one whose `when_args` all appear in the command line runs
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+    // Values for yt-dlp output templates such as %(title)s in written paths
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Rule {
+    #[serde(default)]
+    pub when_args: Vec<String>,
+    #[serde(default)]
+    pub steps: Vec<Step>,
+    #[serde(default)]
+    pub exit_code: i32,
+    // Keeps running after the steps until killed
+    #[serde(default)]
+    pub hang: bool,
+}
+
+// Lines and paths may use {after:<arg>} for the argument that follows <arg>
+// and {last} for the last argument
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Step {
+    Stdout { line: String },
+    Stderr { line: String },
+    Sleep { ms: u64 },
+    WriteFile { path: String, size: u64 },
+    // For outputs the code under test reads back, such as images
+    CopyFile { from: String, to: String },
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Answers --version like yt-dlp, which the backend checks before using it
+    pub fn ytdlp() -> Self {
+        Self::new()
+            .rule(Rule::on(&["--version"]).stdout("2024.12.13"))
+            .field("title", "Video")
+            .field("ext", "mp4")
+    }
+
+    // Answers -version like ffmpeg
+    pub fn ffmpeg() -> Self {
+        Self::new().rule(Rule::on(&["-version"]).stdout("ffmpeg version 7.1-fake"))
+    }
+
+    pub fn ffprobe() -> Self {
+        Self::new().rule(Rule::on(&["-version"]).stdout("ffprobe version 7.1-fake"))
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| format!("Falha ao ler cenário {}: {}", path.as_ref().display(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Cenário inválido: {}", e))
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn field(mut self, name: &str, value: &str) -> Self {
+        self.fields.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn find_rule(&self, args: &[String]) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.when_args.iter().all(|wanted| args.contains(wanted)))
+    }
+
+    // Fills in the placeholders of a step's line or path
+    pub fn expand(&self, text: &str, args: &[String]) -> String {
+        let mut result = text.to_string();
+
+        while let Some(start) = result.find("{after:") {
+            let Some(len) = result[start..].find('}') else {
+                break;
+            };
+            let flag = &result[start + "{after:".len()..start + len];
+            let value = args
+                .iter()
+                .position(|arg| arg == flag)
+                .and_then(|i| args.get(i + 1))
+                .cloned()
+                .unwrap_or_default();
+            result.replace_range(start..start + len + 1, &value);
+        }
+        result = result.replace("{last}", args.last().map(String::as_str).unwrap_or(""));
+
+        for (name, value) in &self.fields {
+            result = result.replace(&format!("%({})s", name), value);
+        }
+        result
+    }
+}
+
+impl Rule {
+    // A rule that runs whatever the arguments; put it last
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    pub fn on(args: &[&str]) -> Self {
+        Self {
+            when_args: args.iter().map(|a| a.to_string()).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn stdout(mut self, line: &str) -> Self {
+        self.steps.push(Step::Stdout { line: line.to_string() });
+        self
+    }
+
+    pub fn stderr(mut self, line: &str) -> Self {
+        self.steps.push(Step::Stderr { line: line.to_string() });
+        self
+    }
+
+    // Replays output recorded from the real program, one step per line
+    pub fn replay_stdout(mut self, recorded: &str) -> Self {
+        for line in recorded.lines() {
+            self.steps.push(Step::Stdout { line: line.to_string() });
+        }
+        self
+    }
+
+    pub fn replay_stderr(mut self, recorded: &str) -> Self {
+        for line in recorded.lines() {
+            self.steps.push(Step::Stderr { line: line.to_string() });
+        }
+        self
+    }
+
+    pub fn sleep(mut self, ms: u64) -> Self {
+        self.steps.push(Step::Sleep { ms });
+        self
+    }
+
+    pub fn write_file(mut self, path: &str, size: u64) -> Self {
+        self.steps.push(Step::WriteFile {
+            path: path.to_string(),
+            size,
+        });
+        self
+    }
+
+    pub fn copy_file(mut self, from: impl AsRef<Path>, to: &str) -> Self {
+        self.steps.push(Step::CopyFile {
+            from: from.as_ref().to_string_lossy().to_string(),
+            to: to.to_string(),
+        });
+        self
+    }
+
+    pub fn exit(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    pub fn hang(mut self) -> Self {
+        self.hang = true;
+        self
+    }
+}
@@ -0,0 +1,78 @@
+use crate::scenario::Scenario;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+// A copy of the jara-fake executable installed under a program name, next to
+// its scenario. Each copy gets its own directory, so tests can run in
+// parallel. The directory is removed on drop
+pub struct FakeTool {
+    dir: TempDir,
+    path: PathBuf,
+}
+
+impl FakeTool {
+    // `fake_exe` is the built jara-fake, env!("CARGO_BIN_EXE_jara-fake") in
+    // this crate's tests
+    pub fn install(fake_exe: impl AsRef<Path>, name: &str, scenario: &Scenario) -> Self {
+        let dir = tempfile::tempdir().expect("falha ao criar diretório temporário");
+        let path = dir.path().join(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+
+        // A copy, not a link: the fake finds its scenario through its own path
+        std::fs::copy(fake_exe.as_ref(), &path).expect("falha ao copiar jara-fake");
+        let tool = Self { dir, path };
+        tool.set_scenario(scenario);
+        tool
+    }
+
+    pub fn ytdlp(fake_exe: impl AsRef<Path>, scenario: &Scenario) -> Self {
+        Self::install(fake_exe, "yt-dlp", scenario)
+    }
+
+    pub fn ffmpeg(fake_exe: impl AsRef<Path>, scenario: &Scenario) -> Self {
+        Self::install(fake_exe, "ffmpeg", scenario)
+    }
+
+    pub fn ffprobe(fake_exe: impl AsRef<Path>, scenario: &Scenario) -> Self {
+        Self::install(fake_exe, "ffprobe", scenario)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Scratch space that lives as long as the tool
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+
+    // Takes effect on the next run
+    pub fn set_scenario(&self, scenario: &Scenario) {
+        let json = serde_json::to_string_pretty(scenario).expect("cenário inválido");
+        std::fs::write(scenario_path(&self.path), json).expect("falha ao salvar cenário");
+    }
+
+    // Arguments of every run so far, oldest first
+    pub fn calls(&self) -> Vec<Vec<String>> {
+        std::fs::read_to_string(calls_path(&self.path))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect()
+    }
+
+    // Runs other than the --version checks
+    pub fn calls_without_version(&self) -> Vec<Vec<String>> {
+        self.calls()
+            .into_iter()
+            .filter(|args| !args.iter().any(|a| a == "--version" || a == "-version"))
+            .collect()
+    }
+}
+
+pub fn scenario_path(exe: &Path) -> PathBuf {
+    exe.with_extension("scenario.json")
+}
+
+pub fn calls_path(exe: &Path) -> PathBuf {
+    exe.with_extension("calls.jsonl")
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use common::{after, probed_harness, Harness};
+use jara_core::animation::{AnimationFormat, AnimationRequest};
+use jara_test_support::{Rule, Scenario};
+
+// 19.06s, H.264 320x240 at 29.97 fps
+const PROBED_MEDIA: &str = include_str!("fixtures/ffprobe-zoo.json");
+
+fn video(harness: &Harness) -> String {
+    let path = harness.ffmpeg.dir().join("clip.mp4");
+    std::fs::write(&path, b"mp4").unwrap();
+    path.to_string_lossy().to_string()
+}
+
+#[tokio::test]
+async fn gif_is_made_with_a_palette_for_the_clip() {
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 2048)), PROBED_MEDIA);
+    let input = video(&harness);
+
+    let result = harness
+        .services
+        .ffmpeg
+        .video_to_animation(&AnimationRequest {
+            input_path: input.clone(),
+            start: Some(2.0),
+            end: Some(5.5),
+            ..AnimationRequest::default()
+        })
+        .await
+        .unwrap();
+
+    let output = harness.ffmpeg.dir().join("clip.gif");
+    let palette = harness.ffmpeg.dir().join("clip.palette.png");
+    assert_eq!(result.output, output.to_string_lossy());
+    assert_eq!((result.fps, result.width, result.size, result.attempts), (15.0, 320, 2048, 1));
+    assert!(output.exists());
+    assert!(!palette.exists());
+
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(calls[0][..6], ["-ss", "2", "-t", "3.5", "-i", &input]);
+    assert_eq!(
+        after(&calls[0], "-vf"),
+        "fps=15,scale=320:-1:flags=lanczos,palettegen=stats_mode=diff"
+    );
+    assert_eq!(calls[0].last().unwrap(), &palette.to_string_lossy().to_string());
+    assert_eq!(after(&calls[1], "-i"), input);
+    assert!(calls[1].contains(&palette.to_string_lossy().to_string()));
+    assert_eq!(
+        after(&calls[1], "-lavfi"),
+        "fps=15,scale=320:-1:flags=lanczos[x];[x][1:v]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle"
+    );
+    assert_eq!(after(&calls[1], "-loop"), "0");
+}
+
+#[tokio::test]
+async fn webp_plays_the_requested_number_of_times() {
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 1024)), PROBED_MEDIA);
+    let input = video(&harness);
+
+    let result = harness
+        .services
+        .ffmpeg
+        .video_to_animation(&AnimationRequest {
+            input_path: input,
+            format: AnimationFormat::Webp,
+            fps: Some(10.0),
+            width: Some(200),
+            loops: Some(3),
+            ..AnimationRequest::default()
+        })
+        .await
+        .unwrap();
+
+    assert!(result.output.ends_with("clip.webp"), "{}", result.output);
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 1);
+    // The whole video, from the probed duration
+    assert_eq!(calls[0][..4], ["-ss", "0", "-t", "19.06"]);
+    assert_eq!(after(&calls[0], "-vf"), "fps=10,scale=200:-1:flags=lanczos");
+    assert_eq!(after(&calls[0], "-c:v"), "libwebp");
+    assert_eq!(after(&calls[0], "-loop"), "3");
+}
+
+#[tokio::test]
+async fn size_limit_lowers_fps_and_width_until_it_fits() {
+    // Too big at the defaults, small enough at anything less
+    let ffmpeg = Scenario::ffmpeg()
+        .rule(Rule::on(&["fps=15,scale=320:-1:flags=lanczos"]).write_file("{last}", 300 * 1024))
+        .rule(Rule::any().write_file("{last}", 50 * 1024));
+    let harness = probed_harness(&ffmpeg, PROBED_MEDIA);
+    let input = video(&harness);
+
+    let result = harness
+        .services
+        .ffmpeg
+        .video_to_animation(&AnimationRequest {
+            input_path: input,
+            format: AnimationFormat::Webp,
+            max_size_kb: Some(100),
+            ..AnimationRequest::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!((result.fps, result.width, result.attempts), (10.0, 214, 2));
+    assert_eq!(result.size, 50 * 1024);
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(after(&calls[1], "-vf"), "fps=10,scale=214:-1:flags=lanczos");
+}
+
+#[tokio::test]
+async fn unreachable_size_limit_fails_without_leaving_a_file() {
+    let ffmpeg = Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 300 * 1024));
+    let harness = probed_harness(&ffmpeg, PROBED_MEDIA);
+    let input = video(&harness);
+
+    let error = harness
+        .services
+        .ffmpeg
+        .video_to_animation(&AnimationRequest {
+            input_path: input,
+            max_size_kb: Some(10),
+            ..AnimationRequest::default()
+        })
+        .await
+        .unwrap_err();
+
+    assert!(error.starts_with("Não foi possível ficar abaixo de 10 KiB; o menor resultado teve 300 KiB"), "{}", error);
+    assert!(!harness.ffmpeg.dir().join("clip.gif").exists());
+    assert!(!harness.ffmpeg.dir().join("clip.palette.png").exists());
+}
+
+#[tokio::test]
+async fn size_limit_needs_a_frame_rate_it_can_lower() {
+    let harness = probed_harness(&Scenario::ffmpeg(), PROBED_MEDIA);
+    let input = video(&harness);
+
+    let error = harness
+        .services
+        .ffmpeg
+        .video_to_animation(&AnimationRequest {
+            input_path: input,
+            fps: Some(0.4),
+            max_size_kb: Some(100),
+            ..AnimationRequest::default()
+        })
+        .await
+        .unwrap_err();
+
+    assert_eq!(error, "Com tamanho máximo, a taxa de quadros precisa ser de pelo menos 5 fps");
+    assert!(harness.ffmpeg.calls().is_empty());
+}
+
+#[tokio::test]
+async fn converting_to_gif_goes_through_the_palette() {
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 512)), PROBED_MEDIA);
+    let input = video(&harness);
+
+    let output = harness.services.ffmpeg.convert(&input, "gif").await.unwrap();
+
+    assert!(output.ends_with("clip.gif"), "{}", output);
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 2);
+    assert!(after(&calls[0], "-vf").ends_with("palettegen=stats_mode=diff"));
+}
@@ -0,0 +1,149 @@
+use jara_core::bulk_import::{BulkImporter, ImportDefaults, ImportReport};
+
+fn import(name: &str, content: &str) -> ImportReport {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join(name);
+    std::fs::write(&path, content).unwrap();
+    let defaults = ImportDefaults {
+        output_path: "/downloads".to_string(),
+        format_id: Some("best".to_string()),
+        audio_only: false,
+    };
+    BulkImporter::new()
+        .parse(&path.to_string_lossy(), &defaults)
+        .unwrap()
+}
+
+fn urls(report: &ImportReport) -> Vec<(usize, &str, &str)> {
+    report
+        .entries
+        .iter()
+        .map(|e| (e.line, e.url.as_str(), e.kind.as_str()))
+        .collect()
+}
+
+#[test]
+fn text_lists_skip_comments_and_report_duplicates_and_invalid_urls() {
+    let report = import(
+        "urls.txt",
+        "\u{feff}# semanal\n\
+         https://www.youtube.com/watch?v=jNQXAC9IVRw  # zoo\n\
+         \n\
+         https://www.youtube.com/playlist?list=PL123\n\
+         // canais\n\
+         https://www.youtube.com/@jawed\n\
+         https://www.youtube.com/watch?v=jNQXAC9IVRw\n\
+         ftp://example.com/video.mp4\n\
+         não é url\n",
+    );
+
+    assert_eq!(
+        urls(&report),
+        [
+            (2, "https://www.youtube.com/watch?v=jNQXAC9IVRw", "video"),
+            (4, "https://www.youtube.com/playlist?list=PL123", "playlist"),
+            (6, "https://www.youtube.com/@jawed", "channel"),
+        ]
+    );
+    assert_eq!(report.entries[0].output_path, "/downloads");
+    assert_eq!(report.entries[0].format_id.as_deref(), Some("best"));
+
+    let duplicates: Vec<usize> = report.duplicates.iter().map(|d| d.line).collect();
+    assert_eq!(duplicates, [7]);
+    let invalid: Vec<(usize, &str)> = report
+        .invalid
+        .iter()
+        .map(|i| (i.line, i.reason.as_str()))
+        .collect();
+    assert_eq!(invalid, [(8, "Protocolo não suportado: ftp"), (9, "URL inválida")]);
+}
+
+#[test]
+fn csv_rows_override_the_defaults() {
+    let report = import(
+        "lista.csv",
+        "URL,Formato,Pasta,Trecho\r\n\
+         https://youtu.be/a,audio,,\r\n\
+         \"https://youtu.be/b\",137+140,/videos,\"*0:00-1:30 | *10:00-12:00\"\r\n\
+         ,,,\r\n\
+         # https://youtu.be/skipped,,,\r\n\
+         https://youtu.be/c,,,\r\n",
+    );
+
+    assert_eq!(
+        urls(&report),
+        [
+            (2, "https://youtu.be/a", "video"),
+            (3, "https://youtu.be/b", "video"),
+            (6, "https://youtu.be/c", "video"),
+        ]
+    );
+    let [a, b, c] = &report.entries[..] else { panic!("{:?}", report.entries) };
+    assert!(a.audio_only);
+    assert_eq!(a.format_id, None);
+    assert_eq!(a.output_path, "/downloads");
+    assert_eq!(b.format_id.as_deref(), Some("137+140"));
+    assert_eq!(b.output_path, "/videos");
+    assert_eq!(b.sections, ["*0:00-1:30", "*10:00-12:00"]);
+    assert_eq!(c.format_id.as_deref(), Some("best"));
+}
+
+#[test]
+fn csv_quotes_only_open_a_field_at_its_start() {
+    let report = import(
+        "lista.csv",
+        "url,output\n\
+         https://example.com/search?q=\"gatos\",/a\n\
+         \"https://youtu.be/x\",\"/pasta, com \"\"aspas\"\"\nem duas linhas\"\n\
+         https://youtu.be/y,/b\n",
+    );
+
+    assert_eq!(
+        urls(&report),
+        [
+            (2, "https://example.com/search?q=%22gatos%22", "video"),
+            (3, "https://youtu.be/x", "video"),
+            (5, "https://youtu.be/y", "video"),
+        ]
+    );
+    let outputs: Vec<&str> = report.entries.iter().map(|e| e.output_path.as_str()).collect();
+    assert_eq!(outputs, ["/a", "/pasta, com \"aspas\"\nem duas linhas", "/b"]);
+}
+
+#[test]
+fn csv_without_url_column_is_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("lista.csv");
+    std::fs::write(&path, "link2,format\nhttps://youtu.be/a,best\n").unwrap();
+    let defaults = ImportDefaults {
+        output_path: "/downloads".to_string(),
+        format_id: None,
+        audio_only: false,
+    };
+
+    let error = BulkImporter::new()
+        .parse(&path.to_string_lossy(), &defaults)
+        .unwrap_err();
+
+    assert_eq!(error, "O CSV precisa de uma coluna \"url\"");
+}
+
+#[test]
+fn bookmarks_exports_yield_every_link() {
+    let report = import(
+        "bookmarks.html",
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n\
+         <DL><p>\n\
+         <DT><A HREF=\"https://www.youtube.com/watch?v=a&amp;list=PL1\" ADD_DATE=\"1\">Lista</A>\n\
+         <DT><A HREF=\"https://vimeo.com/channel/staffpicks\">Vimeo</A>\n\
+         </DL><p>\n",
+    );
+
+    assert_eq!(
+        urls(&report),
+        [
+            (3, "https://www.youtube.com/watch?v=a&list=PL1", "playlist"),
+            (4, "https://vimeo.com/channel/staffpicks", "channel"),
+        ]
+    );
+}
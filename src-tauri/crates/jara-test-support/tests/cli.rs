@@ -0,0 +1,184 @@
+mod common;
+
+use common::FAKE;
+use jara_test_support::{FakeTool, Rule, Scenario};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+const URL: &str = "https://www.youtube.com/watch?v=jNQXAC9IVRw";
+const PLAYLIST_URL: &str = "https://www.youtube.com/playlist?list=PL123";
+
+// jara-cli lives in another package, so Cargo doesn't build it for these
+// tests; build it once into the same target folder as jara-fake
+fn cli_exe() -> &'static Path {
+    static EXE: OnceLock<PathBuf> = OnceLock::new();
+    EXE.get_or_init(|| {
+        let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("../jara-cli/Cargo.toml");
+        let status = Command::new(env!("CARGO"))
+            .args(["build", "--quiet", "--bin", "jara-cli", "--manifest-path"])
+            .arg(manifest)
+            .status()
+            .unwrap();
+        assert!(status.success(), "falha ao compilar o jara-cli");
+        Path::new(FAKE).with_file_name(format!("jara-cli{}", std::env::consts::EXE_SUFFIX))
+    })
+}
+
+// jara-cli with fake tools and its own settings and output folders
+struct Cli {
+    ytdlp: FakeTool,
+    ffmpeg: FakeTool,
+    ffprobe: FakeTool,
+    home: tempfile::TempDir,
+}
+
+impl Cli {
+    fn new() -> Self {
+        Self {
+            ytdlp: FakeTool::ytdlp(FAKE, &Scenario::ytdlp()),
+            ffmpeg: FakeTool::ffmpeg(FAKE, &Scenario::ffmpeg()),
+            ffprobe: FakeTool::ffprobe(FAKE, &Scenario::ffprobe()),
+            home: tempfile::tempdir().unwrap(),
+        }
+    }
+
+    fn output(&self) -> PathBuf {
+        self.home.path().join("downloads")
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(cli_exe());
+        command
+            .arg("--yt-dlp")
+            .arg(self.ytdlp.path())
+            .arg("--ffmpeg")
+            .arg(self.ffmpeg.path())
+            .arg("--ffprobe")
+            .arg(self.ffprobe.path())
+            .args(args)
+            .arg("--output")
+            .arg(self.output())
+            // Keeps the user's real settings out of the tests
+            .env("HOME", self.home.path())
+            .env("XDG_DATA_HOME", self.home.path().join("data"))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+
+    fn run(&self, args: &[&str]) -> Output {
+        self.command(args).output().unwrap()
+    }
+}
+
+fn download_rule(url: &str, file: &Path) -> Rule {
+    let file = file.to_string_lossy();
+    Rule::on(&[url])
+        .stdout(&format!("[download] Destination: {}", file))
+        .stdout("[download] 100% of  520.11KiB in 00:00:00 at 2.10MiB/s")
+        .write_file(&file, 1024)
+}
+
+fn failed_rule(url: &str) -> Rule {
+    Rule::on(&[url]).stderr("ERROR: [youtube] Video unavailable").exit(1)
+}
+
+fn describe(output: &Output) -> String {
+    format!(
+        "código {:?}\nstdout: {}\nstderr: {}",
+        output.status.code(),
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+#[test]
+fn completed_download_exits_with_0() {
+    let cli = Cli::new();
+    let file = cli.output().join("Me at the zoo.mp4");
+    cli.ytdlp.set_scenario(&Scenario::ytdlp().rule(download_rule(URL, &file)));
+
+    let output = cli.run(&["download", URL]);
+
+    assert_eq!(output.status.code(), Some(0), "{}", describe(&output));
+    assert!(file.exists());
+}
+
+#[test]
+fn failed_download_exits_with_1() {
+    let cli = Cli::new();
+    cli.ytdlp.set_scenario(&Scenario::ytdlp().rule(failed_rule(URL)));
+
+    let output = cli.run(&["download", URL]);
+
+    assert_eq!(output.status.code(), Some(1), "{}", describe(&output));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Video unavailable"));
+}
+
+#[test]
+fn playlist_with_some_failures_exits_with_3() {
+    let first = "https://www.youtube.com/watch?v=primeiro";
+    let second = "https://www.youtube.com/watch?v=segundo";
+    let playlist = serde_json::json!({
+        "id": "PL123",
+        "title": "Playlist",
+        "entries": [
+            { "id": "primeiro", "title": "Primeiro", "url": first },
+            { "id": "segundo", "title": "Segundo", "url": second },
+        ],
+    });
+    let cli = Cli::new();
+    let file = cli.output().join("Primeiro.mp4");
+    cli.ytdlp.set_scenario(
+        &Scenario::ytdlp()
+            .rule(Rule::on(&["--flat-playlist"]).stdout(&playlist.to_string()))
+            .rule(download_rule(first, &file))
+            .rule(failed_rule(second)),
+    );
+
+    let output = cli.run(&["playlist", PLAYLIST_URL]);
+
+    assert_eq!(output.status.code(), Some(3), "{}", describe(&output));
+    assert!(file.exists());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("1 de 2 downloads concluídos"));
+
+    // Every entry failing is a plain failure
+    cli.ytdlp.set_scenario(
+        &Scenario::ytdlp()
+            .rule(Rule::on(&["--flat-playlist"]).stdout(&playlist.to_string()))
+            .rule(failed_rule(first))
+            .rule(failed_rule(second)),
+    );
+    let output = cli.run(&["playlist", PLAYLIST_URL]);
+    assert_eq!(output.status.code(), Some(1), "{}", describe(&output));
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupted_download_exits_with_130() {
+    use std::os::unix::process::CommandExt;
+
+    let cli = Cli::new();
+    cli.ytdlp.set_scenario(&Scenario::ytdlp().rule(Rule::on(&[URL]).hang()));
+    // Its own process group, like a job in a terminal
+    let child = cli.command(&["download", URL]).process_group(0).spawn().unwrap();
+
+    // Ctrl+C once yt-dlp is running; the terminal signals the whole group
+    let started = Instant::now();
+    while cli.ytdlp.calls_without_version().is_empty() {
+        assert!(started.elapsed() < Duration::from_secs(10), "o yt-dlp não foi chamado");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    let status = Command::new("kill")
+        .args(["-INT", "--", &format!("-{}", child.id())])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(130), "{}", describe(&output));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Interrompido"));
+}
@@ -0,0 +1,86 @@
+use jara_core::clipboard_watch::{ClipboardSource, ClipboardWatcher, DetectedUrl, SupportedSites};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Returns the next scripted text on every read, then keeps the last one
+struct ScriptedClipboard {
+    script: VecDeque<Option<&'static str>>,
+    current: Option<&'static str>,
+}
+
+impl ClipboardSource for ScriptedClipboard {
+    fn read_text(&mut self) -> Option<String> {
+        if let Some(next) = self.script.pop_front() {
+            self.current = next;
+        }
+        self.current.map(String::from)
+    }
+}
+
+fn extractors() -> Vec<String> {
+    ["youtube", "youtube:playlist", "vimeo", "generic"]
+        .map(String::from)
+        .to_vec()
+}
+
+#[tokio::test]
+async fn watcher_reports_each_new_supported_url_once() {
+    let source = ScriptedClipboard {
+        script: VecDeque::from([
+            // Already on the clipboard when the watcher starts
+            Some("https://www.youtube.com/watch?v=old"),
+            Some("olha isso: https://www.youtube.com/watch?v=abc."),
+            Some("olha isso: https://www.youtube.com/watch?v=abc."),
+            // Only the generic extractor would take it
+            Some("https://example.com/page"),
+            None,
+            Some("de novo https://www.youtube.com/watch?v=abc"),
+            Some("(https://vimeo.com/123) e https://youtu.be/xyz"),
+        ]),
+        current: None,
+    };
+    let detected: Arc<Mutex<Vec<DetectedUrl>>> = Arc::default();
+    let recorded = detected.clone();
+
+    let watcher = ClipboardWatcher::start(
+        Box::new(source),
+        SupportedSites::with_extractors(&extractors()),
+        Duration::from_millis(5),
+        move |url| recorded.lock().unwrap().push(url),
+    );
+
+    let started = Instant::now();
+    while detected.lock().unwrap().len() < 3 {
+        assert!(started.elapsed() < Duration::from_secs(10), "{:?}", detected.lock().unwrap());
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    // Nothing else comes once the script is over
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    drop(watcher);
+
+    let detected: Vec<(String, String)> = detected
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|d| (d.url.clone(), d.site.clone()))
+        .collect();
+    assert_eq!(
+        detected,
+        [
+            ("https://www.youtube.com/watch?v=abc".to_string(), "youtube".to_string()),
+            ("https://vimeo.com/123".to_string(), "vimeo".to_string()),
+            ("https://youtu.be/xyz".to_string(), "youtube".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn supported_sites_match_domains_to_extractors() {
+    let sites = SupportedSites::with_extractors(&extractors());
+
+    assert_eq!(sites.site_for("https://m.youtube.com/watch?v=a").await.as_deref(), Some("youtube"));
+    assert_eq!(sites.site_for("https://player.vimeo.com/video/1").await.as_deref(), Some("vimeo"));
+    assert_eq!(sites.site_for("https://example.com/video").await, None);
+    assert_eq!(sites.site_for("não é url").await, None);
+}
@@ -0,0 +1,80 @@
+use jara_core::comment_export::CommentExporter;
+
+const INFO_JSON: &str = r#"{
+    "id": "jNQXAC9IVRw",
+    "title": "Me at the zoo",
+    "comments": [
+        {
+            "id": "c1",
+            "parent": "root",
+            "author": "@jawed",
+            "author_id": "UC4QobU6STFB0P71PMvOGN5A",
+            "timestamp": 1700000000,
+            "like_count": 120,
+            "is_pinned": true,
+            "author_is_uploader": true,
+            "text": "Olá, \"zoo\"\nsegunda linha"
+        },
+        {
+            "id": "c1.r1",
+            "parent": "c1",
+            "author": "@fã",
+            "text": "elefantes"
+        }
+    ]
+}"#;
+
+fn info_json(content: &str) -> (tempfile::TempDir, String) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("Me at the zoo.info.json");
+    std::fs::write(&path, content).unwrap();
+    let path = path.to_string_lossy().to_string();
+    (dir, path)
+}
+
+#[test]
+fn comments_are_exported_as_csv() {
+    let (dir, input) = info_json(INFO_JSON);
+
+    let output = CommentExporter::new().export(&input, "CSV").unwrap();
+
+    assert_eq!(output, dir.path().join("Me at the zoo.comments.csv").to_string_lossy());
+    assert_eq!(
+        std::fs::read_to_string(output).unwrap(),
+        "id,parent,author,author_id,timestamp,like_count,is_pinned,author_is_uploader,text\n\
+         c1,root,@jawed,UC4QobU6STFB0P71PMvOGN5A,1700000000,120,true,true,\"Olá, \"\"zoo\"\"\nsegunda linha\"\n\
+         c1.r1,c1,@fã,,,,false,false,elefantes\n"
+    );
+}
+
+#[test]
+fn comments_are_exported_as_ndjson() {
+    let (_dir, input) = info_json(INFO_JSON);
+
+    let output = CommentExporter::new().export(&input, "ndjson").unwrap();
+
+    assert!(output.ends_with("Me at the zoo.comments.ndjson"), "{}", output);
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(output)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["text"], "Olá, \"zoo\"\nsegunda linha");
+    assert_eq!(lines[0]["like_count"], 120);
+    assert_eq!(lines[1]["parent"], "c1");
+    assert_eq!(lines[1]["author_id"], serde_json::Value::Null);
+    assert_eq!(lines[1]["is_pinned"], false);
+}
+
+#[test]
+fn info_json_without_comments_is_refused() {
+    let (_dir, input) = info_json(r#"{"id": "jNQXAC9IVRw"}"#);
+    let exporter = CommentExporter::new();
+
+    assert_eq!(
+        exporter.export(&input, "csv").unwrap_err(),
+        "O arquivo não contém comentários. Baixe com a opção de comentários ativada."
+    );
+    assert_eq!(exporter.export(&input, "xlsx").unwrap_err(), "Formato não suportado: xlsx");
+}
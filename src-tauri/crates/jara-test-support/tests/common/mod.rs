@@ -0,0 +1,172 @@
+// Shared setup for the integration tests; not every test file uses all of it
+#![allow(dead_code)]
+
+use jara_core::download_manager::Job;
+use jara_core::encoding::StreamPlan;
+use jara_core::ffmpeg::ConvertProgress;
+use jara_core::settings::Settings;
+use jara_core::ytdlp::{DownloadProgress, DownloadRequest};
+use jara_core::{BinaryPaths, EventSink, Services};
+use jara_test_support::{FakeTool, Rule, Scenario};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub const FAKE: &str = env!("CARGO_BIN_EXE_jara-fake");
+
+const WAIT_LIMIT: Duration = Duration::from_secs(10);
+
+// Services wired to fake yt-dlp, ffmpeg and ffprobe, recording every
+// progress event
+pub struct Harness {
+    pub services: Services,
+    pub events: Arc<Mutex<Vec<DownloadProgress>>>,
+    pub ytdlp: FakeTool,
+    pub ffmpeg: FakeTool,
+    pub ffprobe: FakeTool,
+}
+
+impl Harness {
+    pub fn new(ytdlp: &Scenario, ffmpeg: &Scenario) -> Self {
+        Self::with_tools(ytdlp, ffmpeg, &Scenario::ffprobe())
+    }
+
+    pub fn with_tools(ytdlp: &Scenario, ffmpeg: &Scenario, ffprobe: &Scenario) -> Self {
+        let ytdlp = FakeTool::ytdlp(FAKE, ytdlp);
+        let ffmpeg = FakeTool::ffmpeg(FAKE, ffmpeg);
+        let ffprobe = FakeTool::ffprobe(FAKE, ffprobe);
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let recorded = events.clone();
+        let services = Services::new(
+            BinaryPaths {
+                ytdlp: ytdlp.path().to_path_buf(),
+                ffmpeg: ffmpeg.path().to_path_buf(),
+                ffprobe: ffprobe.path().to_path_buf(),
+            },
+            Settings::default(),
+            move |progress: DownloadProgress| recorded.lock().unwrap().push(progress),
+        );
+
+        Self {
+            services,
+            events,
+            ytdlp,
+            ffmpeg,
+            ffprobe,
+        }
+    }
+
+    pub fn with_ytdlp(ytdlp: &Scenario) -> Self {
+        Self::new(ytdlp, &Scenario::ffmpeg())
+    }
+
+    pub fn with_ffmpeg(ffmpeg: &Scenario) -> Self {
+        Self::new(&Scenario::ytdlp(), ffmpeg)
+    }
+
+    pub fn with_ffprobe(ffprobe: &Scenario) -> Self {
+        Self::with_tools(&Scenario::ytdlp(), &Scenario::ffmpeg(), ffprobe)
+    }
+
+    pub fn events_for(&self, id: &str) -> Vec<DownloadProgress> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|p| p.download_id == id)
+            .cloned()
+            .collect()
+    }
+
+    pub async fn wait_for_status(&self, id: &str, statuses: &[&str]) -> Job {
+        let started = std::time::Instant::now();
+        loop {
+            let job = self.services.downloads.get_job(id).expect("job desconhecido");
+            if statuses.contains(&job.status.as_str()) {
+                return job;
+            }
+            if started.elapsed() > WAIT_LIMIT {
+                panic!("job {} parou em {:?}, esperado {:?}", id, job.status, statuses);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    pub async fn wait_for_progress(&self, id: &str, progress: f64) {
+        let started = std::time::Instant::now();
+        while !self.events_for(id).iter().any(|p| p.progress == progress) {
+            if started.elapsed() > WAIT_LIMIT {
+                panic!("job {} não chegou a {}%", id, progress);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    pub async fn wait_until_finished(&self, id: &str) -> Job {
+        self.wait_for_status(id, &["completed", "error", "cancelled"]).await
+    }
+}
+
+pub fn request(url: &str, output: &Path) -> DownloadRequest {
+    DownloadRequest {
+        url: url.to_string(),
+        format_id: None,
+        output_path: output.to_string_lossy().to_string(),
+        audio_only: false,
+        download_subs: false,
+        sub_lang: None,
+        live: None,
+        metadata: None,
+        sections: Vec::new(),
+        download_archive: None,
+        post_actions: Vec::new(),
+        no_playlist: false,
+        max_filesize: None,
+    }
+}
+
+// Fake ffmpeg next to an ffprobe that answers every input with the recorded JSON
+pub fn probed_harness(ffmpeg: &Scenario, ffprobe_json: &str) -> Harness {
+    let ffprobe = Scenario::ffprobe().rule(Rule::any().replay_stdout(ffprobe_json));
+    Harness::with_tools(&Scenario::ytdlp(), ffmpeg, &ffprobe)
+}
+
+// Records conversion progress events
+#[derive(Clone, Default)]
+pub struct ConvertEvents(Arc<Mutex<Vec<ConvertProgress>>>);
+
+impl EventSink for ConvertEvents {
+    fn download_progress(&self, _progress: DownloadProgress) {}
+
+    fn convert_progress(&self, progress: ConvertProgress) {
+        self.0.lock().unwrap().push(progress);
+    }
+}
+
+impl ConvertEvents {
+    pub fn all(&self) -> Vec<ConvertProgress> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+// The value following a flag in an ffmpeg command line
+pub fn after<'a>(args: &'a [String], flag: &str) -> &'a str {
+    &args[args.iter().position(|a| a == flag).unwrap() + 1]
+}
+
+// "kind source_codec action encoder" for each planned stream
+pub fn describe(streams: &[StreamPlan]) -> Vec<String> {
+    streams
+        .iter()
+        .map(|s| {
+            format!(
+                "{} {} {} {}",
+                s.kind,
+                s.source_codec.as_deref().unwrap_or("-"),
+                s.action,
+                s.encoder.as_deref().unwrap_or("-")
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use common::Harness;
+use jara_core::concat::ConcatRequest;
+use jara_test_support::{Rule, Scenario};
+use std::path::PathBuf;
+
+// 19.06s, H.264 320x240 at 29.97 fps, AAC 44.1 kHz stereo
+const PROBED_MEDIA: &str = include_str!("fixtures/ffprobe-zoo.json");
+
+// Harness whose ffprobe answers with `probe` for each named input, and the
+// paths of those inputs
+fn harness_with_inputs(inputs: &[(&str, String)]) -> (Harness, Vec<String>) {
+    let harness = Harness::new(&Scenario::ytdlp(), &Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 512)));
+    let mut ffprobe = Scenario::ffprobe();
+    let mut paths = Vec::new();
+    for (name, probe) in inputs {
+        let path = harness.ffmpeg.dir().join(name).to_string_lossy().to_string();
+        std::fs::write(&path, b"media").unwrap();
+        ffprobe = ffprobe.rule(Rule::on(&[&path]).replay_stdout(probe));
+        paths.push(path);
+    }
+    harness.ffprobe.set_scenario(&ffprobe);
+    (harness, paths)
+}
+
+fn request(paths: &[String], chapters: bool) -> ConcatRequest {
+    ConcatRequest {
+        input_paths: paths.to_vec(),
+        output_path: None,
+        chapters,
+    }
+}
+
+#[tokio::test]
+async fn matching_files_are_joined_without_re_encoding() {
+    let (harness, paths) = harness_with_inputs(&[
+        ("part1.mp4", PROBED_MEDIA.to_string()),
+        ("part2.mp4", PROBED_MEDIA.to_string()),
+    ]);
+
+    let result = harness.services.ffmpeg.concat_media(&request(&paths, true)).await.unwrap();
+
+    let output = harness.ffmpeg.dir().join("part1-joined.mp4");
+    assert_eq!(PathBuf::from(&result.output), output);
+    assert!(output.exists());
+    assert!(result.lossless);
+    assert_eq!(result.reason, None);
+
+    let titles: Vec<_> = result.chapters.iter().map(|c| c.title.as_deref().unwrap()).collect();
+    assert_eq!(titles, ["part1", "part2"]);
+    assert_eq!((result.chapters[1].start, result.chapters[1].end), (19.06, 38.12));
+
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 1);
+    let args = calls[0].join(" ");
+    assert!(args.starts_with("-f concat -safe 0 -i "), "{}", args);
+    assert!(
+        args.contains("-map 0:V? -map 0:a? -map 0:s? -map_chapters 1 -c copy"),
+        "{}",
+        args
+    );
+
+    // The list and the chapters are only needed while ffmpeg runs
+    assert!(!output.with_extension("concat.txt").exists());
+    assert!(!output.with_extension("chapters.txt").exists());
+}
+
+#[tokio::test]
+async fn different_files_are_normalized_to_the_first_one() {
+    let larger = PROBED_MEDIA
+        .replace("\"width\": 320", "\"width\": 640")
+        .replace("\"height\": 240", "\"height\": 480");
+    let (harness, paths) = harness_with_inputs(&[("a.mp4", PROBED_MEDIA.to_string()), ("b.mp4", larger)]);
+
+    let result = harness.services.ffmpeg.concat_media(&request(&paths, false)).await.unwrap();
+
+    assert!(!result.lossless);
+    assert_eq!(
+        result.reason.as_deref(),
+        Some("b.mp4: vídeo h264 640x480 yuv420p 29.97fps em vez de vídeo h264 320x240 yuv420p 29.97fps")
+    );
+    assert!(result.chapters.is_empty());
+
+    let args = &harness.ffmpeg.calls()[0];
+    let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+    assert!(filter.contains("[1:V:0]scale=320:240:force_original_aspect_ratio=decrease"), "{}", filter);
+    assert!(filter.contains("[1:a:0]aresample=44100,aformat=channel_layouts=stereo[a1]"), "{}", filter);
+    assert!(filter.ends_with("[v0][a0][v1][a1]concat=n=2:v=1:a=1[v][a]"), "{}", filter);
+    assert!(args.contains(&"libx264".to_string()), "{:?}", args);
+}
+
+#[tokio::test]
+async fn missing_audio_is_filled_with_silence() {
+    let silent = serde_json::to_string(&{
+        let mut probe: serde_json::Value = serde_json::from_str(PROBED_MEDIA).unwrap();
+        probe["streams"].as_array_mut().unwrap().truncate(1);
+        probe
+    })
+    .unwrap();
+    let (harness, paths) = harness_with_inputs(&[("intro.mp4", silent), ("talk.mp4", PROBED_MEDIA.to_string())]);
+
+    let result = harness.services.ffmpeg.concat_media(&request(&paths, false)).await.unwrap();
+
+    assert_eq!(result.reason.as_deref(), Some("talk.mp4: faixas diferentes das do primeiro arquivo"));
+    let args = &harness.ffmpeg.calls()[0];
+    let filter = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+    assert!(filter.contains("aevalsrc=0:c=stereo:s=44100:d=19.06[a0]"), "{}", filter);
+}
+
+#[tokio::test]
+async fn concat_needs_two_probed_inputs() {
+    let (harness, paths) = harness_with_inputs(&[("only.mp4", PROBED_MEDIA.to_string())]);
+
+    let error = harness.services.ffmpeg.concat_media(&request(&paths, false)).await.unwrap_err();
+    assert_eq!(error, "Informe ao menos dois arquivos para juntar");
+
+    let missing = harness.ffmpeg.dir().join("missing.mp4").to_string_lossy().to_string();
+    let error = harness
+        .services
+        .ffmpeg
+        .concat_media(&request(&[paths[0].clone(), missing], false))
+        .await
+        .unwrap_err();
+    assert_eq!(error, "Arquivo de entrada não encontrado");
+    assert!(harness.ffmpeg.calls().is_empty());
+}
+
+#[tokio::test]
+async fn existing_outputs_are_never_overwritten_or_removed() {
+    let (harness, paths) = harness_with_inputs(&[
+        ("part1.mp4", PROBED_MEDIA.to_string()),
+        ("part2.mp4", PROBED_MEDIA.to_string()),
+    ]);
+    let output = harness.ffmpeg.dir().join("part1-joined.mp4");
+    std::fs::write(&output, b"meu arquivo").unwrap();
+
+    let error = harness.services.ffmpeg.concat_media(&request(&paths, false)).await.unwrap_err();
+    assert_eq!(error, format!("O arquivo de saída já existe: {}", output.to_string_lossy()));
+    assert_eq!(std::fs::read(&output).unwrap(), b"meu arquivo");
+    assert!(harness.ffmpeg.calls().is_empty());
+
+    // What a failed run wrote itself is cleaned up
+    std::fs::remove_file(&output).unwrap();
+    harness
+        .ffmpeg
+        .set_scenario(&Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 512).exit(1)));
+    harness.services.ffmpeg.concat_media(&request(&paths, false)).await.unwrap_err();
+    assert!(!output.exists());
+}
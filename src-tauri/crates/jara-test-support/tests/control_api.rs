@@ -0,0 +1,118 @@
+mod common;
+
+use common::Harness;
+use jara_core::control_api::ControlApi;
+use jara_test_support::Scenario;
+use serde_json::json;
+
+const TOKEN: &str = "0123456789abcdef0123456789abcdef";
+
+async fn start_api(harness: &Harness) -> (ControlApi, String) {
+    // Let the OS pick a free port, then hand it to the API
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let api = ControlApi::start(port, TOKEN.to_string(), harness.services.clone())
+        .await
+        .unwrap();
+    (api, format!("http://127.0.0.1:{}/api/v1/jobs", port))
+}
+
+#[tokio::test]
+async fn enqueue_refuses_post_actions_and_output_paths() {
+    let harness = Harness::with_ytdlp(&Scenario::ytdlp());
+    let (_api, jobs) = start_api(&harness).await;
+    let marker = harness.ytdlp.dir().join("pwned");
+    let client = reqwest::Client::new();
+
+    let response = client
+        .post(&jobs)
+        .bearer_auth(TOKEN)
+        .json(&json!({
+            "url": "https://youtu.be/jNQXAC9IVRw",
+            "output_path": "/tmp",
+            "post_actions": [{ "type": "command", "program": "touch", "args": [marker] }],
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.status().is_client_error(), "{}", response.status());
+    assert!(harness.services.downloads.list_jobs().is_empty());
+    assert!(harness.ytdlp.calls_without_version().is_empty());
+    assert!(!marker.exists());
+}
+
+#[tokio::test]
+async fn enqueue_takes_a_url_and_a_preset() {
+    let harness = Harness::with_ytdlp(&Scenario::ytdlp());
+    let (_api, jobs) = start_api(&harness).await;
+    let client = reqwest::Client::new();
+
+    let unauthorized = client
+        .post(&jobs)
+        .json(&json!({ "url": "https://youtu.be/jNQXAC9IVRw" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unauthorized.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .post(&jobs)
+        .bearer_auth(TOKEN)
+        .json(&json!({ "url": "https://youtu.be/jNQXAC9IVRw" }))
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success(), "{}", response.status());
+    let body: serde_json::Value = response.json().await.unwrap();
+    let id = body["id"].as_str().unwrap();
+
+    // The request comes from the default preset, not from the client
+    let job = harness.services.downloads.get_job(id).unwrap();
+    assert_eq!(job.request.url, "https://youtu.be/jNQXAC9IVRw");
+    assert_eq!(job.request.output_path, harness.services.settings().output_path());
+    assert!(job.request.post_actions.is_empty());
+
+    let unknown = client
+        .post(&jobs)
+        .bearer_auth(TOKEN)
+        .json(&json!({ "url": "https://youtu.be/jNQXAC9IVRw", "preset": "nenhum" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unknown.status(), reqwest::StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = unknown.json().await.unwrap();
+    assert_eq!(body["error"], "Preset não encontrado: nenhum");
+}
+
+#[tokio::test]
+async fn urls_that_look_like_options_never_reach_ytdlp() {
+    let harness = Harness::with_ytdlp(&Scenario::ytdlp());
+    let (api, jobs) = start_api(&harness).await;
+    let client = reqwest::Client::new();
+
+    for url in ["--config-locations=/tmp/jara.conf", "--batch-file=/etc/passwd", "file:///etc/passwd"] {
+        let enqueue = client
+            .post(&jobs)
+            .bearer_auth(TOKEN)
+            .json(&json!({ "url": url }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(enqueue.status(), reqwest::StatusCode::BAD_REQUEST, "{}", url);
+
+        let info = client
+            .get(format!("http://127.0.0.1:{}/api/v1/video-info", api.port()))
+            .query(&[("url", url)])
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(info.status(), reqwest::StatusCode::BAD_REQUEST, "{}", url);
+    }
+    assert!(harness.services.downloads.list_jobs().is_empty());
+    assert!(harness.ytdlp.calls_without_version().is_empty());
+}
@@ -0,0 +1,448 @@
+mod common;
+
+use common::{describe, probed_harness, ConvertEvents, Harness};
+use jara_core::encoding::ConversionOptions;
+use jara_core::ffmpeg::FFmpeg;
+use jara_test_support::{Rule, Scenario};
+
+const RECORDED_CONVERSION: &str = include_str!("fixtures/ffmpeg-convert.txt");
+const RECORDED_PROGRESS: &str = include_str!("fixtures/ffmpeg-progress.txt");
+const PROBED_MEDIA: &str = include_str!("fixtures/ffprobe-zoo.json");
+const PROBED_HDR: &str = include_str!("fixtures/ffprobe-hdr.json");
+
+// The planned arguments between the input and the progress flags
+async fn codec_args(ffmpeg: &FFmpeg, input_path: &str, format: &str, options: &ConversionOptions) -> String {
+    let args = ffmpeg.plan_conversion(input_path, format, options).await.unwrap().args;
+    args[3..args.len() - 4].join(" ")
+}
+
+#[tokio::test]
+async fn convert_media_runs_ffmpeg_and_returns_output() {
+    let scenario = Scenario::ffmpeg().rule(
+        Rule::any()
+            .replay_stderr(RECORDED_CONVERSION)
+            .write_file("{last}", 2048),
+    );
+    let harness = Harness::with_ffmpeg(&scenario);
+    let input = harness.ffmpeg.dir().join("input.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+
+    let output = harness
+        .services
+        .ffmpeg
+        .convert(&input.to_string_lossy(), "mp3")
+        .await
+        .unwrap();
+
+    assert_eq!(output, input.with_extension("mp3").to_string_lossy());
+    assert_eq!(std::fs::metadata(&output).unwrap().len(), 2048);
+
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 1);
+    let args = &calls[0];
+    assert_eq!(args[..2], ["-i".to_string(), input.to_string_lossy().to_string()]);
+    assert!(args.contains(&"libmp3lame".to_string()), "{:?}", args);
+    assert_eq!(args.last().unwrap(), &output);
+}
+
+#[tokio::test]
+async fn failed_conversion_reports_ffmpeg_output() {
+    let scenario = Scenario::ffmpeg().rule(
+        Rule::any()
+            .stderr("ffmpeg version 7.1 Copyright (c) 2000-2024 the FFmpeg developers")
+            .stderr("{after:-i}: Invalid data found when processing input")
+            .exit(183),
+    );
+    let harness = Harness::with_ffmpeg(&scenario);
+    let input = harness.ffmpeg.dir().join("broken.mkv");
+    std::fs::write(&input, b"not a video").unwrap();
+
+    let error = harness
+        .services
+        .ffmpeg
+        .convert(&input.to_string_lossy(), "mp4")
+        .await
+        .unwrap_err();
+
+    assert!(error.starts_with("Erro na conversão"), "{}", error);
+    assert!(error.contains("broken.mkv: Invalid data found"), "{}", error);
+    assert!(!input.with_extension("mp4").exists());
+}
+
+#[tokio::test]
+async fn missing_input_is_refused_before_running_ffmpeg() {
+    let harness = Harness::with_ffmpeg(&Scenario::ffmpeg().rule(Rule::any()));
+    let input = harness.ffmpeg.dir().join("missing.mkv");
+
+    let error = harness
+        .services
+        .ffmpeg
+        .convert(&input.to_string_lossy(), "mp3")
+        .await
+        .unwrap_err();
+
+    assert_eq!(error, "Arquivo de entrada não encontrado");
+    assert!(harness.ffmpeg.calls().is_empty());
+}
+
+#[tokio::test]
+async fn convert_by_extension_picks_the_converter() {
+    let scenario = Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 16));
+    let harness = Harness::with_ffmpeg(&scenario);
+    let dir = harness.ffmpeg.dir();
+
+    // Images stay in-process
+    let image = dir.join("photo.png");
+    image::RgbImage::from_pixel(8, 4, image::Rgb([200, 30, 30]))
+        .save(&image)
+        .unwrap();
+    let converted = harness
+        .services
+        .convert_by_extension(&image.to_string_lossy(), "jpg")
+        .await
+        .unwrap();
+    assert_eq!(image::image_dimensions(&converted).unwrap(), (8, 4));
+    assert!(harness.ffmpeg.calls().is_empty());
+
+    // Media goes to ffmpeg
+    let video = dir.join("clip.webm");
+    std::fs::write(&video, b"webm").unwrap();
+    let converted = harness
+        .services
+        .convert_by_extension(&video.to_string_lossy(), "mp3")
+        .await
+        .unwrap();
+    assert!(converted.ends_with("clip.mp3"));
+    assert_eq!(harness.ffmpeg.calls().len(), 1);
+}
+
+#[tokio::test]
+async fn conversion_reports_progress_against_the_probed_duration() {
+    let ffmpeg = Scenario::ffmpeg().rule(
+        Rule::any()
+            .replay_stdout(RECORDED_PROGRESS)
+            .write_file("{last}", 1024),
+    );
+    let harness = probed_harness(&ffmpeg, PROBED_MEDIA);
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let events = ConvertEvents::default();
+
+    let output = harness
+        .services
+        .ffmpeg
+        .start_conversion("c1".to_string(), &input.to_string_lossy(), "webm", &ConversionOptions::default(), events.clone())
+        .await
+        .unwrap();
+
+    let events = events.all();
+    let statuses: Vec<&str> = events.iter().map(|e| e.status.as_str()).collect();
+    assert_eq!(statuses, ["converting", "converting", "completed"]);
+    assert!(events.iter().all(|e| e.conversion_id == "c1"));
+
+    let halfway = &events[0];
+    assert_eq!(halfway.progress, 50.0);
+    assert_eq!(halfway.fps, Some(59.87));
+    assert_eq!(halfway.speed, Some(2.0));
+    assert!((halfway.eta.unwrap() - 4.765).abs() < 0.001, "{:?}", halfway.eta);
+
+    assert_eq!(events[1].progress, 100.0);
+    assert_eq!(events[1].eta, Some(0.0));
+    assert_eq!(events[2].output.as_deref(), Some(output.as_str()));
+}
+
+#[tokio::test]
+async fn cancel_conversion_kills_ffmpeg_and_removes_partial_output() {
+    let ffmpeg = Scenario::ffmpeg().rule(
+        Rule::any()
+            .write_file("{last}", 512)
+            .stdout("out_time_us=1000000")
+            .stdout("progress=continue")
+            .hang(),
+    );
+    let harness = probed_harness(&ffmpeg, PROBED_MEDIA);
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let events = ConvertEvents::default();
+
+    let ffmpeg = harness.services.ffmpeg.clone();
+    let input_path = input.to_string_lossy().to_string();
+    let recorded = events.clone();
+    let conversion = tokio::spawn(async move {
+        ffmpeg
+            .start_conversion("c2".to_string(), &input_path, "mp4", &ConversionOptions::default(), recorded)
+            .await
+    });
+
+    let started = std::time::Instant::now();
+    while events.all().is_empty() {
+        assert!(started.elapsed().as_secs() < 10, "ffmpeg não informou progresso");
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert!(input.with_extension("mp4").exists());
+
+    harness.services.ffmpeg.cancel_conversion("c2").await.unwrap();
+    let error = conversion.await.unwrap().unwrap_err();
+
+    assert_eq!(error, "Conversão cancelada");
+    assert!(!input.with_extension("mp4").exists());
+    assert!(input.exists());
+    assert!(harness.services.ffmpeg.cancel_conversion("c2").await.is_err());
+}
+
+#[tokio::test]
+async fn conversion_options_reach_ffmpeg() {
+    let scenario = Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 64));
+    let harness = Harness::with_ffmpeg(&scenario);
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let input_path = input.to_string_lossy().to_string();
+    let options = ConversionOptions {
+        video_codec: Some("hevc".to_string()),
+        crf: Some(28),
+        encoder_preset: Some("slow".to_string()),
+        height: Some(720),
+        fps: Some(30.0),
+        audio_bitrate: Some(128),
+        sample_rate: Some(48000),
+        channels: Some(2),
+        ..Default::default()
+    };
+
+    let preview = harness
+        .services
+        .ffmpeg
+        .plan_conversion(&input_path, "mp4", &options)
+        .await
+        .unwrap()
+        .args;
+    let output = input.with_extension("mp4").to_string_lossy().to_string();
+    let expected: Vec<String> = [
+        "-i", &input_path, "-y",
+        "-c:v", "libx265", "-crf", "28", "-preset", "slow", "-vf", "scale=-2:720", "-r", "30",
+        "-c:a", "aac", "-b:a", "128k", "-ar", "48000", "-ac", "2",
+        "-progress", "pipe:1", "-nostats", &output,
+    ]
+    .iter()
+    .map(|arg| arg.to_string())
+    .collect();
+    assert_eq!(preview, expected);
+    // The dry run doesn't touch ffmpeg
+    assert!(harness.ffmpeg.calls().is_empty());
+
+    harness
+        .services
+        .ffmpeg
+        .convert_with_options(&input_path, "mp4", &options)
+        .await
+        .unwrap();
+    assert_eq!(harness.ffmpeg.calls(), [expected]);
+}
+
+#[tokio::test]
+async fn default_recipes_are_kept_without_options() {
+    let harness = Harness::with_ffmpeg(&Scenario::ffmpeg());
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let input_path = input.to_string_lossy().to_string();
+    let ffmpeg = &harness.services.ffmpeg;
+    let defaults = ConversionOptions::default();
+
+    assert_eq!(codec_args(ffmpeg, &input_path, "mp3", &defaults).await, "-vn -c:a libmp3lame -q:a 0");
+    assert_eq!(codec_args(ffmpeg, &input_path, "m4a", &defaults).await, "-vn -c:a aac -b:a 256k");
+    assert_eq!(
+        codec_args(ffmpeg, &input_path, "mp4", &defaults).await,
+        "-c:v libx264 -preset fast -c:a aac -b:a 256k"
+    );
+    assert_eq!(
+        codec_args(ffmpeg, &input_path, "webm", &defaults).await,
+        "-c:v libvpx-vp9 -b:v 2M -c:a libopus"
+    );
+    assert_eq!(codec_args(ffmpeg, &input_path, "mkv", &defaults).await, "-c:v copy -c:a copy");
+    assert_eq!(codec_args(ffmpeg, &input_path, "avi", &defaults).await, "-c copy");
+
+    // Scaling a copied stream needs an encoder; the audio is still copied
+    let scaled = ConversionOptions {
+        width: Some(1280),
+        ..Default::default()
+    };
+    assert_eq!(
+        codec_args(ffmpeg, &input_path, "mkv", &scaled).await,
+        "-c:v libx264 -preset fast -vf scale=1280:-2 -c:a copy"
+    );
+
+    // VP9 only honours CRF with the bitrate cap off
+    let quality = ConversionOptions {
+        crf: Some(31),
+        ..Default::default()
+    };
+    assert_eq!(
+        codec_args(ffmpeg, &input_path, "webm", &quality).await,
+        "-c:v libvpx-vp9 -crf 31 -b:v 0 -c:a libopus"
+    );
+}
+
+#[tokio::test]
+async fn invalid_conversion_options_are_refused_before_running_ffmpeg() {
+    let harness = Harness::with_ffmpeg(&Scenario::ffmpeg().rule(Rule::any()));
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let input_path = input.to_string_lossy().to_string();
+
+    let cases = [
+        (
+            "mp4",
+            ConversionOptions {
+                crf: Some(20),
+                video_bitrate: Some(4000),
+                ..Default::default()
+            },
+            "Use CRF ou bitrate de vídeo, não os dois",
+        ),
+        (
+            "mp4",
+            ConversionOptions {
+                crf: Some(60),
+                ..Default::default()
+            },
+            "CRF inválido para h264: 60 (use de 0 a 51)",
+        ),
+        (
+            "mp3",
+            ConversionOptions {
+                height: Some(720),
+                ..Default::default()
+            },
+            "mp3 é um formato só de áudio; parâmetros de vídeo não se aplicam",
+        ),
+        (
+            "mp4",
+            ConversionOptions {
+                video_codec: Some("vp9".to_string()),
+                ..Default::default()
+            },
+            "mp4 não suporta vídeo vp9",
+        ),
+        (
+            "mkv",
+            ConversionOptions {
+                video_codec: Some("copy".to_string()),
+                fps: Some(24.0),
+                ..Default::default()
+            },
+            "Copiar o vídeo não permite mudar qualidade, tamanho ou fps",
+        ),
+        (
+            "flac",
+            ConversionOptions {
+                audio_bitrate: Some(320),
+                ..Default::default()
+            },
+            "O codec flac é sem perdas e não usa bitrate",
+        ),
+        (
+            "mp4",
+            ConversionOptions {
+                width: Some(641),
+                ..Default::default()
+            },
+            "Largura inválida: 641 (use um número par de 16 a 7680)",
+        ),
+    ];
+
+    for (format, options, expected) in cases {
+        let error = harness
+            .services
+            .ffmpeg
+            .convert_with_options(&input_path, format, &options)
+            .await
+            .unwrap_err();
+        assert_eq!(error, expected);
+    }
+    assert!(harness.ffmpeg.calls().is_empty());
+}
+
+#[tokio::test]
+async fn compatible_streams_are_copied_instead_of_re_encoded() {
+    // H.264 and AAC, which mp4 holds as they are
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 64)), PROBED_MEDIA);
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let events = ConvertEvents::default();
+
+    harness
+        .services
+        .ffmpeg
+        .start_conversion(
+            "c3".to_string(),
+            &input.to_string_lossy(),
+            "mp4",
+            &ConversionOptions::default(),
+            events.clone(),
+        )
+        .await
+        .unwrap();
+
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls[0][3..7], ["-c:v", "copy", "-c:a", "copy"]);
+    assert!(!calls[0].contains(&"libx264".to_string()), "{:?}", calls[0]);
+
+    let completed = events.all().pop().unwrap();
+    assert_eq!(completed.status, "completed");
+    assert_eq!(describe(&completed.streams), ["video h264 copy -", "audio aac copy -"]);
+
+    // Asking for a different size still re-encodes the video only
+    let scaled = ConversionOptions {
+        height: Some(120),
+        ..Default::default()
+    };
+    let plan = harness
+        .services
+        .ffmpeg
+        .plan_conversion(&input.to_string_lossy(), "mp4", &scaled)
+        .await
+        .unwrap();
+    assert_eq!(describe(&plan.streams), ["video h264 encode libx264", "audio aac copy -"]);
+}
+
+#[tokio::test]
+async fn only_incompatible_streams_are_re_encoded() {
+    // HEVC, 5.1 Opus, SubRip subtitles and cover art
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any()), PROBED_HDR);
+    let input = harness.ffmpeg.dir().join("movie.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let input_path = input.to_string_lossy().to_string();
+    let ffmpeg = &harness.services.ffmpeg;
+    let defaults = ConversionOptions::default();
+
+    let plan = ffmpeg.plan_conversion(&input_path, "mp4", &defaults).await.unwrap();
+    assert_eq!(
+        describe(&plan.streams),
+        ["video hevc copy -", "audio opus copy -", "subtitle subrip encode mov_text"]
+    );
+    assert_eq!(
+        codec_args(ffmpeg, &input_path, "mp4", &defaults).await,
+        "-c:v copy -c:a copy -c:s mov_text"
+    );
+
+    let plan = ffmpeg.plan_conversion(&input_path, "webm", &defaults).await.unwrap();
+    assert_eq!(
+        describe(&plan.streams),
+        ["video hevc encode libvpx-vp9", "audio opus copy -", "subtitle subrip encode webvtt"]
+    );
+
+    let plan = ffmpeg.plan_conversion(&input_path, "flac", &defaults).await.unwrap();
+    assert_eq!(
+        describe(&plan.streams),
+        ["video hevc drop -", "audio opus encode flac", "subtitle subrip drop -"]
+    );
+
+    // A copy the output can't hold fails before ffmpeg runs
+    let copy = ConversionOptions {
+        video_codec: Some("copy".to_string()),
+        ..Default::default()
+    };
+    let error = ffmpeg.convert_with_options(&input_path, "webm", &copy).await.unwrap_err();
+    assert_eq!(error, "webm não aceita vídeo hevc sem recodificar");
+    assert!(harness.ffmpeg.calls().is_empty());
+}
@@ -0,0 +1,99 @@
+use jara_core::deep_link::{parse_deep_link, DeepLinkAction};
+
+fn media_file(dir: &tempfile::TempDir, name: &str) -> String {
+    let path = dir.path().join(name);
+    std::fs::write(&path, b"mkv").unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn encoded(path: &str) -> String {
+    url::form_urlencoded::byte_serialize(path.as_bytes()).collect()
+}
+
+#[test]
+fn download_links_decode_the_url_and_preset() {
+    assert_eq!(
+        parse_deep_link("jara://download?url=https%3A%2F%2Fyoutu.be%2FjNQXAC9IVRw%3Ft%3D5&preset=Só+áudio"),
+        Ok(DeepLinkAction::Download {
+            url: "https://youtu.be/jNQXAC9IVRw?t=5".to_string(),
+            preset: Some("Só áudio".to_string()),
+        })
+    );
+    // The action may also come as a path
+    assert_eq!(
+        parse_deep_link("jara:download?url=https://vimeo.com/1"),
+        Ok(DeepLinkAction::Download {
+            url: "https://vimeo.com/1".to_string(),
+            preset: None,
+        })
+    );
+}
+
+#[test]
+fn convert_links_need_an_existing_absolute_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = media_file(&dir, "meu vídeo.mkv");
+
+    assert_eq!(
+        parse_deep_link(&format!("jara://convert?path={}&format=MP4", encoded(&input))),
+        Ok(DeepLinkAction::Convert {
+            path: input.clone(),
+            format: "mp4".to_string(),
+        })
+    );
+    assert_eq!(
+        parse_deep_link("jara://convert?path=videos%2Fclip.mkv&format=mp4"),
+        Err("O caminho do arquivo deve ser absoluto".to_string())
+    );
+    let missing = dir.path().join("sumiu.mkv").to_string_lossy().to_string();
+    assert_eq!(
+        parse_deep_link(&format!("jara://convert?path={}&format=mp4", encoded(&missing))),
+        Err("Arquivo de entrada não encontrado".to_string())
+    );
+    for format in ["", "mp4%2F..", "matroska"] {
+        assert_eq!(
+            parse_deep_link(&format!("jara://convert?path={}&format={}", encoded(&input), format)),
+            Err(format!("Formato inválido: {}", format.replace("%2F", "/"))),
+        );
+    }
+}
+
+#[test]
+fn convert_links_never_overwrite_a_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = media_file(&dir, "clip.mkv");
+    let existing = media_file(&dir, "clip.mp4");
+
+    assert_eq!(
+        parse_deep_link(&format!("jara://convert?path={}&format=mp4", encoded(&input))),
+        Err(format!("O arquivo de saída já existe: {}", existing))
+    );
+    // Converting to the input's own format would overwrite the input
+    assert_eq!(
+        parse_deep_link(&format!("jara://convert?path={}&format=mkv", encoded(&input))),
+        Err(format!("O arquivo de saída já existe: {}", input))
+    );
+}
+
+#[test]
+fn malformed_links_are_refused() {
+    let cases = [
+        ("https://youtu.be/a", "Protocolo não suportado: https"),
+        ("jara://", "Link sem ação"),
+        ("jara://delete?path=%2Fhome", "Ação desconhecida: delete"),
+        ("jara://download", "Parâmetro obrigatório ausente: url"),
+        ("jara://download?url=file%3A%2F%2F%2Fetc%2Fpasswd", "A URL de download deve ser http ou https"),
+        ("jara://download?url=não é url", "URL de download inválida"),
+        ("jara://download?url=https://a.com&url=https://b.com", "Parâmetro repetido: url"),
+        ("jara://download?url=https://a.com&output=%2Ftmp", "Parâmetro desconhecido: output"),
+        ("jara://download?url=https://a.com&preset=..%2F..", "Preset inválido: ../.."),
+        ("jara://convert?format=mp4", "Parâmetro obrigatório ausente: path"),
+        ("jara://convert?path=%2Ftmp%2Fa.mkv", "Parâmetro obrigatório ausente: format"),
+    ];
+    for (link, error) in cases {
+        assert_eq!(parse_deep_link(link), Err(error.to_string()), "{}", link);
+    }
+
+    let long = format!("jara://download?url=https://a.com/{}", "a".repeat(5000));
+    assert_eq!(parse_deep_link(&long), Err("Link muito longo".to_string()));
+}
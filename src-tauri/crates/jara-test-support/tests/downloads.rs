@@ -0,0 +1,283 @@
+mod common;
+
+use common::{request, Harness};
+use jara_core::ytdlp::LiveOptions;
+use jara_test_support::{Rule, Scenario};
+use std::path::Path;
+
+const RECORDED_DOWNLOAD: &str = include_str!("fixtures/ytdlp-download.txt");
+const VIDEO_INFO: &str = include_str!("fixtures/ytdlp-video-info.json");
+const PROBED_MEDIA: &str = include_str!("fixtures/ffprobe-zoo.json");
+
+fn download_scenario() -> Scenario {
+    Scenario::ytdlp().field("title", "Me at the zoo").rule(
+        Rule::any()
+            .replay_stdout(RECORDED_DOWNLOAD)
+            .write_file("{after:-o}", 4096),
+    )
+}
+
+#[tokio::test]
+async fn download_reports_progress_and_completes() {
+    let harness = Harness::with_ytdlp(&download_scenario());
+    let output = harness.ytdlp.dir().join("out");
+
+    let id = harness
+        .services
+        .downloads
+        .enqueue(request("https://www.youtube.com/watch?v=jNQXAC9IVRw", &output));
+    let job = harness.wait_until_finished(&id).await;
+
+    assert_eq!(job.status, "completed");
+    assert_eq!(job.progress, 100.0);
+    assert!(output.join("Me at the zoo.mp4").exists());
+
+    let events = harness.events_for(&id);
+    let progress: Vec<f64> = events
+        .iter()
+        .filter(|p| p.status == "downloading")
+        .map(|p| p.progress)
+        .collect();
+    assert_eq!(progress, vec![0.1, 12.9, 51.8, 100.0]);
+
+    // yt-dlp has no speed estimate yet on its first line
+    let first_download = events.iter().find(|p| p.status == "downloading").unwrap();
+    assert_eq!(first_download.speed.as_deref(), Some("Unknown B/s"));
+    assert_eq!(first_download.eta.as_deref(), Some("Unknown"));
+
+    let last_download = events.iter().rev().find(|p| p.status == "downloading").unwrap();
+    assert_eq!(last_download.speed.as_deref(), Some("3.02MiB/s"));
+    assert_eq!(
+        last_download.filename.as_deref().map(Path::new),
+        Some(output.join("Me at the zoo.mp4").as_path())
+    );
+    assert_eq!(events.first().unwrap().status, "queued");
+    assert_eq!(events.last().unwrap().status, "completed");
+
+    // The fake ffprobe can't probe anything here
+    assert_eq!(job.files.len(), 1);
+    assert_eq!(Path::new(&job.files[0].path), output.join("Me at the zoo.mp4"));
+    assert_eq!(job.files[0].size, 4096);
+    assert!(job.files[0].media.is_none());
+}
+
+#[tokio::test]
+async fn merged_download_reports_the_merged_file() {
+    let output = tempfile::tempdir().unwrap();
+    let path = |name: &str| output.path().join(name).to_string_lossy().to_string();
+    let scenario = Scenario::ytdlp().rule(
+        Rule::any()
+            .stdout(&format!("[download] Destination: {}", path("Me at the zoo.f134.mp4")))
+            .stdout("[download] 100% of  520.11KiB in 00:00:00 at 2.10MiB/s")
+            .stdout(&format!("[download] Destination: {}", path("Me at the zoo.f140.m4a")))
+            .stdout("[download] 100% of  301.02KiB in 00:00:00 at 1.95MiB/s")
+            .stdout(&format!("[Merger] Merging formats into \"{}\"", path("Me at the zoo.mp4")))
+            .write_file(&path("Me at the zoo.mp4"), 2048)
+            .stdout(&format!("Deleting original file {} (pass -k to keep)", path("Me at the zoo.f134.mp4")))
+            .stdout(&format!("Deleting original file {} (pass -k to keep)", path("Me at the zoo.f140.m4a"))),
+    );
+    let ffprobe = Scenario::ffprobe().rule(Rule::any().replay_stdout(PROBED_MEDIA));
+    let harness = Harness::with_tools(&scenario, &Scenario::ffmpeg(), &ffprobe);
+
+    let id = harness
+        .services
+        .downloads
+        .enqueue(request("https://youtu.be/jNQXAC9IVRw", output.path()));
+    let job = harness.wait_until_finished(&id).await;
+
+    assert_eq!(job.status, "completed");
+    let paths: Vec<&str> = job.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec![path("Me at the zoo.mp4")]);
+    assert_eq!(job.files[0].size, 2048);
+
+    let media = job.files[0].media.as_ref().unwrap();
+    assert_eq!(media.duration, Some(19.06));
+    assert_eq!(media.video().and_then(|v| v.codec.as_deref()), Some("h264"));
+    assert_eq!(media.audio().and_then(|a| a.codec.as_deref()), Some("aac"));
+    assert_eq!(harness.ffprobe.calls_without_version()[0].last(), Some(&path("Me at the zoo.mp4")));
+
+    let completed = harness.events_for(&id).pop().unwrap();
+    assert_eq!(completed.status, "completed");
+    assert_eq!(completed.filename, Some(path("Me at the zoo.mp4")));
+    assert_eq!(completed.files.len(), 1);
+}
+
+#[tokio::test]
+async fn extracted_audio_replaces_the_downloaded_file() {
+    let output = tempfile::tempdir().unwrap();
+    let path = |name: &str| output.path().join(name).to_string_lossy().to_string();
+    let scenario = Scenario::ytdlp().rule(
+        Rule::any()
+            .stdout(&format!("[download] Destination: {}", path("Me at the zoo.webm")))
+            .write_file(&path("Me at the zoo.webm"), 1024)
+            .stdout("[download] 100% of  301.02KiB in 00:00:00 at 1.95MiB/s")
+            .stdout(&format!("[ExtractAudio] Destination: {}", path("Me at the zoo.mp3")))
+            .write_file(&path("Me at the zoo.mp3"), 512)
+            .stdout(&format!("Deleting original file {} (pass -k to keep)", path("Me at the zoo.webm"))),
+    );
+    let harness = Harness::with_ytdlp(&scenario);
+
+    let mut request = request("https://youtu.be/jNQXAC9IVRw", output.path());
+    request.audio_only = true;
+    let id = harness.services.downloads.enqueue(request);
+    let job = harness.wait_until_finished(&id).await;
+
+    // The fake never deletes the webm, but yt-dlp said it did
+    let paths: Vec<&str> = job.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(paths, vec![path("Me at the zoo.mp3")]);
+    assert_eq!(job.files[0].size, 512);
+}
+
+#[tokio::test]
+async fn download_passes_request_options_to_ytdlp() {
+    let harness = Harness::with_ytdlp(&download_scenario());
+    let output = harness.ytdlp.dir().join("out");
+    let archive = harness.ytdlp.dir().join("archive.txt");
+
+    let mut request = request("https://youtu.be/jNQXAC9IVRw", &output);
+    request.audio_only = true;
+    request.download_archive = Some(archive.to_string_lossy().to_string());
+    request.sections = vec!["*0:05-0:10".to_string()];
+    request.no_playlist = true;
+    request.max_filesize = Some(50 * 1024 * 1024);
+
+    let id = harness.services.downloads.enqueue(request);
+    harness.wait_until_finished(&id).await;
+
+    let calls = harness.ytdlp.calls_without_version();
+    assert_eq!(calls.len(), 1);
+    let args = &calls[0];
+    let after = |flag: &str| {
+        let i = args.iter().position(|a| a == flag).unwrap();
+        args[i + 1].clone()
+    };
+    assert_eq!(after("-o"), format!("{}/%(title)s.%(ext)s", output.to_string_lossy()));
+    assert_eq!(after("--audio-format"), "mp3");
+    assert_eq!(after("--download-archive"), archive.to_string_lossy());
+    assert_eq!(after("--download-sections"), "*0:05-0:10");
+    assert_eq!(after("--max-filesize"), "52428800");
+    assert!(args.contains(&"--no-playlist".to_string()), "{:?}", args);
+    assert_eq!(args[args.len() - 2..], ["--", "https://youtu.be/jNQXAC9IVRw"]);
+}
+
+#[tokio::test]
+async fn failed_download_reports_ytdlp_error() {
+    let scenario =
+        Scenario::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scenarios/ytdlp-unsupported-url.json"))
+            .unwrap();
+    let harness = Harness::with_ytdlp(&scenario);
+
+    let id = harness
+        .services
+        .downloads
+        .enqueue(request("https://example.com/page", harness.ytdlp.dir()));
+    let job = harness.wait_until_finished(&id).await;
+
+    assert_eq!(job.status, "error");
+    let error = job.error.unwrap();
+    assert!(error.contains("ERROR: Unsupported URL: https://example.com/page"), "{}", error);
+    // Warnings are not errors
+    assert!(!error.contains("WARNING"), "{}", error);
+    assert_eq!(harness.events_for(&id).last().unwrap().status, "error");
+}
+
+#[tokio::test]
+async fn cancel_stops_running_download() {
+    let scenario = Scenario::ytdlp().rule(
+        Rule::any()
+            .stdout("[download]   5.0% of   10.00MiB at  512.00KiB/s ETA 00:19")
+            .hang(),
+    );
+    let harness = Harness::with_ytdlp(&scenario);
+
+    let id = harness
+        .services
+        .downloads
+        .enqueue(request("https://youtu.be/jNQXAC9IVRw", harness.ytdlp.dir()));
+    // Cancelled mid-download, after yt-dlp reported some progress
+    harness.wait_for_progress(&id, 5.0).await;
+
+    harness.services.downloads.cancel(&id).await.unwrap();
+    let job = harness.wait_until_finished(&id).await;
+
+    assert_eq!(job.status, "cancelled");
+    assert!(job.error.is_none());
+}
+
+#[tokio::test]
+async fn stopped_recording_completes() {
+    let scenario = Scenario::ytdlp().rule(
+        Rule::any()
+            .stdout("[download]   5.0% of   10.00MiB at  512.00KiB/s ETA 00:19")
+            .hang(),
+    );
+    let harness = Harness::with_ytdlp(&scenario);
+    let mut recording = request("https://youtu.be/live", harness.ytdlp.dir());
+    recording.live = Some(LiveOptions::default());
+
+    let id = harness.services.downloads.enqueue(recording);
+    harness.wait_for_progress(&id, 5.0).await;
+
+    // The interrupted process exits with a failure, but what it recorded is kept
+    harness.services.ytdlp().await.unwrap().stop_recording(&id).await.unwrap();
+    let job = harness.wait_until_finished(&id).await;
+
+    assert_eq!(job.status, "completed");
+    assert!(job.error.is_none());
+}
+
+#[tokio::test]
+async fn cancel_drops_queued_job_before_it_runs() {
+    let scenario = Scenario::ytdlp().rule(Rule::any().hang());
+    let harness = Harness::with_ytdlp(&scenario);
+    let downloads = &harness.services.downloads;
+
+    // Three downloads run at a time, so the fourth waits
+    let running: Vec<String> = (0..3)
+        .map(|i| downloads.enqueue(request(&format!("https://youtu.be/{}", i), harness.ytdlp.dir())))
+        .collect();
+    for id in &running {
+        harness.wait_for_status(id, &["downloading"]).await;
+    }
+    let queued = downloads.enqueue(request("https://youtu.be/queued", harness.ytdlp.dir()));
+    assert_eq!(downloads.get_job(&queued).unwrap().status, "queued");
+
+    downloads.cancel(&queued).await.unwrap();
+    assert_eq!(harness.wait_until_finished(&queued).await.status, "cancelled");
+
+    for id in &running {
+        downloads.cancel(id).await.unwrap();
+        harness.wait_until_finished(id).await;
+    }
+    let urls: Vec<String> = harness
+        .ytdlp
+        .calls_without_version()
+        .iter()
+        .map(|args| args.last().unwrap().clone())
+        .collect();
+    assert!(!urls.contains(&"https://youtu.be/queued".to_string()), "{:?}", urls);
+}
+
+#[tokio::test]
+async fn video_info_parses_dump_json() {
+    let scenario = Scenario::ytdlp().rule(Rule::on(&["--dump-json"]).stdout(VIDEO_INFO.trim()));
+    let harness = Harness::with_ytdlp(&scenario);
+
+    let info = harness
+        .services
+        .ytdlp()
+        .await
+        .unwrap()
+        .get_video_info("https://youtu.be/jNQXAC9IVRw")
+        .await
+        .unwrap();
+
+    assert_eq!(info.title, "Me at the zoo");
+    assert_eq!(info.duration, Some(19.0));
+    assert!(!info.is_live);
+    let ids: Vec<&str> = info.formats.iter().map(|f| f.format_id.as_str()).collect();
+    assert_eq!(ids, vec!["139", "18"]);
+    assert_eq!(info.thumbnails.len(), 1);
+    let calls = harness.ytdlp.calls_without_version();
+    assert_eq!(calls[0][calls[0].len() - 2..], ["--", "https://youtu.be/jNQXAC9IVRw"]);
+}
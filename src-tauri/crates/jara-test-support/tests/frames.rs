@@ -0,0 +1,128 @@
+mod common;
+
+use common::{after, probed_harness, Harness};
+use jara_core::frames::{ContactSheetOptions, FrameOptions};
+use jara_test_support::{Rule, Scenario};
+use std::path::PathBuf;
+
+// 19.06s, H.264 320x240
+const PROBED_MEDIA: &str = include_str!("fixtures/ffprobe-zoo.json");
+
+// ffmpeg "extracts" a plain red 320x240 frame from clip.mp4
+fn frame_harness() -> (Harness, String) {
+    let harness = probed_harness(&Scenario::ffmpeg(), PROBED_MEDIA);
+
+    let frame = harness.ffmpeg.dir().join("red.png");
+    image::RgbImage::from_pixel(320, 240, image::Rgb([200, 30, 30]))
+        .save(&frame)
+        .unwrap();
+    harness
+        .ffmpeg
+        .set_scenario(&Scenario::ffmpeg().rule(Rule::any().copy_file(&frame, "{last}")));
+
+    let input = harness.ffmpeg.dir().join("clip.mp4");
+    std::fs::write(&input, b"mp4").unwrap();
+    (harness, input.to_string_lossy().to_string())
+}
+
+#[tokio::test]
+async fn single_frame_is_taken_at_the_timestamp() {
+    let (harness, input) = frame_harness();
+
+    let output = harness
+        .services
+        .ffmpeg
+        .extract_frame(&input, 5.5, &FrameOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(PathBuf::from(&output), harness.ffmpeg.dir().join("clip-frame-5.5s.jpg"));
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls[0], ["-ss", "5.5", "-i", &input, "-frames:v", "1", "-q:v", "2", "-y", &output]);
+
+    let error = harness
+        .services
+        .ffmpeg
+        .extract_frame(&input, 30.0, &FrameOptions::default())
+        .await
+        .unwrap_err();
+    assert_eq!(error, "O vídeo não tem quadro em 30s");
+    assert_eq!(harness.ffmpeg.calls().len(), 1);
+}
+
+#[tokio::test]
+async fn frames_are_spread_evenly_over_the_video() {
+    let (harness, input) = frame_harness();
+    let options = FrameOptions {
+        format: Some("png".to_string()),
+        width: Some(160),
+    };
+
+    let outputs = harness.services.ffmpeg.extract_frames(&input, 3, &options).await.unwrap();
+
+    let names: Vec<&str> = outputs.iter().map(|o| o.rsplit('/').next().unwrap()).collect();
+    assert_eq!(names, ["clip-frame-1.png", "clip-frame-2.png", "clip-frame-3.png"]);
+    let calls = harness.ffmpeg.calls();
+    let times: Vec<f64> = calls.iter().map(|c| after(c, "-ss").parse().unwrap()).collect();
+    for (time, expected) in times.iter().zip([19.06 / 6.0, 19.06 / 2.0, 19.06 * 5.0 / 6.0]) {
+        assert!((time - expected).abs() < 0.001, "{:?}", times);
+    }
+    assert_eq!(after(&calls[0], "-vf"), "scale=160:-2");
+}
+
+#[tokio::test]
+async fn contact_sheet_tiles_frames_with_their_times() {
+    let (harness, input) = frame_harness();
+    let options = ContactSheetOptions {
+        columns: Some(2),
+        rows: Some(2),
+        format: Some("png".to_string()),
+        ..ContactSheetOptions::default()
+    };
+
+    let output = harness.services.ffmpeg.contact_sheet(&input, &options).await.unwrap();
+
+    assert_eq!(PathBuf::from(&output), harness.ffmpeg.dir().join("clip-sheet.png"));
+    let sheet = image::open(&output).unwrap().to_rgb8();
+    // Two 320x240 tiles each way, 4px apart and from the edges
+    assert_eq!(sheet.dimensions(), (652, 492));
+    assert_eq!(sheet.get_pixel(0, 0).0, [17, 17, 17]);
+    assert_eq!(sheet.get_pixel(10, 10).0, [200, 30, 30]);
+    // The time sits on a black box in the bottom right corner of each tile
+    assert_eq!(sheet.get_pixel(4 + 319, 4 + 239).0, [0, 0, 0]);
+    assert_eq!(sheet.get_pixel(328 + 319, 248 + 239).0, [0, 0, 0]);
+
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 4);
+    assert!(calls.iter().all(|c| after(c, "-vf") == "scale=320:-2"));
+    // Only the sheet is left behind
+    let leftovers = std::fs::read_dir(harness.ffmpeg.dir())
+        .unwrap()
+        .filter(|e| e.as_ref().unwrap().file_name().to_string_lossy().starts_with(".clip"))
+        .count();
+    assert_eq!(leftovers, 0);
+}
+
+#[tokio::test]
+async fn invalid_frame_options_are_refused() {
+    let (harness, input) = frame_harness();
+    let ffmpeg = &harness.services.ffmpeg;
+
+    let options = FrameOptions {
+        format: Some("gif".to_string()),
+        ..FrameOptions::default()
+    };
+    let error = ffmpeg.extract_frame(&input, 1.0, &options).await.unwrap_err();
+    assert_eq!(error, "Formato de imagem inválido: gif (use jpg, png, webp)");
+
+    let error = ffmpeg.extract_frames(&input, 0, &FrameOptions::default()).await.unwrap_err();
+    assert_eq!(error, "Número de quadros inválido: 0 (use de 1 a 100)");
+
+    let options = ContactSheetOptions {
+        columns: Some(12),
+        ..ContactSheetOptions::default()
+    };
+    let error = ffmpeg.contact_sheet(&input, &options).await.unwrap_err();
+    assert_eq!(error, "Grade inválida: 12x4 (use até 10 colunas e 10 linhas)");
+    assert!(harness.ffmpeg.calls().is_empty());
+}
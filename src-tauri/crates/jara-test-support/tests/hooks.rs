@@ -0,0 +1,204 @@
+mod common;
+
+use common::{request, Harness, FAKE};
+use jara_core::hooks::PostAction;
+use jara_test_support::{FakeTool, Rule, Scenario};
+
+fn download_scenario() -> Scenario {
+    Scenario::ytdlp().field("title", "Me at the zoo").rule(
+        Rule::any()
+            .stdout("[download] Destination: {after:-o}")
+            .stdout("[download] 100.0% of  770.24KiB at    3.02MiB/s ETA 00:00")
+            .write_file("{after:-o}", 1024),
+    )
+}
+
+#[tokio::test]
+async fn move_then_command_sees_the_moved_file() {
+    let harness = Harness::with_ytdlp(&download_scenario());
+    let scanner = FakeTool::install(FAKE, "scan-library", &Scenario::new().rule(Rule::any().stdout("scan ok")));
+    let output = harness.ytdlp.dir().join("out");
+    let nas = harness.ytdlp.dir().join("nas");
+
+    let mut request = request("https://youtu.be/jNQXAC9IVRw", &output);
+    request.post_actions = vec![
+        PostAction::Move {
+            destination: nas.join("{ext}").to_string_lossy().to_string(),
+            rename: Some("{title} [{id}].{ext}".to_string()),
+        },
+        PostAction::Command {
+            program: scanner.path().to_string_lossy().to_string(),
+            args: vec!["--file".to_string(), "{path}".to_string(), "{title}".to_string()],
+            timeout_secs: None,
+        },
+    ];
+
+    let id = harness.services.downloads.enqueue(request);
+    let job = harness.wait_until_finished(&id).await;
+
+    let moved = nas.join("mp4").join(format!("Me at the zoo [{}].mp4", id));
+    assert_eq!(job.status, "completed");
+    assert!(moved.exists());
+    assert!(!output.join("Me at the zoo.mp4").exists());
+    assert_eq!(job.files[0].path, moved.to_string_lossy());
+
+    assert_eq!(job.hooks.len(), 2);
+    assert!(job.hooks.iter().all(|hook| hook.success), "{:?}", job.hooks);
+    assert_eq!(job.hooks[1].output.as_deref(), Some("scan ok"));
+    assert_eq!(
+        scanner.calls(),
+        vec![vec![
+            "--file".to_string(),
+            moved.to_string_lossy().to_string(),
+            "Me at the zoo".to_string()
+        ]]
+    );
+
+    // Clients only see "completed" once the actions are done
+    let statuses: Vec<String> = harness.events_for(&id).into_iter().map(|p| p.status).collect();
+    assert_eq!(statuses[statuses.len() - 2..], ["processing", "completed"]);
+    let completed = harness.events_for(&id).pop().unwrap();
+    assert_eq!(completed.filename.as_deref(), Some(moved.to_string_lossy().as_ref()));
+}
+
+#[tokio::test]
+async fn failed_action_is_recorded_and_stops_the_chain() {
+    let harness = Harness::with_ytdlp(&download_scenario());
+    let uploader = FakeTool::install(
+        FAKE,
+        "upload",
+        &Scenario::new().rule(Rule::any().stderr("sem conexão com o servidor").exit(3)),
+    );
+    let output = harness.ytdlp.dir().join("out");
+    let backup = harness.ytdlp.dir().join("backup");
+
+    let mut request = request("https://youtu.be/jNQXAC9IVRw", &output);
+    request.post_actions = vec![
+        PostAction::Command {
+            program: uploader.path().to_string_lossy().to_string(),
+            args: vec!["{path}".to_string()],
+            timeout_secs: None,
+        },
+        PostAction::Copy {
+            destination: backup.to_string_lossy().to_string(),
+            rename: None,
+        },
+    ];
+
+    let id = harness.services.downloads.enqueue(request);
+    let job = harness.wait_until_finished(&id).await;
+
+    // The download itself worked
+    assert_eq!(job.status, "completed");
+    assert!(job.error.is_none());
+    assert!(output.join("Me at the zoo.mp4").exists());
+
+    assert_eq!(job.hooks.len(), 1);
+    let hook = &job.hooks[0];
+    assert_eq!(hook.action, "command");
+    assert!(!hook.success);
+    assert!(hook.error.as_deref().unwrap().contains("sem conexão com o servidor"), "{:?}", hook);
+    assert!(!backup.exists());
+}
+
+#[tokio::test]
+async fn command_past_its_timeout_is_killed() {
+    let harness = Harness::with_ytdlp(&download_scenario());
+    let stuck = FakeTool::install(FAKE, "stuck", &Scenario::new().rule(Rule::any().hang()));
+
+    let mut request = request("https://youtu.be/jNQXAC9IVRw", harness.ytdlp.dir());
+    request.post_actions = vec![PostAction::Command {
+        program: stuck.path().to_string_lossy().to_string(),
+        args: Vec::new(),
+        timeout_secs: Some(1),
+    }];
+
+    let id = harness.services.downloads.enqueue(request);
+    let job = harness.wait_until_finished(&id).await;
+
+    assert_eq!(job.status, "completed");
+    let error = job.hooks[0].error.as_deref().unwrap();
+    assert!(error.contains("tempo limite"), "{}", error);
+}
+
+#[tokio::test]
+async fn convert_chains_through_ffmpeg() {
+    let ffmpeg = Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 512));
+    let harness = Harness::new(&download_scenario(), &ffmpeg);
+    let output = harness.ytdlp.dir().join("out");
+
+    let mut request = request("https://youtu.be/jNQXAC9IVRw", &output);
+    request.post_actions = vec![PostAction::Convert {
+        format: "mp3".to_string(),
+        keep_original: false,
+    }];
+
+    let id = harness.services.downloads.enqueue(request);
+    let job = harness.wait_until_finished(&id).await;
+
+    let converted = output.join("Me at the zoo.mp3");
+    assert!(job.hooks[0].success, "{:?}", job.hooks);
+    assert!(converted.exists());
+    assert!(!output.join("Me at the zoo.mp4").exists());
+    assert_eq!(job.files[0].path, converted.to_string_lossy());
+
+    let args = &harness.ffmpeg.calls()[0];
+    assert!(args.contains(&output.join("Me at the zoo.mp4").to_string_lossy().to_string()));
+}
+
+#[tokio::test]
+async fn normalize_replaces_the_download_in_place() {
+    let ffmpeg = Scenario::ffmpeg()
+        .rule(Rule::on(&["null"]).replay_stderr(include_str!("fixtures/ffmpeg-loudnorm-measure.txt")))
+        .rule(
+            Rule::any()
+                .write_file("{last}", 512)
+                .replay_stderr(include_str!("fixtures/ffmpeg-loudnorm-normalize.txt")),
+        );
+    let harness = Harness::new(&download_scenario(), &ffmpeg);
+    let output = harness.ytdlp.dir().join("out");
+
+    let mut request = request("https://youtu.be/jNQXAC9IVRw", &output);
+    let actions = r#"[{ "type": "normalize", "preset": "podcast" }]"#;
+    request.post_actions = serde_json::from_str(actions).unwrap();
+
+    let id = harness.services.downloads.enqueue(request);
+    let job = harness.wait_until_finished(&id).await;
+
+    let downloaded = output.join("Me at the zoo.mp4");
+    assert!(job.hooks[0].success, "{:?}", job.hooks);
+    assert_eq!(job.hooks[0].action, "normalize");
+    assert_eq!(job.hooks[0].output.as_deref(), Some(downloaded.to_string_lossy().as_ref()));
+    assert_eq!(job.files[0].path, downloaded.to_string_lossy());
+    assert_eq!(std::fs::metadata(&downloaded).unwrap().len(), 512);
+    assert!(!output.join("Me at the zoo-normalized.mp4").exists());
+
+    let calls = harness.ffmpeg.calls();
+    assert!(calls[0].contains(&"loudnorm=I=-16:TP=-1.5:LRA=11:print_format=json".to_string()));
+}
+
+#[tokio::test]
+async fn normalized_copy_is_reported_as_the_download() {
+    let ffmpeg = Scenario::ffmpeg()
+        .rule(Rule::on(&["null"]).replay_stderr(include_str!("fixtures/ffmpeg-loudnorm-measure.txt")))
+        .rule(
+            Rule::any()
+                .write_file("{last}", 512)
+                .replay_stderr(include_str!("fixtures/ffmpeg-loudnorm-normalize.txt")),
+        );
+    let harness = Harness::new(&download_scenario(), &ffmpeg);
+    let output = harness.ytdlp.dir().join("out");
+
+    let mut request = request("https://youtu.be/jNQXAC9IVRw", &output);
+    let actions = r#"[{ "type": "normalize", "preset": "podcast", "keep_original": true }]"#;
+    request.post_actions = serde_json::from_str(actions).unwrap();
+
+    let id = harness.services.downloads.enqueue(request);
+    let job = harness.wait_until_finished(&id).await;
+
+    let normalized = output.join("Me at the zoo-normalized.mp4");
+    assert!(job.hooks[0].success, "{:?}", job.hooks);
+    assert_eq!(job.files.len(), 1);
+    assert_eq!(job.files[0].path, normalized.to_string_lossy());
+    assert!(output.join("Me at the zoo.mp4").exists());
+}
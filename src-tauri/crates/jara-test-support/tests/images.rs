@@ -0,0 +1,38 @@
+use jara_core::image_convert::{ImageConverter, ImageOptions};
+
+fn image_file(dir: &tempfile::TempDir, name: &str, width: u32, height: u32) -> String {
+    let path = dir.path().join(name);
+    image::RgbImage::from_pixel(width, height, image::Rgb([200, 30, 30]))
+        .save(&path)
+        .unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn convert(input: &str, options: ImageOptions) -> (u32, u32) {
+    let output = ImageConverter::new()
+        .convert_with_options(input, "jpg", &options)
+        .unwrap();
+    image::image_dimensions(output).unwrap()
+}
+
+#[test]
+fn max_size_shrinks_but_never_enlarges() {
+    let dir = tempfile::tempdir().unwrap();
+    let fits = |max_size| ImageOptions {
+        max_size: Some(max_size),
+        square: false,
+    };
+
+    let large = image_file(&dir, "large.png", 1280, 720);
+    assert_eq!(convert(&large, fits(640)), (640, 360));
+
+    let small = image_file(&dir, "small.png", 320, 180);
+    assert_eq!(convert(&small, fits(640)), (320, 180));
+
+    // Cropped to 180x180 first, which already fits
+    let square = ImageOptions {
+        max_size: Some(640),
+        square: true,
+    };
+    assert_eq!(convert(&small, square), (180, 180));
+}
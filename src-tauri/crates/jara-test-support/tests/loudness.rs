@@ -0,0 +1,182 @@
+mod common;
+
+use common::{probed_harness, Harness};
+use jara_core::loudness::{LoudnessMeasurement, LoudnessOptions, LoudnessRequest, LoudnessTarget};
+use jara_test_support::{Rule, Scenario};
+
+// 19.06s, H.264 320x240 at 29.97 fps, AAC 44.1 kHz stereo
+const PROBED_MEDIA: &str = include_str!("fixtures/ffprobe-zoo.json");
+// loudnorm's report on a quiet recording, then on its corrected copy
+const MEASURE_LOG: &str = include_str!("fixtures/ffmpeg-loudnorm-measure.txt");
+const NORMALIZE_LOG: &str = include_str!("fixtures/ffmpeg-loudnorm-normalize.txt");
+
+// The measuring pass writes to the null muxer
+fn loudnorm_harness() -> Harness {
+    let ffmpeg = Scenario::ffmpeg()
+        .rule(Rule::on(&["null"]).replay_stderr(MEASURE_LOG))
+        .rule(Rule::any().write_file("{last}", 512).replay_stderr(NORMALIZE_LOG));
+    probed_harness(&ffmpeg, PROBED_MEDIA)
+}
+
+fn input(harness: &Harness, name: &str) -> String {
+    let path = harness.ffmpeg.dir().join(name);
+    std::fs::write(&path, b"media").unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn preset(name: &str) -> LoudnessOptions {
+    LoudnessOptions {
+        preset: Some(name.to_string()),
+        ..LoudnessOptions::default()
+    }
+}
+
+fn filter(args: &[String]) -> &str {
+    &args[args.iter().position(|a| a == "-af").unwrap() + 1]
+}
+
+#[tokio::test]
+async fn loudness_is_measured_then_corrected_to_the_preset() {
+    let harness = loudnorm_harness();
+    let clip = input(&harness, "clip.mp4");
+
+    let outcomes = harness
+        .services
+        .ffmpeg
+        .normalize_loudness(&LoudnessRequest {
+            input_paths: vec![clip.clone()],
+            options: preset("podcast"),
+        })
+        .await
+        .unwrap();
+
+    let result = outcomes[0].result.as_ref().unwrap();
+    let output = harness.ffmpeg.dir().join("clip-normalized.mp4");
+    assert_eq!(result.output, output.to_string_lossy());
+    assert!(output.exists());
+    assert_eq!(
+        result.target,
+        LoudnessTarget {
+            integrated: -16.0,
+            true_peak: -1.5,
+            lra: 11.0
+        }
+    );
+    assert_eq!(
+        result.measured,
+        LoudnessMeasurement {
+            integrated: -27.61,
+            true_peak: -4.47,
+            lra: 6.06,
+            threshold: -38.2
+        }
+    );
+    assert_eq!(result.normalized.integrated, -16.02);
+    assert!(result.linear);
+
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 2);
+    assert_eq!(filter(&calls[0]), "loudnorm=I=-16:TP=-1.5:LRA=11:print_format=json");
+    assert!(calls[0].join(" ").contains("-vn -sn -dn"), "{:?}", calls[0]);
+    assert_eq!(
+        filter(&calls[1]),
+        "loudnorm=I=-16:TP=-1.5:LRA=11:measured_I=-27.61:measured_TP=-4.47:measured_LRA=6.06:\
+         measured_thresh=-38.2:offset=0.58:linear=true:print_format=json"
+    );
+    // Only the audio goes through the filter, at the rate it had
+    let second = calls[1].join(" ");
+    assert!(second.contains("-c:v copy -c:a aac -b:a 256k -ar 44100"), "{}", second);
+}
+
+#[tokio::test]
+async fn batch_reports_each_file() {
+    let harness = loudnorm_harness();
+    let first = input(&harness, "first.m4a");
+    let missing = harness.ffmpeg.dir().join("missing.m4a").to_string_lossy().to_string();
+    let last = input(&harness, "last.mkv");
+
+    let outcomes = harness
+        .services
+        .ffmpeg
+        .normalize_loudness(&LoudnessRequest {
+            input_paths: vec![first, missing, last],
+            options: LoudnessOptions {
+                integrated: Some(-18.0),
+                ..preset("broadcast")
+            },
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(outcomes.len(), 3);
+    assert!(outcomes[0].result.is_some(), "{:?}", outcomes[0]);
+    assert_eq!(outcomes[1].error.as_deref(), Some("Arquivo de entrada não encontrado"));
+    let last = outcomes[2].result.as_ref().unwrap();
+    assert!(last.output.ends_with("last-normalized.mkv"), "{}", last.output);
+    assert_eq!(last.target.integrated, -18.0);
+    assert_eq!(last.target.lra, 20.0);
+
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 4);
+    assert!(filter(&calls[0]).starts_with("loudnorm=I=-18:TP=-1:LRA=20:"));
+}
+
+#[tokio::test]
+async fn silent_audio_is_not_normalized() {
+    let harness = loudnorm_harness();
+    let silent = MEASURE_LOG.replace("\"-27.61\"", "\"-inf\"");
+    harness.ffmpeg.set_scenario(&Scenario::ffmpeg().rule(Rule::any().replay_stderr(&silent)));
+    let clip = input(&harness, "clip.mp4");
+
+    let outcomes = harness
+        .services
+        .ffmpeg
+        .normalize_loudness(&LoudnessRequest {
+            input_paths: vec![clip],
+            options: LoudnessOptions::default(),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(
+        outcomes[0].error.as_deref(),
+        Some("O áudio está em silêncio; não há volume para normalizar")
+    );
+    assert_eq!(harness.ffmpeg.calls().len(), 1);
+}
+
+#[tokio::test]
+async fn invalid_targets_are_refused() {
+    let harness = loudnorm_harness();
+    let clip = input(&harness, "clip.mp4");
+    let normalize = |options| {
+        let request = LoudnessRequest {
+            input_paths: vec![clip.clone()],
+            options,
+        };
+        let ffmpeg = &harness.services.ffmpeg;
+        async move { ffmpeg.normalize_loudness(&request).await.unwrap_err() }
+    };
+
+    assert_eq!(
+        normalize(preset("cinema")).await,
+        "Preset de volume desconhecido: cinema (use podcast, streaming, broadcast)"
+    );
+    let options = LoudnessOptions {
+        true_peak: Some(1.0),
+        ..LoudnessOptions::default()
+    };
+    assert_eq!(normalize(options).await, "Pico máximo inválido: 1 dBTP (use de -9 a 0)");
+
+    let error = harness
+        .services
+        .ffmpeg
+        .normalize_loudness(&LoudnessRequest {
+            input_paths: Vec::new(),
+            options: LoudnessOptions::default(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(error, "Informe ao menos um arquivo");
+    assert!(harness.ffmpeg.calls().is_empty());
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::Harness;
+use jara_test_support::{Rule, Scenario};
+
+const PROBED_HDR: &str = include_str!("fixtures/ffprobe-hdr.json");
+
+#[tokio::test]
+async fn probe_media_reads_streams_chapters_and_tags() {
+    let harness = Harness::with_ffprobe(&Scenario::ffprobe().rule(Rule::any().replay_stdout(PROBED_HDR)));
+    let input = harness.ffprobe.dir().join("trailer.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+
+    let info = harness
+        .services
+        .ffmpeg
+        .probe_media(&input.to_string_lossy())
+        .await
+        .unwrap();
+
+    assert_eq!(info.container, "matroska,webm");
+    assert_eq!(info.duration, Some(151.568));
+    assert_eq!(info.bitrate, Some(15773243));
+    assert_eq!(info.size, Some(298844160));
+    assert_eq!(info.tags.get("title").map(String::as_str), Some("Trailer"));
+    assert_eq!(info.streams.len(), 4);
+
+    // The cover is a video stream too, but not the video
+    let video = info.video().unwrap();
+    assert_eq!(video.index, 0);
+    assert_eq!(video.codec.as_deref(), Some("hevc"));
+    assert_eq!((video.width, video.height), (Some(3840), Some(2160)));
+    assert!((video.fps.unwrap() - 23.976).abs() < 0.001);
+    assert_eq!(video.pixel_format.as_deref(), Some("yuv420p10le"));
+    assert_eq!(video.hdr.as_deref(), Some("hdr10"));
+    assert!(info.streams[3].attached_pic);
+    assert_eq!(info.streams[3].fps, Some(90000.0));
+
+    let audio = info.audio().unwrap();
+    assert_eq!(audio.codec.as_deref(), Some("opus"));
+    assert_eq!(audio.sample_rate, Some(48000));
+    assert_eq!(audio.channels, Some(6));
+    assert_eq!(audio.channel_layout.as_deref(), Some("5.1"));
+    assert_eq!(audio.language.as_deref(), Some("jpn"));
+    assert_eq!(audio.fps, None);
+
+    let subtitle = info.streams_of("subtitle").next().unwrap();
+    assert_eq!(subtitle.language.as_deref(), Some("por"));
+    assert_eq!(subtitle.title.as_deref(), Some("Português"));
+    assert!(!subtitle.default);
+
+    let chapters: Vec<(f64, f64, Option<&str>)> = info
+        .chapters
+        .iter()
+        .map(|c| (c.start, c.end, c.title.as_deref()))
+        .collect();
+    assert_eq!(chapters, [(0.0, 65.0, Some("Abertura")), (65.0, 151.568, Some("Parte 1"))]);
+
+    let args = &harness.ffprobe.calls()[0];
+    assert!(args.windows(2).any(|w| w == ["-print_format", "json"]), "{:?}", args);
+    assert_eq!(args.last().unwrap(), &input.to_string_lossy());
+}
+
+#[tokio::test]
+async fn probe_media_reports_ffprobe_errors() {
+    let scenario = Scenario::ffprobe().rule(
+        Rule::any()
+            .stderr("{last}: Invalid data found when processing input")
+            .exit(1),
+    );
+    let harness = Harness::with_ffprobe(&scenario);
+    let input = harness.ffprobe.dir().join("broken.mkv");
+    std::fs::write(&input, b"not a video").unwrap();
+
+    let error = harness
+        .services
+        .ffmpeg
+        .probe_media(&input.to_string_lossy())
+        .await
+        .unwrap_err();
+
+    assert!(error.contains("broken.mkv: Invalid data found"), "{}", error);
+}
+
+#[tokio::test]
+async fn probe_media_refuses_missing_files() {
+    let harness = Harness::with_ffprobe(&Scenario::ffprobe());
+    let input = harness.ffprobe.dir().join("missing.mkv");
+
+    let error = harness
+        .services
+        .ffmpeg
+        .probe_media(&input.to_string_lossy())
+        .await
+        .unwrap_err();
+
+    assert_eq!(error, "Arquivo de entrada não encontrado");
+    assert!(harness.ffprobe.calls().is_empty());
+}
@@ -0,0 +1,281 @@
+mod common;
+
+use common::Harness;
+use jara_server::api::{self, AppState};
+use jara_server::db::{self, Database, JobRecord, Limits};
+use jara_server::jobs;
+use jara_server::storage::Storage;
+use jara_test_support::Scenario;
+use reqwest::StatusCode;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const DAY: i64 = 24 * 60 * 60;
+
+struct Server {
+    harness: Harness,
+    db: Database,
+    storage: Storage,
+    base: String,
+    _dir: tempfile::TempDir,
+}
+
+impl Server {
+    async fn start(max_storage: Option<u64>) -> Self {
+        let harness = Harness::with_ytdlp(&Scenario::ytdlp());
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open(&dir.path().join("jara.db")).unwrap();
+        let storage = Storage::new(dir.path().join("downloads"), max_storage).unwrap();
+        let state = AppState {
+            services: harness.services.clone(),
+            storage: storage.clone(),
+            db: db.clone(),
+            enqueue_lock: Arc::new(Mutex::new(())),
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, api::router(state)).await });
+
+        Self {
+            harness,
+            db,
+            storage,
+            base,
+            _dir: dir,
+        }
+    }
+
+    // Returns the new user's id and token
+    fn add_user(&self, name: &str, limits: Limits) -> (i64, String) {
+        let token = self.db.add_user(name, &limits).unwrap();
+        let user = self.db.find_user_by_token(&token).unwrap().unwrap();
+        (user.id, token)
+    }
+
+    async fn download(&self, token: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/api/download", self.base))
+            .bearer_auth(token)
+            .json(&json!({ "url": "https://youtu.be/jNQXAC9IVRw" }))
+            .send()
+            .await
+            .unwrap()
+    }
+
+    // Enqueues a download that must be accepted and waits for yt-dlp to run
+    async fn accepted_download(&self, token: &str) -> String {
+        let response = self.download(token).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        let id = body["downloadId"].as_str().unwrap().to_string();
+        self.harness.wait_until_finished(&id).await;
+        id
+    }
+
+    // --max-filesize of the last yt-dlp download
+    fn last_max_filesize(&self) -> String {
+        let calls = self.harness.ytdlp.calls_without_version();
+        common::after(calls.last().unwrap(), "--max-filesize").to_string()
+    }
+}
+
+fn job(id: &str, user_id: i64, folder: PathBuf, bytes: u64, created_at: i64, finished_at: Option<i64>) -> JobRecord {
+    JobRecord {
+        id: id.to_string(),
+        user_id,
+        url: "https://youtu.be/jNQXAC9IVRw".to_string(),
+        folder,
+        status: if finished_at.is_some() { "completed" } else { "downloading" }.to_string(),
+        error: None,
+        bytes,
+        created_at,
+        finished_at,
+        deleted_at: None,
+    }
+}
+
+async fn error_of(response: reqwest::Response) -> (StatusCode, String) {
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.unwrap();
+    (status, body["error"].as_str().unwrap_or_default().to_string())
+}
+
+#[tokio::test]
+async fn bytes_since_counts_finished_sizes_and_running_reservations() {
+    let server = Server::start(None).await;
+    let (user, _) = server.add_user("ana", Limits::default());
+    let (other, _) = server.add_user("bia", Limits::default());
+    let now = db::unix_now();
+    let folder = PathBuf::from("/nao/existe");
+
+    for record in [
+        job("ontem", user, folder.clone(), 500, now - 2 * DAY, Some(now - 2 * DAY)),
+        job("hoje", user, folder.clone(), 300, now - 60, Some(now - 30)),
+        // Unfinished jobs hold the share of the quota they were given
+        job("rodando", user, folder.clone(), 200, now, None),
+        job("da-bia", other, folder.clone(), 1000, now, Some(now)),
+    ] {
+        server.db.insert_job(&record).unwrap();
+    }
+
+    assert_eq!(server.db.bytes_since(user, now - DAY).unwrap(), 500);
+    assert_eq!(server.db.active_job_count(user).unwrap(), 1);
+
+    // Finishing replaces the reservation with the real size
+    server.db.finish_job("rodando", "completed", None, 50).unwrap();
+    assert_eq!(server.db.bytes_since(user, now - DAY).unwrap(), 350);
+    assert_eq!(server.db.active_job_count(user).unwrap(), 0);
+    assert_eq!(server.db.bytes_since(other, now - DAY).unwrap(), 1000);
+}
+
+#[tokio::test]
+async fn routes_refuse_missing_and_wrong_tokens() {
+    let server = Server::start(None).await;
+    let (user, token) = server.add_user("ana", Limits::default());
+    let client = reqwest::Client::new();
+    let url = |path: &str| format!("{}{}", server.base, path);
+
+    let requests = [
+        client.get(url("/api/me")),
+        client.get(url("/api/files")),
+        client.post(url("/api/video-info")).json(&json!({ "url": "https://youtu.be/a" })),
+        client.post(url("/api/download")).json(&json!({ "url": "https://youtu.be/a" })),
+        client.get(url("/api/download/x/progress")),
+        client.get(url("/api/download/x/file")),
+    ];
+    for request in requests {
+        let wrong = request.try_clone().unwrap().bearer_auth("0".repeat(64));
+        for (request, message) in [(request, "Token obrigatório"), (wrong, "Token inválido")] {
+            assert_eq!(
+                error_of(request.send().await.unwrap()).await,
+                (StatusCode::UNAUTHORIZED, message.to_string())
+            );
+        }
+    }
+    assert!(server.harness.ytdlp.calls_without_version().is_empty());
+    assert!(server.db.user_jobs(user).unwrap().is_empty());
+
+    // Header or query string, for EventSource and download links
+    let me = client.get(url("/api/me")).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(me.status(), StatusCode::OK);
+    let me: serde_json::Value = me.json().await.unwrap();
+    assert_eq!(me["name"], "ana");
+    let me = client.get(url("/api/me")).query(&[("token", &token)]).send().await.unwrap();
+    assert_eq!(me.status(), StatusCode::OK);
+
+    // Removed users lose access
+    server.db.remove_user("ana").unwrap();
+    let me = client.get(url("/api/me")).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(me.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn daily_quota_is_reserved_for_running_downloads() {
+    let server = Server::start(None).await;
+    let limits = Limits {
+        max_bytes_per_day: Some(1000),
+        ..Limits::default()
+    };
+    let (_, token) = server.add_user("ana", limits);
+
+    let first = server.accepted_download(&token).await;
+    assert_eq!(server.last_max_filesize(), "1000");
+
+    // Nothing is left while the first download may still use all of it
+    assert_eq!(
+        error_of(server.download(&token).await).await,
+        (StatusCode::TOO_MANY_REQUESTS, "Limite diário de download atingido".to_string())
+    );
+
+    // Once it finishes only its real size counts
+    server.db.finish_job(&first, "completed", None, 400).unwrap();
+    server.accepted_download(&token).await;
+    assert_eq!(server.last_max_filesize(), "600");
+
+    let me: serde_json::Value = reqwest::Client::new()
+        .get(format!("{}/api/me", server.base))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(me["bytes_today"], 1000);
+}
+
+#[tokio::test]
+async fn concurrent_limit_counts_unfinished_jobs() {
+    let server = Server::start(None).await;
+    let limits = Limits {
+        max_concurrent: Some(1),
+        ..Limits::default()
+    };
+    let (_, token) = server.add_user("ana", limits);
+    let (_, other) = server.add_user("bia", Limits::default());
+
+    // The recorder isn't running, so the first job stays unfinished
+    let first = server.accepted_download(&token).await;
+    assert_eq!(
+        error_of(server.download(&token).await).await,
+        (StatusCode::TOO_MANY_REQUESTS, "Limite de downloads simultâneos atingido".to_string())
+    );
+    // Other users have their own count
+    server.accepted_download(&other).await;
+
+    server.db.finish_job(&first, "completed", None, 0).unwrap();
+    server.accepted_download(&token).await;
+}
+
+#[tokio::test]
+async fn storage_limit_evicts_the_oldest_folders_and_marks_their_jobs() {
+    let server = Server::start(Some(100)).await;
+    let (user, token) = server.add_user("ana", Limits::default());
+    let now = db::unix_now();
+
+    let old = server.storage.create_folder().unwrap();
+    std::fs::write(old.join("velho.mp4"), vec![0u8; 200]).unwrap();
+    server.db.insert_job(&job("velho", user, old.clone(), 200, now - 60, Some(now - 60))).unwrap();
+
+    server.accepted_download(&token).await;
+
+    assert!(!old.exists());
+    assert!(server.db.get_job("velho").unwrap().unwrap().deleted_at.is_some());
+}
+
+#[tokio::test]
+async fn sweeper_deletes_files_past_the_retention() {
+    let server = Server::start(None).await;
+    let (user, _) = server.add_user("ana", Limits::default());
+    let now = db::unix_now();
+
+    let mut folders = Vec::new();
+    for (id, finished_at) in [("antigo", now - 2 * 60 * 60), ("recente", now - 60)] {
+        let folder = server.storage.create_folder().unwrap();
+        std::fs::write(folder.join("video.mp4"), b"mp4").unwrap();
+        server
+            .db
+            .insert_job(&job(id, user, folder.clone(), 3, finished_at, Some(finished_at)))
+            .unwrap();
+        folders.push(folder);
+    }
+
+    // The first sweep runs right away
+    jobs::spawn_sweeper(
+        server.harness.services.clone(),
+        server.db.clone(),
+        Some(Duration::from_secs(60 * 60)),
+    );
+
+    let started = Instant::now();
+    while server.db.get_job("antigo").unwrap().unwrap().deleted_at.is_none() {
+        assert!(started.elapsed() < Duration::from_secs(10), "o job antigo não foi apagado");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(!folders[0].exists());
+    assert!(folders[1].exists());
+    assert!(server.db.get_job("recente").unwrap().unwrap().deleted_at.is_none());
+}
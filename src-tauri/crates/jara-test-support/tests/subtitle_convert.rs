@@ -0,0 +1,119 @@
+use jara_core::subtitle_convert::SubtitleConverter;
+use std::path::Path;
+
+const SRT: &str = "\u{feff}1\r\n\
+00:00:01,500 --> 00:00:03,000\r\n\
+Olá, <i>mundo</i>\r\n\
+\r\n\
+2\r\n\
+01:02:03,040 --> 01:02:05,000\r\n\
+Segunda linha\r\n\
+e terceira\r\n";
+
+fn write(dir: &Path, name: &str, content: &str) -> String {
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn convert(input: &str, format: &str) -> String {
+    let output = SubtitleConverter::new().convert(input, format).unwrap();
+    std::fs::read_to_string(output).unwrap()
+}
+
+#[test]
+fn srt_round_trips_through_vtt_and_ass() {
+    let dir = tempfile::tempdir().unwrap();
+    let srt = write(dir.path(), "aula.srt", SRT);
+
+    let vtt = convert(&srt, "vtt");
+    assert_eq!(
+        vtt,
+        "WEBVTT\n\n\
+         00:00:01.500 --> 00:00:03.000\nOlá, <i>mundo</i>\n\n\
+         01:02:03.040 --> 01:02:05.000\nSegunda linha\ne terceira\n\n"
+    );
+
+    // ASS keeps centiseconds only and no markup
+    let ass = convert(&srt, "ass");
+    let dialogue: Vec<&str> = ass.lines().filter(|l| l.starts_with("Dialogue:")).collect();
+    assert_eq!(
+        dialogue,
+        [
+            "Dialogue: 0,0:00:01.50,0:00:03.00,Default,,0,0,0,,Olá, mundo",
+            "Dialogue: 0,1:02:03.04,1:02:05.00,Default,,0,0,0,,Segunda linha\\Ne terceira",
+        ]
+    );
+
+    let back = write(dir.path(), "volta.vtt", &vtt);
+    assert_eq!(
+        convert(&back, "srt"),
+        "1\n00:00:01,500 --> 00:00:03,000\nOlá, mundo\n\n\
+         2\n01:02:03,040 --> 01:02:05,000\nSegunda linha\ne terceira\n\n"
+    );
+    let back = write(dir.path(), "volta.ass", &ass);
+    assert_eq!(
+        convert(&back, "srt"),
+        "1\n00:00:01,500 --> 00:00:03,000\nOlá, mundo\n\n\
+         2\n01:02:03,040 --> 01:02:05,000\nSegunda linha\ne terceira\n\n"
+    );
+}
+
+#[test]
+fn vtt_headers_cue_settings_and_karaoke_tags_are_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let vtt = write(
+        dir.path(),
+        "auto.vtt",
+        "WEBVTT\nKind: captions\nLanguage: pt\n\n\
+         NOTE gerado automaticamente\n\n\
+         STYLE\n::cue { color: yellow }\n\n\
+         intro\n00:01.000 --> 00:02.000 align:start position:0%\n\
+         oi<00:00:01.500><c> pessoal</c>\n\n\
+         00:02.000 --> 00:03.000 align:start position:0%\n\
+         oi pessoal\ntudo bem &amp; com vocês\n",
+    );
+
+    assert_eq!(
+        convert(&vtt, "srt"),
+        "1\n00:00:01,000 --> 00:00:02,000\noi pessoal\n\n\
+         2\n00:00:02,000 --> 00:00:03,000\noi pessoal\ntudo bem & com vocês\n\n"
+    );
+    // Rolling auto-captions repeat lines; the transcript keeps each once
+    assert_eq!(convert(&vtt, "txt"), "oi pessoal\ntudo bem & com vocês\n");
+}
+
+#[test]
+fn ass_dialogue_is_read_by_its_format_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let ass = write(
+        dir.path(),
+        "anime.ass",
+        "[Script Info]\nTitle: teste\n\n\
+         [Events]\n\
+         Format: Layer, Start, End, Style, Text\n\
+         Dialogue: 0,0:00:05.00,0:00:06.50,Default,{\\an8}Depois, vem\\Nisto\n\
+         Comment: 0,0:00:00.00,0:00:01.00,Default,ignorado\n\
+         Dialogue: 0,0:00:01.00,0:00:02.00,Default,Primeiro\\hsempre\n",
+    );
+
+    assert_eq!(
+        convert(&ass, "srt"),
+        "1\n00:00:01,000 --> 00:00:02,000\nPrimeiro sempre\n\n\
+         2\n00:00:05,000 --> 00:00:06,500\nDepois, vem\nisto\n\n"
+    );
+}
+
+#[test]
+fn unusable_inputs_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    let converter = SubtitleConverter::new();
+    let srt = write(dir.path(), "aula.srt", SRT);
+    let empty = write(dir.path(), "vazio.srt", "sem legendas aqui\n");
+    let sub = write(dir.path(), "filme.sub", "{0}{25}oi\n");
+
+    assert_eq!(converter.convert(&srt, "srt").unwrap_err(), "O arquivo já está no formato srt");
+    assert_eq!(converter.convert(&srt, "sub").unwrap_err(), "Formato não suportado: sub");
+    assert_eq!(converter.convert(&empty, "vtt").unwrap_err(), "Nenhuma legenda encontrada no arquivo");
+    assert_eq!(converter.convert(&sub, "srt").unwrap_err(), "Formato de legenda não suportado: sub");
+}
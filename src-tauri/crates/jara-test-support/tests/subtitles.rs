@@ -0,0 +1,221 @@
+mod common;
+
+use common::{after, describe, probed_harness, Harness};
+use jara_core::encoding::{BurnSubtitle, ConversionOptions, SubtitleFile};
+use jara_test_support::{Rule, Scenario};
+
+// HEVC, 5.1 Opus, SubRip subtitles (#2) and cover art
+const PROBED_HDR: &str = include_str!("fixtures/ffprobe-hdr.json");
+
+fn movie_harness(probed: &str) -> (Harness, String) {
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 1024)), probed);
+    let input = harness.ffmpeg.dir().join("movie.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    (harness, input.to_string_lossy().to_string())
+}
+
+fn subtitle_file(harness: &Harness, name: &str) -> String {
+    let path = harness.ffmpeg.dir().join(name);
+    std::fs::write(&path, b"1\n00:00:01,000 --> 00:00:02,000\nOi\n").unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn burn(stream: Option<u32>, file: Option<String>) -> ConversionOptions {
+    ConversionOptions {
+        burn_subtitle: Some(BurnSubtitle {
+            stream,
+            file,
+            ..BurnSubtitle::default()
+        }),
+        ..ConversionOptions::default()
+    }
+}
+
+#[tokio::test]
+async fn subtitle_files_are_added_as_tagged_tracks() {
+    let (harness, input) = movie_harness(PROBED_HDR);
+    let english = subtitle_file(&harness, "movie.en.srt");
+    let styled = subtitle_file(&harness, "movie.pt.ass");
+    let options = ConversionOptions {
+        subtitle_files: vec![
+            SubtitleFile {
+                path: english.clone(),
+                language: Some("eng".to_string()),
+                title: Some("English".to_string()),
+            },
+            SubtitleFile {
+                path: styled.clone(),
+                language: Some("por".to_string()),
+                title: None,
+            },
+        ],
+        ..ConversionOptions::default()
+    };
+
+    let plan = harness
+        .services
+        .ffmpeg
+        .plan_conversion(&input, "mp4", &options)
+        .await
+        .unwrap();
+
+    // The input's own subtitle is kept as the first subtitle track
+    let expected = format!(
+        "-i {} -y -i {} -i {} -map 0:0 -map 0:1 -map 0:2 -map 1:0 -map 2:0 \
+         -c:s:0 mov_text -c:s:1 mov_text -metadata:s:s:1 language=eng -metadata:s:s:1 title=English \
+         -c:s:2 mov_text -metadata:s:s:2 language=por -c:v copy -c:a copy",
+        input, english, styled
+    );
+    assert_eq!(plan.args[..plan.args.len() - 4].join(" "), expected);
+    assert_eq!(
+        describe(&plan.streams),
+        [
+            "video hevc copy -",
+            "audio opus copy -",
+            "subtitle subrip encode mov_text",
+            "subtitle subrip encode mov_text",
+            "subtitle ass encode mov_text"
+        ]
+    );
+
+    // Matroska holds them as they are
+    let plan = harness
+        .services
+        .ffmpeg
+        .plan_conversion(&input, "mkv", &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        ["-c:s:0", "-c:s:1", "-c:s:2"].map(|flag| after(&plan.args, flag)),
+        ["copy", "copy", "copy"]
+    );
+}
+
+#[tokio::test]
+async fn probed_subtitle_track_is_burned_with_its_style() {
+    let (harness, input) = movie_harness(PROBED_HDR);
+    let mut options = burn(Some(2), None);
+    options.width = Some(1280);
+    if let Some(burn) = options.burn_subtitle.as_mut() {
+        burn.font_size = Some(24);
+        burn.color = Some("#FFFF00".to_string());
+        burn.bold = true;
+    }
+
+    let output = harness
+        .services
+        .ffmpeg
+        .convert_with_options(&input, "mp4", &options)
+        .await
+        .unwrap();
+
+    assert!(output.ends_with("movie.mp4"), "{}", output);
+    let calls = harness.ffmpeg.calls();
+    // si counts subtitle streams only; the commas of the style are escaped
+    // for the filtergraph
+    assert_eq!(
+        after(&calls[0], "-vf"),
+        format!(
+            "scale=1280:-2,subtitles=filename={}:si=0:force_style=FontSize=24\\,PrimaryColour=&H0000FFFF\\,Bold=1",
+            input
+        )
+    );
+    assert_eq!(after(&calls[0], "-c:v"), "libx264");
+    assert!(calls[0].contains(&"-sn".to_string()), "{:?}", calls[0]);
+
+    let plan = harness
+        .services
+        .ffmpeg
+        .plan_conversion(&input, "mp4", &options)
+        .await
+        .unwrap();
+    assert_eq!(
+        describe(&plan.streams),
+        ["video hevc encode libx264", "audio opus copy -", "subtitle subrip drop -"]
+    );
+}
+
+#[tokio::test]
+async fn subtitle_file_paths_are_escaped_for_the_filter() {
+    let (harness, input) = movie_harness(PROBED_HDR);
+    let file = subtitle_file(&harness, "it's, part 1.srt");
+
+    let plan = harness
+        .services
+        .ffmpeg
+        .plan_conversion(&input, "mkv", &burn(None, Some(file)))
+        .await
+        .unwrap();
+
+    let dir = harness.ffmpeg.dir().to_string_lossy().to_string();
+    assert_eq!(
+        after(&plan.args, "-vf"),
+        format!("subtitles=filename={}/it\\\\\\'s\\, part 1.srt", dir)
+    );
+    // The input's subtitle stays when a file is burned
+    assert_eq!(after(&plan.args, "-c:s"), "copy");
+}
+
+#[tokio::test]
+async fn invalid_subtitle_options_are_refused_before_running_ffmpeg() {
+    let (harness, input) = movie_harness(PROBED_HDR);
+    let ffmpeg = &harness.services.ffmpeg;
+    let convert = |format: &'static str, options: ConversionOptions| {
+        let input = input.clone();
+        async move { ffmpeg.convert_with_options(&input, format, &options).await.unwrap_err() }
+    };
+
+    assert_eq!(
+        convert("mp4", burn(Some(1), None)).await,
+        "A faixa 1 não é uma legenda do arquivo"
+    );
+    assert_eq!(
+        convert("mp4", burn(None, None)).await,
+        "Escolha uma faixa de legenda do arquivo ou um arquivo de legenda para queimar"
+    );
+    let mut copy = burn(Some(2), None);
+    copy.video_codec = Some("copy".to_string());
+    assert_eq!(convert("mp4", copy).await, "Queimar legendas exige recodificar o vídeo");
+    let mut colored = burn(Some(2), None);
+    if let Some(burn) = colored.burn_subtitle.as_mut() {
+        burn.color = Some("amarelo".to_string());
+    }
+    assert_eq!(convert("mp4", colored).await, "Cor inválida: amarelo (use #RRGGBB)");
+
+    let with_file = |path: &str, language: Option<&str>| ConversionOptions {
+        subtitle_files: vec![SubtitleFile {
+            path: path.to_string(),
+            language: language.map(String::from),
+            title: None,
+        }],
+        ..ConversionOptions::default()
+    };
+    let srt = subtitle_file(&harness, "movie.srt");
+    assert_eq!(
+        convert("mp4", with_file("movie.sub", None)).await,
+        "Formato de legenda não suportado: movie.sub (use srt, vtt ou ass)"
+    );
+    assert_eq!(
+        convert("mp4", with_file(&srt, Some("Português"))).await,
+        "Idioma inválido: Português (use um código como por ou en)"
+    );
+    assert_eq!(
+        convert("mp3", with_file(&srt, None)).await,
+        "mp3 é um formato só de áudio; legendas não se aplicam"
+    );
+    assert!(harness.ffmpeg.calls().is_empty());
+
+    // PGS and other image subtitles can't be drawn by libass
+    let (harness, input) = movie_harness(&PROBED_HDR.replace("\"subrip\"", "\"hdmv_pgs_subtitle\""));
+    let error = harness
+        .services
+        .ffmpeg
+        .convert_with_options(&input, "mp4", &burn(Some(2), None))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error,
+        "Legendas em imagem (hdmv_pgs_subtitle) não podem ser queimadas; escolha uma legenda em texto"
+    );
+    assert!(harness.ffmpeg.calls().is_empty());
+}
@@ -0,0 +1,220 @@
+mod common;
+
+use common::{probed_harness, ConvertEvents};
+use jara_core::ffmpeg::conversion_events;
+use jara_core::trim::{parse_time, TimeRange, TrimMode, TrimRequest};
+use jara_test_support::{Rule, Scenario};
+
+const PROBED_MEDIA: &str = include_str!("fixtures/ffprobe-zoo.json");
+
+fn range(start: f64, end: Option<f64>) -> TimeRange {
+    TimeRange { start, end }
+}
+
+#[test]
+fn time_ranges_parse_clock_and_seconds() {
+    assert_eq!(parse_time("90"), Some(90.0));
+    assert_eq!(parse_time("1:30.5"), Some(90.5));
+    assert_eq!(parse_time("1:02:03"), Some(3723.0));
+    assert_eq!(parse_time("1:75"), None);
+    assert_eq!(parse_time("1.5:00"), None);
+    assert_eq!(parse_time("inf"), None);
+
+    assert_eq!(TimeRange::parse("0:30-1:45").unwrap(), range(30.0, Some(105.0)));
+    assert_eq!(TimeRange::parse("1:00:00-").unwrap(), range(3600.0, None));
+    assert_eq!(
+        TimeRange::parse("abc").unwrap_err(),
+        "Trecho inválido: abc (use início-fim, ex.: 0:30-1:45)"
+    );
+}
+
+#[tokio::test]
+async fn fast_trim_copies_streams_from_the_start_keyframe() {
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 256)), PROBED_MEDIA);
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let input_path = input.to_string_lossy().to_string();
+
+    let outputs = harness
+        .services
+        .ffmpeg
+        .trim_media(&TrimRequest {
+            input_path: input_path.clone(),
+            ranges: vec![range(2.0, Some(5.5))],
+            mode: TrimMode::Fast,
+            join: false,
+        })
+        .await
+        .unwrap();
+
+    let output = harness.ffmpeg.dir().join("clip-trim.mkv");
+    assert_eq!(outputs, [output.to_string_lossy().to_string()]);
+    assert!(output.exists());
+
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(
+        calls[0][..13],
+        ["-ss", "2", "-t", "3.5", "-i", &input_path, "-y", "-map", "0", "-c", "copy", "-avoid_negative_ts", "make_zero"]
+    );
+}
+
+#[tokio::test]
+async fn precise_trim_re_encodes_and_joins_the_ranges() {
+    let ffmpeg = Scenario::ffmpeg().rule(
+        Rule::any()
+            .write_file("{last}", 256)
+            .stdout("out_time_us=1000000")
+            .stdout("progress=end"),
+    );
+    let harness = probed_harness(&ffmpeg, PROBED_MEDIA);
+    let input = harness.ffmpeg.dir().join("clip.mp4");
+    std::fs::write(&input, b"mp4").unwrap();
+    let events = ConvertEvents::default();
+
+    let outputs = harness
+        .services
+        .ffmpeg
+        .start_trim(
+            "t1".to_string(),
+            &TrimRequest {
+                input_path: input.to_string_lossy().to_string(),
+                // The open end stops at the probed duration
+                ranges: vec![range(1.0, Some(3.0)), range(17.06, None)],
+                mode: TrimMode::Precise,
+                join: true,
+            },
+            events.clone(),
+        )
+        .await
+        .unwrap();
+
+    let output = harness.ffmpeg.dir().join("clip-trim.mp4");
+    assert_eq!(outputs, [output.to_string_lossy().to_string()]);
+    assert!(output.exists());
+
+    let calls = harness.ffmpeg.calls();
+    assert_eq!(calls.len(), 3);
+    for (call, length) in calls[..2].iter().zip(["2", "2"]) {
+        assert_eq!(call[2..4], ["-t", length]);
+        assert!(call.contains(&"libx264".to_string()), "{:?}", call);
+        assert!(!call.contains(&"copy".to_string()), "{:?}", call);
+    }
+    assert_eq!(calls[2][..5], ["-f", "concat", "-safe", "0", "-i"]);
+    assert_eq!(calls[2].last().unwrap(), &output.to_string_lossy().to_string());
+
+    // Only the joined file is left behind
+    let mut files: Vec<String> = std::fs::read_dir(harness.ffmpeg.dir())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("clip") || name.starts_with(".clip"))
+        .collect();
+    files.sort();
+    assert_eq!(files, ["clip-trim.mp4", "clip.mp4"]);
+
+    // Two cuts of 2s, then the join over the same 4s
+    let events = events.all();
+    let progress: Vec<f64> = events.iter().map(|e| e.progress).collect();
+    assert_eq!(progress, [12.5, 37.5, 62.5, 100.0]);
+    assert_eq!(events.last().unwrap().status, "completed");
+}
+
+#[tokio::test]
+async fn progress_only_reaches_callers_with_a_conversion_id() {
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 64)), PROBED_MEDIA);
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let request = TrimRequest {
+        input_path: input.to_string_lossy().to_string(),
+        ranges: vec![range(0.0, Some(4.0))],
+        mode: TrimMode::Fast,
+        join: false,
+    };
+    let events = ConvertEvents::default();
+
+    for conversion_id in [None, Some("corte".to_string())] {
+        let (id, sink) = conversion_events(conversion_id, events.clone());
+        let outputs = harness.services.ffmpeg.start_trim(id, &request, sink).await.unwrap();
+        std::fs::remove_file(&outputs[0]).unwrap();
+    }
+
+    let events = events.all();
+    assert!(!events.is_empty());
+    assert!(events.iter().all(|e| e.conversion_id == "corte"), "{:?}", events);
+    assert_eq!(events.last().unwrap().status, "completed");
+}
+
+#[tokio::test]
+async fn separate_ranges_get_numbered_files() {
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any().write_file("{last}", 64)), PROBED_MEDIA);
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+
+    let outputs = harness
+        .services
+        .ffmpeg
+        .trim_media(&TrimRequest {
+            input_path: input.to_string_lossy().to_string(),
+            ranges: vec![range(0.0, Some(4.0)), range(10.0, Some(12.0))],
+            mode: TrimMode::Fast,
+            join: false,
+        })
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = outputs.iter().map(|o| o.rsplit('/').next().unwrap()).collect();
+    assert_eq!(names, ["clip-trim-1.mkv", "clip-trim-2.mkv"]);
+}
+
+#[tokio::test]
+async fn failed_cut_removes_the_parts_already_written() {
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any()), PROBED_MEDIA);
+    let scenario = Scenario::ffmpeg()
+        .rule(Rule::on(&["10"]).stderr("Conversion failed!").exit(1))
+        .rule(Rule::any().write_file("{last}", 64));
+    harness.ffmpeg.set_scenario(&scenario);
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+
+    let error = harness
+        .services
+        .ffmpeg
+        .trim_media(&TrimRequest {
+            input_path: input.to_string_lossy().to_string(),
+            ranges: vec![range(0.0, Some(4.0)), range(10.0, Some(12.0))],
+            mode: TrimMode::Fast,
+            join: true,
+        })
+        .await
+        .unwrap_err();
+
+    assert!(error.contains("Conversion failed!"), "{}", error);
+    assert_eq!(harness.ffmpeg.calls().len(), 2);
+    assert!(!harness.ffmpeg.dir().join(".clip-trim-1.mkv").exists());
+    assert!(input.exists());
+}
+
+#[tokio::test]
+async fn ranges_outside_the_file_are_refused() {
+    let harness = probed_harness(&Scenario::ffmpeg().rule(Rule::any()), PROBED_MEDIA);
+    let input = harness.ffmpeg.dir().join("clip.mkv");
+    std::fs::write(&input, b"mkv").unwrap();
+    let trim = |ranges| TrimRequest {
+        input_path: input.to_string_lossy().to_string(),
+        ranges,
+        mode: TrimMode::Fast,
+        join: false,
+    };
+    let ffmpeg = &harness.services.ffmpeg;
+
+    let error = ffmpeg.trim_media(&trim(vec![range(5.0, Some(2.0))])).await.unwrap_err();
+    assert_eq!(error, "Trecho inválido: o fim (0:00:02) precisa vir depois do início (0:00:05)");
+
+    let error = ffmpeg.trim_media(&trim(vec![range(30.0, None)])).await.unwrap_err();
+    assert_eq!(error, "O trecho começa em 0:00:30, depois do fim do arquivo (0:00:19.060)");
+
+    let error = ffmpeg.trim_media(&trim(Vec::new())).await.unwrap_err();
+    assert_eq!(error, "Informe ao menos um trecho");
+
+    assert!(harness.ffmpeg.calls().is_empty());
+}
@@ -0,0 +1,17 @@
+use jara_core::clipboard_watch::ClipboardSource;
+
+// The clipboard is opened once and kept; opening is retried on the next poll
+// if it failed
+#[derive(Default)]
+pub struct SystemClipboard {
+    clipboard: Option<arboard::Clipboard>,
+}
+
+impl ClipboardSource for SystemClipboard {
+    fn read_text(&mut self) -> Option<String> {
+        if self.clipboard.is_none() {
+            self.clipboard = arboard::Clipboard::new().ok();
+        }
+        self.clipboard.as_mut()?.get_text().ok()
+    }
+}
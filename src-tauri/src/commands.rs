@@ -1,94 +1,309 @@
-use crate::document_convert::DocumentConverter;
-use crate::ffmpeg::FFmpeg;
-use crate::image_convert::ImageConverter;
-use crate::ytdlp::{DownloadProgress, PlaylistInfo, VideoInfo, YtDlp};
-use serde::{Deserialize, Serialize};
-use std::sync::OnceLock;
-use tauri::{Emitter, Window};
+use crate::clipboard_watch::SystemClipboard;
+use jara_core::animation::{AnimationRequest, AnimationResult};
+use jara_core::bulk_import::{ImportDefaults, ImportReport};
+use jara_core::clipboard_watch::{ClipboardWatcher, DetectedUrl, SupportedSites};
+use jara_core::concat::{ConcatRequest, ConcatResult};
+use jara_core::control_api::{self, ControlApi};
+use jara_core::deep_link::{parse_deep_link, DeepLinkAction};
+use jara_core::download_manager::Job;
+use jara_core::encoding::{ConversionOptions, ConversionPlan, ConversionPreset};
+use jara_core::ffmpeg::{conversion_events, ConvertProgress};
+use jara_core::frames::{ContactSheetOptions, FrameOptions};
+use jara_core::loudness::{LoudnessOutcome, LoudnessRequest};
+use jara_core::media_info::MediaInfo;
+use jara_core::settings::{DownloadPreset, Settings};
+use jara_core::trim::TrimRequest;
+use jara_core::ytdlp::{
+    DownloadProgress, DownloadRequest, OutputFile, PlaylistInfo, SubtitleRequest, ThumbnailRequest,
+    VideoInfo,
+};
+use jara_core::{EventSink, Services};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State, Window};
 
 use tokio::sync::Mutex as TokioMutex;
 
-static YTDLP: OnceLock<TokioMutex<YtDlp>> = OnceLock::new();
-static FFMPEG: OnceLock<FFmpeg> = OnceLock::new();
-static IMAGE_CONVERTER: OnceLock<ImageConverter> = OnceLock::new();
-static DOCUMENT_CONVERTER: OnceLock<DocumentConverter> = OnceLock::new();
+static CLIPBOARD_WATCHER: Mutex<Option<ClipboardWatcher>> = Mutex::new(None);
+static CONTROL_API: TokioMutex<Option<ControlApi>> = TokioMutex::const_new(None);
+static DEEP_LINKS: Mutex<Vec<DeepLinkRequest>> = Mutex::new(Vec::new());
 
-fn get_ytdlp() -> &'static TokioMutex<YtDlp> {
-    YTDLP.get_or_init(|| TokioMutex::new(YtDlp::new()))
-}
-
-fn get_ffmpeg() -> &'static FFmpeg {
-    FFMPEG.get_or_init(FFmpeg::new)
-}
-
-fn get_image_converter() -> &'static ImageConverter {
-    IMAGE_CONVERTER.get_or_init(ImageConverter::new)
-}
+// Forwards backend events to the webview, either to every window through the
+// AppHandle or to the one window that started a download
+pub struct AppEvents<E>(pub E);
 
-fn get_document_converter() -> &'static DocumentConverter {
-    DOCUMENT_CONVERTER.get_or_init(DocumentConverter::new)
-}
+impl<E> EventSink for AppEvents<E>
+where
+    E: Emitter<tauri::Wry> + Send + Sync,
+{
+    fn download_progress(&self, progress: DownloadProgress) {
+        let _ = self.0.emit("download-progress", &progress);
+    }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct DownloadRequest {
-    pub url: String,
-    pub format_id: Option<String>,
-    pub output_path: String,
-    pub audio_only: bool,
-    #[serde(default)]
-    pub download_subs: bool,
-    pub sub_lang: Option<String>,
+    fn convert_progress(&self, progress: ConvertProgress) {
+        let _ = self.0.emit("convert-progress", &progress);
+    }
 }
 
 #[tauri::command]
-pub async fn get_video_info(url: String) -> Result<VideoInfo, String> {
-    let mut ytdlp = get_ytdlp().lock().await;
-    ytdlp.ensure_ytdlp_exists().await?;
-    ytdlp.get_video_info(&url).await
+pub async fn get_video_info(services: State<'_, Services>, url: String) -> Result<VideoInfo, String> {
+    services.ytdlp().await?.get_video_info(&url).await
 }
 
 #[tauri::command]
-pub async fn get_playlist_info(url: String) -> Result<PlaylistInfo, String> {
-    let mut ytdlp = get_ytdlp().lock().await;
-    ytdlp.ensure_ytdlp_exists().await?;
-    ytdlp.get_playlist_info(&url).await
+pub async fn get_playlist_info(services: State<'_, Services>, url: String) -> Result<PlaylistInfo, String> {
+    services.ytdlp().await?.get_playlist_info(&url).await
 }
 
 #[tauri::command]
-pub async fn is_playlist(url: String) -> Result<bool, String> {
-    let ytdlp = get_ytdlp().lock().await;
+pub async fn is_playlist(services: State<'_, Services>, url: String) -> Result<bool, String> {
+    let ytdlp = services.ytdlp.lock().await;
     Ok(ytdlp.is_playlist(&url).await)
 }
 
 #[tauri::command]
 pub async fn start_download(
+    services: State<'_, Services>,
     window: Window,
     download_id: String,
     request: DownloadRequest,
 ) -> Result<(), String> {
-    let mut ytdlp = get_ytdlp().lock().await;
-    ytdlp.ensure_ytdlp_exists().await?;
-
-    ytdlp
-        .start_download(
-            download_id,
-            &request.url,
-            request.format_id.as_deref(),
-            &request.output_path,
-            request.audio_only,
-            request.download_subs,
-            request.sub_lang.as_deref(),
-            move |progress: DownloadProgress| {
-                let _ = window.emit("download-progress", &progress);
-            },
-        )
-        .await
+    let paths = services
+        .ytdlp()
+        .await?
+        .start_download(download_id.clone(), &request, AppEvents(window.clone()))
+        .await?;
+
+    let files = OutputFile::inspect_all(&paths, &services.ffmpeg).await;
+    AppEvents(window).download_progress(DownloadProgress::completed(&download_id, files));
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn cancel_download(services: State<'_, Services>, download_id: String) -> Result<(), String> {
+    services.downloads.cancel(&download_id).await
+}
+
+// Async so the job is spawned on the Tokio runtime
+#[tauri::command]
+pub async fn enqueue_download(services: State<'_, Services>, request: DownloadRequest) -> Result<String, String> {
+    Ok(services.downloads.enqueue(request))
+}
+
+#[tauri::command]
+pub fn list_jobs(services: State<'_, Services>) -> Vec<Job> {
+    services.downloads.list_jobs()
+}
+
+#[tauri::command]
+pub fn clear_finished_jobs(services: State<'_, Services>) {
+    services.downloads.clear_finished();
+}
+
+#[tauri::command]
+pub async fn import_urls(
+    services: State<'_, Services>,
+    input_path: String,
+    defaults: ImportDefaults,
+) -> Result<ImportReport, String> {
+    services.import_urls(&input_path, &defaults)
+}
+
+#[tauri::command]
+pub async fn stop_recording(services: State<'_, Services>, download_id: String) -> Result<(), String> {
+    let ytdlp = services.ytdlp.lock().await;
+    ytdlp.stop_recording(&download_id).await
+}
+
+#[tauri::command]
+pub async fn download_subtitles(
+    services: State<'_, Services>,
+    request: SubtitleRequest,
+) -> Result<Vec<String>, String> {
+    services.ytdlp().await?.download_subtitles(&request).await
+}
+
+#[tauri::command]
+pub async fn download_thumbnail(
+    services: State<'_, Services>,
+    request: ThumbnailRequest,
+) -> Result<String, String> {
+    services.ytdlp().await?.download_thumbnail(&request).await
+}
+
+#[tauri::command]
+pub fn load_settings(services: State<'_, Services>) -> Settings {
+    services.settings()
+}
+
+#[tauri::command]
+pub async fn save_settings(
+    app_handle: AppHandle,
+    services: State<'_, Services>,
+    mut settings: Settings,
+) -> Result<Settings, String> {
+    if settings.api.enabled && settings.api.token.is_none() {
+        settings.api.token = Some(control_api::generate_token()?);
+    }
+
+    services.save_settings(settings.clone())?;
+
+    set_clipboard_watcher(app_handle, settings.clipboard_watcher);
+    apply_control_api(&services).await?;
+    Ok(settings)
+}
+
+// Starts, restarts or stops the control API to match the saved settings
+pub async fn apply_control_api(services: &Services) -> Result<(), String> {
+    let api_settings = services.settings().api;
+    let mut api = CONTROL_API.lock().await;
+
+    let wanted = match (api_settings.enabled, api_settings.token) {
+        (true, Some(token)) => Some((api_settings.port, token)),
+        _ => None,
+    };
+    let running = api.as_ref().map(|a| (a.port(), a.token().to_string()));
+    if running == wanted {
+        return Ok(());
+    }
+
+    // Dropping the running server shuts it down
+    *api = None;
+
+    if let Some((port, token)) = wanted {
+        *api = Some(ControlApi::start(port, token, services.clone()).await?);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_presets(services: State<'_, Services>) -> Vec<DownloadPreset> {
+    services.list_presets()
+}
+
+// Queue a URL with a named preset, or the default one
+#[tauri::command]
+pub async fn enqueue_url(
+    services: State<'_, Services>,
+    url: String,
+    preset: Option<String>,
+) -> Result<String, String> {
+    services.enqueue_url(&url, preset.as_deref())
+}
+
+#[tauri::command]
+pub fn list_conversion_presets(services: State<'_, Services>) -> Vec<ConversionPreset> {
+    services.list_conversion_presets()
+}
+
+// Starts or stops the clipboard watcher; must run inside the async runtime
+pub fn set_clipboard_watcher(app_handle: AppHandle, enabled: bool) {
+    let mut watcher = CLIPBOARD_WATCHER.lock().unwrap();
+
+    if !enabled {
+        *watcher = None;
+        return;
+    }
+    if watcher.is_some() {
+        return;
+    }
+
+    let sites = SupportedSites::new(app_handle.state::<Services>().ytdlp.clone());
+    *watcher = Some(ClipboardWatcher::start(
+        Box::new(SystemClipboard::default()),
+        sites,
+        Duration::from_secs(1),
+        move |detected: DetectedUrl| {
+            let _ = app_handle.emit("url-detected", &detected);
+
+            // While the window has focus App.tsx asks instead of the notification
+            let focused = app_handle
+                .get_webview_window("main")
+                .and_then(|w| w.is_focused().ok())
+                .unwrap_or(false);
+            if !focused {
+                notify_detected(app_handle.clone(), detected);
+            }
+        },
+    ));
+}
+
+// Offers a "Baixar" button that queues the link with the default preset.
+// Only freedesktop notifications report which button was clicked
+#[cfg(all(unix, not(target_os = "macos")))]
+fn notify_detected(app_handle: AppHandle, detected: DetectedUrl) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let shown = notify_rust::Notification::new()
+            .summary("Link detectado")
+            .body(&detected.url)
+            .action("enqueue", "Baixar")
+            .show();
+        let Ok(notification) = shown else {
+            return;
+        };
+
+        notification.wait_for_action(|action| {
+            if action != "enqueue" {
+                return;
+            }
+            if let Err(e) = app_handle.state::<Services>().enqueue_url(&detected.url, None) {
+                use tauri_plugin_notification::NotificationExt;
+                let _ = app_handle
+                    .notification()
+                    .builder()
+                    .title("Falha ao adicionar download")
+                    .body(e)
+                    .show();
+            }
+        });
+    });
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn notify_detected(app_handle: AppHandle, detected: DetectedUrl) {
+    use tauri_plugin_notification::NotificationExt;
+    let _ = app_handle
+        .notification()
+        .builder()
+        .title("Link detectado")
+        .body(format!("{} - abra o Jara para baixar", detected.url))
+        .show();
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeepLinkRequest {
+    link: String,
+    action: Option<DeepLinkAction>,
+    error: Option<String>,
+}
+
+// Handles a jara:// link from the command line, the OS or a second instance.
+// Any web page can open one, so nothing runs yet: the parsed link waits until
+// the webview takes it with take_deep_links, and run_deep_link runs it once
+// the user confirms. Links that arrive before the webview listens still wait
+pub fn open_deep_link(app_handle: AppHandle, link: String) {
+    let (action, error) = match parse_deep_link(&link) {
+        Ok(action) => (Some(action), None),
+        Err(e) => (None, Some(e)),
+    };
+    DEEP_LINKS.lock().unwrap().push(DeepLinkRequest { link, action, error });
+    let _ = app_handle.emit("deep-link", ());
 }
 
 #[tauri::command]
-pub async fn cancel_download(download_id: String) -> Result<(), String> {
-    let ytdlp = get_ytdlp().lock().await;
-    ytdlp.cancel_download(&download_id).await
+pub fn take_deep_links() -> Vec<DeepLinkRequest> {
+    std::mem::take(&mut *DEEP_LINKS.lock().unwrap())
+}
+
+// Runs a link the user confirmed. It is parsed again, so the checks still
+// hold. Returns the queued job for downloads, the output file for conversions
+#[tauri::command]
+pub async fn run_deep_link(services: State<'_, Services>, link: String) -> Result<String, String> {
+    match parse_deep_link(&link)? {
+        DeepLinkAction::Download { url, preset } => services.enqueue_url(&url, preset.as_deref()),
+        DeepLinkAction::Convert { path, format } => services.convert_by_extension(&path, &format).await,
+    }
 }
 
 #[tauri::command]
@@ -97,18 +312,166 @@ pub async fn select_directory() -> Result<Option<String>, String> {
 }
 
 #[tauri::command]
-pub async fn convert_file(input_path: String, output_format: String) -> Result<String, String> {
-    get_ffmpeg().convert(&input_path, &output_format).await
+pub async fn probe_media(services: State<'_, Services>, input_path: String) -> Result<MediaInfo, String> {
+    services.ffmpeg.probe_media(&input_path).await
+}
+
+// With a conversion_id the webview gets "convert-progress" events and can
+// cancel the conversion
+#[tauri::command]
+pub async fn convert_file(
+    services: State<'_, Services>,
+    window: Window,
+    input_path: String,
+    output_format: String,
+    conversion_id: Option<String>,
+    options: Option<ConversionOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let (id, events) = conversion_events(conversion_id, AppEvents(window));
+    services
+        .ffmpeg
+        .start_conversion(id, &input_path, &output_format, &options, events)
+        .await
+}
+
+// Dry run: the ffmpeg arguments convert_file would use and which streams it
+// copies or re-encodes
+#[tauri::command]
+pub async fn preview_conversion(
+    services: State<'_, Services>,
+    input_path: String,
+    output_format: String,
+    options: Option<ConversionOptions>,
+) -> Result<ConversionPlan, String> {
+    services
+        .ffmpeg
+        .plan_conversion(&input_path, &output_format, &options.unwrap_or_default())
+        .await
+}
+
+// Progress and cancelling work like convert_file
+#[tauri::command]
+pub async fn trim_media(
+    services: State<'_, Services>,
+    window: Window,
+    request: TrimRequest,
+    conversion_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let (id, events) = conversion_events(conversion_id, AppEvents(window));
+    services.ffmpeg.start_trim(id, &request, events).await
+}
+
+#[tauri::command]
+pub async fn concat_media(
+    services: State<'_, Services>,
+    window: Window,
+    request: ConcatRequest,
+    conversion_id: Option<String>,
+) -> Result<ConcatResult, String> {
+    let (id, events) = conversion_events(conversion_id, AppEvents(window));
+    services.ffmpeg.start_concat(id, &request, events).await
+}
+
+#[tauri::command]
+pub async fn video_to_animation(
+    services: State<'_, Services>,
+    window: Window,
+    request: AnimationRequest,
+    conversion_id: Option<String>,
+) -> Result<AnimationResult, String> {
+    let (id, events) = conversion_events(conversion_id, AppEvents(window));
+    services.ffmpeg.start_animation(id, &request, events).await
+}
+
+#[tauri::command]
+pub async fn extract_frame(
+    services: State<'_, Services>,
+    input_path: String,
+    at: f64,
+    options: Option<FrameOptions>,
+) -> Result<String, String> {
+    services
+        .ffmpeg
+        .extract_frame(&input_path, at, &options.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+pub async fn extract_frames(
+    services: State<'_, Services>,
+    input_path: String,
+    count: u32,
+    options: Option<FrameOptions>,
+) -> Result<Vec<String>, String> {
+    services
+        .ffmpeg
+        .extract_frames(&input_path, count, &options.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+pub async fn contact_sheet(
+    services: State<'_, Services>,
+    input_path: String,
+    options: Option<ContactSheetOptions>,
+) -> Result<String, String> {
+    services
+        .ffmpeg
+        .contact_sheet(&input_path, &options.unwrap_or_default())
+        .await
+}
+
+#[tauri::command]
+pub async fn normalize_loudness(
+    services: State<'_, Services>,
+    window: Window,
+    request: LoudnessRequest,
+    conversion_id: Option<String>,
+) -> Result<Vec<LoudnessOutcome>, String> {
+    let (id, events) = conversion_events(conversion_id, AppEvents(window));
+    services.ffmpeg.start_normalize(id, &request, events).await
+}
+
+#[tauri::command]
+pub async fn cancel_conversion(services: State<'_, Services>, conversion_id: String) -> Result<(), String> {
+    services.ffmpeg.cancel_conversion(&conversion_id).await
+}
+
+#[tauri::command]
+pub fn convert_image(
+    services: State<'_, Services>,
+    input_path: String,
+    output_format: String,
+) -> Result<String, String> {
+    services.images.convert(&input_path, &output_format)
+}
+
+#[tauri::command]
+pub fn convert_document(
+    services: State<'_, Services>,
+    input_path: String,
+    output_format: String,
+) -> Result<String, String> {
+    services.documents.convert(&input_path, &output_format)
 }
 
 #[tauri::command]
-pub fn convert_image(input_path: String, output_format: String) -> Result<String, String> {
-    get_image_converter().convert(&input_path, &output_format)
+pub fn convert_subtitle(
+    services: State<'_, Services>,
+    input_path: String,
+    output_format: String,
+) -> Result<String, String> {
+    services.subtitles.convert(&input_path, &output_format)
 }
 
 #[tauri::command]
-pub fn convert_document(input_path: String, output_format: String) -> Result<String, String> {
-    get_document_converter().convert(&input_path, &output_format)
+pub fn export_comments(
+    services: State<'_, Services>,
+    input_path: String,
+    output_format: String,
+) -> Result<String, String> {
+    services.comments.export(&input_path, &output_format)
 }
 
 #[tauri::command]
@@ -1,31 +1,116 @@
+mod clipboard_watch;
 mod commands;
-mod document_convert;
-mod ffmpeg;
-mod image_convert;
-mod ytdlp;
 
 use commands::{
-    cancel_download, convert_document, convert_file, convert_image, get_playlist_info,
-    get_video_info, is_playlist, select_directory, start_download, send_notification,
+    cancel_conversion, cancel_download, clear_finished_jobs, concat_media, contact_sheet,
+    convert_document, convert_file, convert_image, convert_subtitle, download_subtitles,
+    download_thumbnail, enqueue_download, enqueue_url, export_comments, extract_frame,
+    extract_frames, get_playlist_info, get_video_info, import_urls,
+    is_playlist, list_conversion_presets, list_jobs, list_presets, load_settings,
+    normalize_loudness, preview_conversion, probe_media, run_deep_link, save_settings, select_directory,
+    send_notification, start_download, stop_recording, take_deep_links, trim_media, video_to_animation,
 };
 
+use jara_core::settings::Settings;
+use jara_core::{BinaryPaths, Services};
+use tauri::Manager;
+use tauri_plugin_deep_link::DeepLinkExt;
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        // Must be registered first. Its deep-link feature forwards jara:// links
+        // from a second launch to on_open_url in the running instance
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_fs::init())
+        .setup(|app| {
+            // Queued jobs report progress to every window
+            let services = Services::new(
+                BinaryPaths::detect(),
+                Settings::load(),
+                commands::AppEvents(app.handle().clone()),
+            );
+            app.manage(services.clone());
+
+            // The clipboard watcher is opt-in
+            if services.settings().clipboard_watcher {
+                let handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    commands::set_clipboard_watcher(handle, true);
+                });
+            }
+
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = commands::apply_control_api(&services).await {
+                    eprintln!("API de controle não iniciada: {}", e);
+                }
+            });
+
+            // Installed builds register the scheme at install time; this covers
+            // portable builds and AppImages
+            #[cfg(any(windows, target_os = "linux"))]
+            let _ = app.deep_link().register_all();
+
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    commands::open_deep_link(handle.clone(), url.to_string());
+                }
+            });
+
+            // A link that launched this instance
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                for url in urls {
+                    commands::open_deep_link(app.handle().clone(), url.to_string());
+                }
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_video_info,
             get_playlist_info,
             is_playlist,
             start_download,
             cancel_download,
+            stop_recording,
+            enqueue_download,
+            list_jobs,
+            clear_finished_jobs,
+            import_urls,
+            enqueue_url,
+            run_deep_link,
+            take_deep_links,
+            list_presets,
+            list_conversion_presets,
+            load_settings,
+            save_settings,
+            download_subtitles,
+            download_thumbnail,
             select_directory,
+            probe_media,
             convert_file,
+            preview_conversion,
+            trim_media,
+            concat_media,
+            video_to_animation,
+            extract_frame,
+            extract_frames,
+            contact_sheet,
+            normalize_loudness,
+            cancel_conversion,
             convert_image,
             convert_document,
+            convert_subtitle,
+            export_comments,
             send_notification
         ])
         .run(tauri::generate_context!())
@@ -1,10 +1,7 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod clipboard_watch;
 mod commands;
-mod document_convert;
-mod ffmpeg;
-mod image_convert;
-mod ytdlp;
 
 fn main() {
     jara_lib::run()
@@ -13,7 +13,17 @@ import { AnimatedBackground } from "./components/AnimatedBackground";
 import { GradientBackground } from "./components/GradientBackground";
 import { HomePage } from "./components/HomePage"; // Import HomePage
 import { useDownload } from "./hooks/useDownload";
-import { selectDirectory, platform, getDownloadUrl } from "./services/api";
+import {
+  selectDirectory,
+  platform,
+  getDownloadUrl,
+  onDeepLink,
+  takeDeepLinks,
+  runDeepLink,
+  onUrlDetected,
+  enqueueUrl,
+  sendNotification,
+} from "./services/api";
 import { loadSettings, saveSettings } from "./services/storage";
 import type { VideoInfo, VideoFormat, AppSettings, PlaylistInfo } from "./types";
 
@@ -42,6 +52,60 @@ function App() {
     });
   }, []);
 
+  // Any web page can open a jara:// link, so each one asks before running
+  useEffect(() => {
+    if (!platform.isTauri) return;
+
+    const review = async () => {
+      for (const request of await takeDeepLinks()) {
+        if (!request.action) {
+          await sendNotification("Link do Jara recusado", request.error ?? request.link);
+          continue;
+        }
+        const question =
+          request.action.action === "download"
+            ? `Baixar ${request.action.url}?`
+            : `Converter ${request.action.path} para ${request.action.format}?`;
+        if (!window.confirm(question)) continue;
+
+        try {
+          await runDeepLink(request.link);
+          if (request.action.action === "download") setActiveTab("queue");
+        } catch (e) {
+          await sendNotification("Falha ao abrir link do Jara", String(e));
+        }
+      }
+    };
+
+    const unlisten = onDeepLink(review);
+    // Links that arrived before the listener
+    review();
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
+  // Links copied while the window has focus only come here; the backend
+  // notifies when it doesn't
+  useEffect(() => {
+    if (!platform.isTauri) return;
+
+    const unlisten = onUrlDetected(async (detected) => {
+      if (!document.hasFocus()) return;
+      if (!window.confirm(`Link do ${detected.site} copiado. Baixar ${detected.url}?`)) return;
+
+      try {
+        await enqueueUrl(detected.url);
+        setActiveTab("queue");
+      } catch (e) {
+        await sendNotification("Falha ao adicionar download", String(e));
+      }
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
   // Save settings when they change
   useEffect(() => {
     if (settingsLoaded && settings) {
@@ -1,5 +1,6 @@
 import { useState, useCallback, useRef, useEffect } from "react";
 import { platform } from "../services/api";
+import type { ConvertProgress } from "../types";
 import { convertFile, downloadBlob } from "../services/converter";
 import { FFmpeg } from '@ffmpeg/ffmpeg';
 import { fetchFile, toBlobURL } from '@ffmpeg/util';
@@ -127,6 +128,11 @@ export function ConvertPage({ initialAction }: { initialAction?: string }) {
   const [isDragging, setIsDragging] = useState(false);
   const [isLoadingFFmpeg, setIsLoadingFFmpeg] = useState(false);
   const [conversionResult, setConversionResult] = useState<{ url: string; filename: string } | null>(null);
+  // Desktop media conversions report progress and can be cancelled
+  const [conversionId, setConversionId] = useState<string | null>(null);
+  const [conversionEta, setConversionEta] = useState<number | null>(null);
+  // Every stream is copied, nothing re-encoded
+  const [isRemux, setIsRemux] = useState(false);
   const fileInputRef = useRef<HTMLInputElement>(null);
 
   // Image tools state
@@ -953,19 +959,48 @@ export function ConvertPage({ initialAction }: { initialAction?: string }) {
         // @ts-ignore - Tauri API only available in desktop app
         const { invoke } = await import("@tauri-apps/api/core");
 
-        const progressInterval = setInterval(() => {
-          setProgress(prev => Math.min(prev + 10, 90));
-        }, 300);
-
         const command = category === "media" ? "convert_file" :
           category === "image" ? "convert_image" : "convert_document";
 
-        const result = await invoke<string>(command, {
-          inputPath: selectedFilePath,
-          outputFormat: outputFormat,
-        });
+        let result: string;
+        if (category === "media") {
+          // @ts-ignore - Tauri API only available in desktop app
+          const { listen } = await import("@tauri-apps/api/event");
+          const id = `convert-${Date.now()}`;
+          const unlisten = await listen<ConvertProgress>("convert-progress", (event) => {
+            if (event.payload.conversion_id !== id) return;
+            setProgress(event.payload.progress);
+            setConversionEta(event.payload.eta);
+            const streams = event.payload.streams;
+            setIsRemux(streams.length > 0 && streams.every(s => s.action !== "encode"));
+          });
+          setConversionId(id);
+          try {
+            result = await invoke<string>(command, {
+              inputPath: selectedFilePath,
+              outputFormat: outputFormat,
+              conversionId: id,
+            });
+          } finally {
+            unlisten();
+            setConversionId(null);
+            setConversionEta(null);
+            setIsRemux(false);
+          }
+        } else {
+          const progressInterval = setInterval(() => {
+            setProgress(prev => Math.min(prev + 10, 90));
+          }, 300);
+          try {
+            result = await invoke<string>(command, {
+              inputPath: selectedFilePath,
+              outputFormat: outputFormat,
+            });
+          } finally {
+            clearInterval(progressInterval);
+          }
+        }
 
-        clearInterval(progressInterval);
         setProgress(100);
         setSuccess(`Arquivo convertido: ${result.split(/[/\\]/).pop()}`);
       } else if (selectedFile) {
@@ -995,6 +1030,13 @@ export function ConvertPage({ initialAction }: { initialAction?: string }) {
     }
   };
 
+  const handleCancelConversion = async () => {
+    if (!conversionId) return;
+    // @ts-ignore - Tauri API only available in desktop app
+    const { invoke } = await import("@tauri-apps/api/core");
+    await invoke("cancel_conversion", { conversionId }).catch(() => {});
+  };
+
   const handleClear = () => {
     setSelectedFile(null);
     setSelectedFilePath(null);
@@ -2968,8 +3010,17 @@ export function ConvertPage({ initialAction }: { initialAction?: string }) {
                     />
                   </div>
                   <p className="text-xs text-gray-500 font-mono text-center tracking-wider">
-                    {isLoadingFFmpeg ? "CARREGANDO FFMPEG..." : "CONVERTENDO..."}
+                    {isLoadingFFmpeg ? "CARREGANDO FFMPEG..." : isRemux ? "REMUXANDO..." : "CONVERTENDO..."}
+                    {conversionEta !== null && ` ${Math.round(progress)}% · ${Math.ceil(conversionEta)}s RESTANTES`}
                   </p>
+                  {conversionId && (
+                    <button
+                      onClick={handleCancelConversion}
+                      className="w-full py-2 text-xs text-gray-400 hover:text-white border border-dark-700 rounded-lg transition-all"
+                    >
+                      cancelar
+                    </button>
+                  )}
                 </div>
               )}
 
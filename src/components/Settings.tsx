@@ -1,5 +1,5 @@
-import { useState } from "react";
-import { platform } from "../services/api";
+import { useState, useEffect } from "react";
+import { platform, getClipboardWatcher, setClipboardWatcher } from "../services/api";
 import type { AppSettings } from "../types";
 
 const CURRENT_VERSION = "1.2.0";
@@ -14,6 +14,24 @@ interface SettingsProps {
 export function Settings({ settings, onSettingsChange }: SettingsProps) {
   const [updateStatus, setUpdateStatus] = useState<"idle" | "checking" | "available" | "up-to-date" | "error">("idle");
   const [latestVersion, setLatestVersion] = useState<string | null>(null);
+  // Kept in the backend settings, which start and stop the watcher
+  const [clipboardWatcher, setClipboardWatcherState] = useState(false);
+
+  useEffect(() => {
+    if (platform.isTauri) {
+      getClipboardWatcher().then(setClipboardWatcherState).catch(() => {});
+    }
+  }, []);
+
+  const toggleClipboardWatcher = async () => {
+    const enabled = !clipboardWatcher;
+    try {
+      await setClipboardWatcher(enabled);
+      setClipboardWatcherState(enabled);
+    } catch (e) {
+      console.error("Failed to save clipboard watcher:", e);
+    }
+  };
 
   const checkForUpdates = async () => {
     setUpdateStatus("checking");
@@ -148,6 +166,23 @@ export function Settings({ settings, onSettingsChange }: SettingsProps) {
           </div>
         </div>
 
+        {/* Clipboard watcher - Desktop only */}
+        {platform.isTauri && (
+          <div className="p-4 flex items-center justify-between">
+            <span className="text-white text-sm">detectar links copiados</span>
+            <div
+              className={`relative w-10 h-5 rounded-full transition-colors cursor-pointer ${clipboardWatcher ? "bg-white" : "bg-dark-600"
+                }`}
+              onClick={toggleClipboardWatcher}
+            >
+              <div
+                className={`absolute top-0.5 w-4 h-4 rounded-full transition-transform ${clipboardWatcher ? "translate-x-5 bg-black" : "translate-x-0.5 bg-gray-500"
+                  }`}
+              />
+            </div>
+          </div>
+        )}
+
         {/* Animated Background */}
         <div className="p-4 flex items-center justify-between">
           <span className="text-white text-sm">fundo animado</span>
@@ -1,6 +1,36 @@
 import { useState, useEffect, useCallback, useRef } from "react";
-import { getVideoInfo as fetchVideoInfo, getPlaylistInfo as fetchPlaylistInfo, isPlaylist as checkIsPlaylist, startDownload as apiStartDownload, cancelDownload as apiCancelDownload, sendNotification, platform } from "../services/api";
-import type { VideoInfo, PlaylistInfo, DownloadItem, DownloadProgress } from "../types";
+import { getVideoInfo as fetchVideoInfo, getPlaylistInfo as fetchPlaylistInfo, isPlaylist as checkIsPlaylist, startDownload as apiStartDownload, cancelDownload as apiCancelDownload, listJobs, sendNotification, platform } from "../services/api";
+import type { VideoInfo, PlaylistInfo, DownloadItem, DownloadProgress, Job } from "../types";
+
+// The backend's own statuses, in the queue's terms
+function itemStatus(status: Job["status"]): DownloadItem["status"] {
+  switch (status) {
+    case "queued":
+    case "waiting":
+      return "pending";
+    case "recording":
+      return "downloading";
+    default:
+      return status;
+  }
+}
+
+function jobToItem(job: Job): DownloadItem {
+  return {
+    id: job.id,
+    url: job.request.url,
+    title: job.request.url,
+    thumbnail: null,
+    format_id: job.request.format_id,
+    audio_only: job.request.audio_only,
+    output_path: job.request.output_path,
+    progress: job.progress,
+    speed: null,
+    eta: null,
+    status: itemStatus(job.status),
+    error: job.error ?? undefined,
+  };
+}
 
 export function useDownload() {
   const [downloads, setDownloads] = useState<DownloadItem[]>([]);
@@ -9,6 +39,24 @@ export function useDownload() {
   const [notificationsEnabled, setNotificationsEnabled] = useState(true);
   const lastUrlRef = useRef<string>("");
   const completedIdsRef = useRef<Set<string>>(new Set());
+  const downloadsRef = useRef<DownloadItem[]>([]);
+  // Ids already looked up in the backend queue; the UI's own downloads are never there
+  const lookedUpIdsRef = useRef<Set<string>>(new Set());
+
+  useEffect(() => {
+    downloadsRef.current = downloads;
+  }, [downloads]);
+
+  // Jobs queued by the backend (deep links, imports, the clipboard and the
+  // control API) join the list, with their current status
+  const addBackendJobs = useCallback(async () => {
+    const jobs = await listJobs();
+    setDownloads((prev) => {
+      const known = new Set(prev.map((item) => item.id));
+      const added = jobs.filter((job) => !known.has(job.id)).map(jobToItem);
+      return added.length > 0 ? [...added.reverse(), ...prev] : prev;
+    });
+  }, []);
 
   // Listen for download progress events (Tauri only - web uses polling in api.ts)
   useEffect(() => {
@@ -16,10 +64,17 @@ export function useDownload() {
 
     let unlisten: (() => void) | null = null;
 
+    addBackendJobs().catch(() => {});
+
     (async () => {
       const { listen } = await import("@tauri-apps/api/event");
       unlisten = await listen<DownloadProgress>("download-progress", (event) => {
         const progress = event.payload;
+        const id = progress.download_id;
+        if (!lookedUpIdsRef.current.has(id) && !downloadsRef.current.some((item) => item.id === id)) {
+          lookedUpIdsRef.current.add(id);
+          addBackendJobs().catch(() => {});
+        }
         setDownloads((prev) =>
           prev.map((item) =>
             item.id === progress.download_id
@@ -28,7 +83,7 @@ export function useDownload() {
                 progress: progress.progress,
                 speed: progress.speed,
                 eta: progress.eta,
-                status: progress.status as DownloadItem["status"],
+                status: itemStatus(progress.status),
               }
               : item
           )
@@ -39,7 +94,7 @@ export function useDownload() {
     return () => {
       if (unlisten) unlisten();
     };
-  }, []);
+  }, [addBackendJobs]);
 
   // Send notifications when downloads complete
   useEffect(() => {
@@ -1,5 +1,5 @@
 // API abstraction layer - works with both Tauri and Web
-import type { VideoInfo, DownloadProgress, PlaylistInfo } from "../types";
+import type { VideoInfo, DownloadProgress, PlaylistInfo, DeepLinkRequest, DetectedUrl, Job } from "../types";
 
 const IS_TAURI = typeof window !== "undefined" && "__TAURI__" in window;
 const API_BASE = import.meta.env.VITE_API_URL || "";
@@ -8,6 +8,35 @@ const API_BASE = import.meta.env.VITE_API_URL || "";
 let invoke: ((cmd: string, args?: Record<string, unknown>) => Promise<unknown>) | null = null;
 let listen: ((event: string, handler: (event: { payload: unknown }) => void) => Promise<() => void>) | null = null;
 
+const TOKEN_KEY = "jara_token";
+
+// The web server needs a user token; it is asked for once and kept in localStorage
+async function apiFetch(path: string, init: RequestInit = {}): Promise<Response> {
+  const send = () =>
+    fetch(`${API_BASE}${path}`, {
+      ...init,
+      headers: {
+        ...(init.headers as Record<string, string>),
+        Authorization: `Bearer ${localStorage.getItem(TOKEN_KEY) ?? ""}`,
+      },
+    });
+
+  let response = await send();
+  if (response.status === 401) {
+    const token = window.prompt("Token de acesso do Jara");
+    if (token) {
+      localStorage.setItem(TOKEN_KEY, token.trim());
+      response = await send();
+    }
+  }
+  return response;
+}
+
+// EventSource and download links can't send headers
+function withToken(path: string): string {
+  return `${API_BASE}${path}?token=${encodeURIComponent(localStorage.getItem(TOKEN_KEY) ?? "")}`;
+}
+
 async function loadTauri() {
   if (IS_TAURI && !invoke) {
     const core = await import("@tauri-apps/api/core");
@@ -22,7 +51,7 @@ export async function getVideoInfo(url: string): Promise<VideoInfo> {
     await loadTauri();
     return invoke!("get_video_info", { url }) as Promise<VideoInfo>;
   } else {
-    const response = await fetch(`${API_BASE}/api/video-info`, {
+    const response = await apiFetch("/api/video-info", {
       method: "POST",
       headers: { "Content-Type": "application/json" },
       body: JSON.stringify({ url }),
@@ -50,7 +79,7 @@ export async function getPlaylistInfo(url: string): Promise<PlaylistInfo> {
     await loadTauri();
     return invoke!("get_playlist_info", { url }) as Promise<PlaylistInfo>;
   } else {
-    const response = await fetch(`${API_BASE}/api/playlist-info`, {
+    const response = await apiFetch("/api/playlist-info", {
       method: "POST",
       headers: { "Content-Type": "application/json" },
       body: JSON.stringify({ url }),
@@ -100,10 +129,16 @@ export async function startDownload(
     return downloadId;
   } else {
     // Web version
-    const response = await fetch(`${API_BASE}/api/download`, {
+    const response = await apiFetch("/api/download", {
       method: "POST",
       headers: { "Content-Type": "application/json" },
-      body: JSON.stringify({ url, format_id: formatId, audio_only: audioOnly }),
+      body: JSON.stringify({
+        url,
+        format_id: formatId,
+        audio_only: audioOnly,
+        download_subs: downloadSubs,
+        sub_lang: subLang,
+      }),
     });
     
     if (!response.ok) {
@@ -113,30 +148,20 @@ export async function startDownload(
     
     const { downloadId } = await response.json();
     
-    // Poll for progress
-    const pollProgress = async () => {
-      try {
-        const res = await fetch(`${API_BASE}/api/download/${downloadId}/progress`);
-        const data = await res.json();
-        
-        onProgress({
-          download_id: downloadId,
-          status: data.status,
-          progress: data.progress,
-          speed: null,
-          eta: null,
-          filename: data.filename,
-        });
-        
-        if (data.status === "downloading" || data.status === "processing") {
-          setTimeout(pollProgress, 1000);
-        }
-      } catch {
-        // Ignore polling errors
+    // Progress is pushed by the server until the download finishes
+    const events = new EventSource(withToken(`/api/download/${downloadId}/events`));
+    events.onmessage = (event) => {
+      const progress = JSON.parse(event.data) as DownloadProgress;
+      onProgress(progress);
+      if (["completed", "error", "cancelled"].includes(progress.status)) {
+        events.close();
       }
     };
+    events.onerror = () => {
+      // The server closes the stream after the last event
+      events.close();
+    };
     
-    pollProgress();
     return downloadId;
   }
 }
@@ -146,10 +171,58 @@ export async function cancelDownload(downloadId: string): Promise<void> {
     await loadTauri();
     await invoke!("cancel_download", { downloadId });
   } else {
-    await fetch(`${API_BASE}/api/download/${downloadId}`, { method: "DELETE" });
+    await apiFetch(`/api/download/${downloadId}`, { method: "DELETE" });
   }
 }
 
+// jara:// links (desktop only). The "deep-link" event says new ones arrived;
+// they are taken once and run only after the user confirms
+export async function onDeepLink(handler: () => void): Promise<() => void> {
+  await loadTauri();
+  return listen!("deep-link", () => handler());
+}
+
+export async function takeDeepLinks(): Promise<DeepLinkRequest[]> {
+  await loadTauri();
+  return invoke!("take_deep_links") as Promise<DeepLinkRequest[]>;
+}
+
+export async function runDeepLink(link: string): Promise<string> {
+  await loadTauri();
+  return invoke!("run_deep_link", { link }) as Promise<string>;
+}
+
+// Queues a URL with a backend preset, or the default one (desktop only)
+export async function enqueueUrl(url: string, preset?: string): Promise<string> {
+  await loadTauri();
+  return invoke!("enqueue_url", { url, preset: preset ?? null }) as Promise<string>;
+}
+
+// Jobs in the backend queue, including the ones started outside the UI
+export async function listJobs(): Promise<Job[]> {
+  await loadTauri();
+  return invoke!("list_jobs") as Promise<Job[]>;
+}
+
+// Clipboard watcher (desktop only). The backend emits "url-detected" for each
+// supported link copied; its switch lives in the backend settings
+export async function onUrlDetected(handler: (detected: DetectedUrl) => void): Promise<() => void> {
+  await loadTauri();
+  return listen!("url-detected", (event) => handler(event.payload as DetectedUrl));
+}
+
+export async function getClipboardWatcher(): Promise<boolean> {
+  await loadTauri();
+  const settings = (await invoke!("load_settings")) as { clipboard_watcher: boolean };
+  return settings.clipboard_watcher;
+}
+
+export async function setClipboardWatcher(enabled: boolean): Promise<void> {
+  await loadTauri();
+  const settings = (await invoke!("load_settings")) as Record<string, unknown>;
+  await invoke!("save_settings", { settings: { ...settings, clipboard_watcher: enabled } });
+}
+
 export async function convertFile(
   inputPath: string,
   outputFormat: string,
@@ -194,7 +267,7 @@ export async function selectFile(filters?: { name: string; extensions: string[]
 }
 
 export function getDownloadUrl(downloadId: string): string {
-  return `${API_BASE}/api/download/${downloadId}/file`;
+  return withToken(`/api/download/${downloadId}/file`);
 }
 
 export const platform = {
@@ -12,6 +12,14 @@ export interface VideoFormat {
   tbr: number | null;
 }
 
+export interface Thumbnail {
+  id: string;
+  url: string;
+  width: number | null;
+  height: number | null;
+  preference: number | null;
+}
+
 export interface VideoInfo {
   id: string;
   title: string;
@@ -21,15 +29,234 @@ export interface VideoInfo {
   uploader: string | null;
   view_count: number | null;
   formats: VideoFormat[];
+  thumbnails: Thumbnail[];
+  is_live: boolean;
+  live_status: "is_live" | "is_upcoming" | "was_live" | "post_live" | "not_live" | null;
+  release_timestamp: number | null;
 }
 
 export interface DownloadProgress {
   download_id: string;
-  status: "downloading" | "processing" | "completed" | "error" | "cancelled";
+  status: "waiting" | "downloading" | "recording" | "processing" | "completed" | "error" | "cancelled";
   progress: number;
   speed: string | null;
   eta: string | null;
   filename: string | null;
+  elapsed: number | null;
+  downloaded_bytes: number | null;
+  files: OutputFile[];
+}
+
+export interface StreamInfo {
+  index: number;
+  kind: "video" | "audio" | "subtitle" | "data" | "attachment";
+  codec: string | null;
+  codec_name: string | null;
+  profile: string | null;
+  bitrate: number | null;
+  duration: number | null;
+  language: string | null;
+  title: string | null;
+  default: boolean;
+  attached_pic: boolean;
+  width: number | null;
+  height: number | null;
+  fps: number | null;
+  pixel_format: string | null;
+  color_transfer: string | null;
+  color_primaries: string | null;
+  hdr: "hdr10" | "hlg" | "dolby_vision" | null;
+  sample_rate: number | null;
+  channels: number | null;
+  channel_layout: string | null;
+  tags: Record<string, string>;
+}
+
+export interface ChapterInfo {
+  start: number;
+  end: number;
+  title: string | null;
+}
+
+export interface MediaInfo {
+  container: string;
+  container_name: string | null;
+  duration: number | null;
+  bitrate: number | null;
+  size: number | null;
+  streams: StreamInfo[];
+  chapters: ChapterInfo[];
+  tags: Record<string, string>;
+}
+
+export interface ConvertProgress {
+  conversion_id: string;
+  status: "converting" | "completed";
+  progress: number;
+  fps: number | null;
+  speed: number | null;
+  eta: number | null;
+  output: string | null;
+  streams: StreamPlan[];
+}
+
+export interface StreamPlan {
+  kind: "video" | "audio" | "subtitle";
+  source_codec: string | null;
+  action: "copy" | "encode" | "drop";
+  encoder: string | null;
+}
+
+export interface ConversionPlan {
+  args: string[];
+  streams: StreamPlan[];
+}
+
+export interface ConversionOptions {
+  video_codec?: "h264" | "hevc" | "vp9" | "av1" | "copy";
+  crf?: number;
+  video_bitrate?: number;
+  encoder_preset?: string;
+  width?: number;
+  height?: number;
+  fps?: number;
+  audio_codec?: "aac" | "mp3" | "opus" | "vorbis" | "flac" | "pcm" | "copy";
+  audio_bitrate?: number;
+  sample_rate?: number;
+  channels?: number;
+  subtitle_files?: SubtitleFile[];
+  burn_subtitle?: BurnSubtitle;
+}
+
+export interface SubtitleFile {
+  path: string;
+  language?: string;
+  title?: string;
+}
+
+export interface BurnSubtitle {
+  stream?: number;
+  file?: string;
+  font?: string;
+  font_size?: number;
+  color?: string;
+  outline?: number;
+  bold?: boolean;
+}
+
+export interface ConversionPreset {
+  name: string;
+  options: ConversionOptions;
+}
+
+export interface TimeRange {
+  start: number;
+  end: number | null;
+}
+
+export interface TrimRequest {
+  input_path: string;
+  ranges: TimeRange[];
+  mode?: "fast" | "precise";
+  join?: boolean;
+}
+
+export interface ConcatRequest {
+  input_paths: string[];
+  output_path?: string | null;
+  chapters?: boolean;
+}
+
+export interface ConcatResult {
+  output: string;
+  lossless: boolean;
+  reason: string | null;
+  chapters: ChapterInfo[];
+}
+
+export interface AnimationRequest {
+  input_path: string;
+  format?: "gif" | "webp";
+  start?: number | null;
+  end?: number | null;
+  fps?: number | null;
+  width?: number | null;
+  loops?: number | null;
+  max_size_kb?: number | null;
+}
+
+export interface AnimationResult {
+  output: string;
+  fps: number;
+  width: number;
+  size: number;
+  attempts: number;
+}
+
+export interface FrameOptions {
+  format?: "jpg" | "png" | "webp" | null;
+  width?: number | null;
+}
+
+export interface ContactSheetOptions {
+  columns?: number | null;
+  rows?: number | null;
+  tile_width?: number | null;
+  hide_timestamps?: boolean;
+  format?: "jpg" | "png" | "webp" | null;
+}
+
+export interface LoudnessOptions {
+  preset?: "podcast" | "streaming" | "broadcast" | null;
+  integrated?: number | null;
+  true_peak?: number | null;
+  lra?: number | null;
+}
+
+export interface LoudnessRequest {
+  input_paths: string[];
+  options?: LoudnessOptions;
+}
+
+export interface LoudnessTarget {
+  integrated: number;
+  true_peak: number;
+  lra: number;
+}
+
+export interface LoudnessMeasurement {
+  integrated: number;
+  true_peak: number;
+  lra: number;
+  threshold: number;
+}
+
+export interface LoudnessResult {
+  input: string;
+  output: string;
+  target: LoudnessTarget;
+  measured: LoudnessMeasurement;
+  normalized: LoudnessMeasurement;
+  linear: boolean;
+}
+
+export interface LoudnessOutcome {
+  input: string;
+  result: LoudnessResult | null;
+  error: string | null;
+}
+
+export interface OutputFile {
+  path: string;
+  size: number;
+  media: MediaInfo | null;
+}
+
+export interface LiveOptions {
+  from_start?: boolean;
+  max_duration?: number;
+  stop_at?: number;
+  wait_for_start?: boolean;
 }
 
 export interface DownloadItem {
@@ -54,6 +281,45 @@ export interface DownloadRequest {
   audio_only: boolean;
   download_subs?: boolean;
   sub_lang?: string;
+  live?: LiveOptions;
+  metadata?: MetadataOptions;
+}
+
+// A download queued in the backend's download manager
+export interface Job {
+  id: string;
+  request: DownloadRequest;
+  status: "queued" | DownloadProgress["status"];
+  progress: number;
+  error: string | null;
+  created_at: number;
+  finished_at: number | null;
+  files: OutputFile[];
+}
+
+export interface MetadataOptions {
+  write_info_json?: boolean;
+  write_description?: boolean;
+  write_comments?: boolean;
+  max_comments?: number;
+  comment_sort?: "top" | "new";
+}
+
+export interface SubtitleRequest {
+  url: string;
+  output_path: string;
+  languages: string[];
+  auto_captions?: boolean;
+  format: "srt" | "vtt" | "ass" | "txt";
+}
+
+export interface ThumbnailRequest {
+  url: string;
+  output_path: string;
+  thumbnail_id?: string;
+  format: string;
+  max_size?: number;
+  square?: boolean;
 }
 
 export interface PlaylistEntry {
@@ -81,3 +347,19 @@ export interface AppSettings {
   animatedBackground: boolean;
 }
 
+
+export interface DetectedUrl {
+  url: string;
+  site: string;
+}
+
+export type DeepLinkAction =
+  | { action: "download"; url: string; preset: string | null }
+  | { action: "convert"; path: string; format: string };
+
+// A jara:// link waiting for the user; action is null when it was refused
+export interface DeepLinkRequest {
+  link: string;
+  action: DeepLinkAction | null;
+  error: string | null;
+}